@@ -1,6 +1,8 @@
-use super::Header;
+#[allow(deprecated)]
+use super::{split_list, Header};
 use crate::{HeaderName, HeaderValue, Value};
 use core::{convert::TryFrom, ops::BitOr};
+use std::fmt;
 use std::time::{Duration, Instant};
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -29,6 +31,7 @@ impl Age {
     }
 }
 
+#[allow(deprecated)]
 impl Header for Age {
     fn get_headername() -> HeaderName {
         crate::header::AGE
@@ -39,7 +42,14 @@ impl Header for Age {
     }
 }
 
+impl fmt::Display for Age {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.value.as_secs())
+    }
+}
+
 #[doc(hidden)]
+#[derive(Debug)]
 pub struct InvalidAgeValue;
 
 impl TryFrom<HeaderValue> for Age {
@@ -69,7 +79,9 @@ impl TryFrom<Value> for Age {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+typed_header!(Age, AGE);
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct CacheFlags(u16);
 
 impl CacheFlags {
@@ -104,7 +116,7 @@ impl BitOr for CacheFlags {
     }
 }
 
-#[derive(PartialEq, Eq, Clone, Copy)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub struct CacheControl {
     bitflags: CacheFlags,
     max_age: Option<Duration>,
@@ -189,6 +201,7 @@ impl CacheControl {
     }
 }
 
+#[allow(deprecated)]
 impl Header for CacheControl {
     fn get_headername() -> HeaderName {
         crate::header::CACHE_CONTROL
@@ -235,3 +248,166 @@ impl Header for CacheControl {
         )
     }
 }
+
+impl Default for CacheControl {
+    fn default() -> Self {
+        CacheControl::new()
+    }
+}
+
+impl fmt::Display for CacheControl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut first = true;
+        let mut sep = |f: &mut fmt::Formatter<'_>| {
+            if first {
+                first = false;
+                Ok(())
+            } else {
+                f.write_str(", ")
+            }
+        };
+        for (n, flag) in FLAGS.iter().enumerate() {
+            if self.has_flag(CacheFlags(1 << n)) {
+                sep(f)?;
+                f.write_str(flag)?;
+            }
+        }
+        let durations = [
+            ("max-age", self.max_age),
+            ("s-maxage", self.s_maxage),
+            ("stale-while-revalidate", self.stale_while_revalidate),
+            ("stale-if-error", self.stale_if_error),
+            ("max-stale", self.max_stale),
+            ("min-fresh", self.min_fresh),
+        ];
+        for (name, value) in durations.iter() {
+            if let Some(value) = value {
+                sep(f)?;
+                write!(f, "{}={}", name, value.as_secs())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidCacheControl;
+
+impl TryFrom<&str> for CacheControl {
+    type Error = InvalidCacheControl;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut cc = CacheControl::new();
+        for directive in split_list(value) {
+            let (name, arg) = match directive.find('=') {
+                Some(idx) => (directive[..idx].trim(), Some(directive[idx + 1..].trim())),
+                None => (directive, None),
+            };
+            if name.is_empty() {
+                return Err(InvalidCacheControl);
+            }
+            let name = name.to_ascii_lowercase();
+
+            if let Some(n) = FLAGS.iter().position(|flag| *flag == name) {
+                // field names of `private` and `no-cache` are not supported
+                cc.bitflags.0 |= 1 << n;
+                continue;
+            }
+
+            let field = match name.as_str() {
+                "max-age" => &mut cc.max_age,
+                "s-maxage" => &mut cc.s_maxage,
+                "stale-while-revalidate" => &mut cc.stale_while_revalidate,
+                "stale-if-error" => &mut cc.stale_if_error,
+                "max-stale" => &mut cc.max_stale,
+                "min-fresh" => &mut cc.min_fresh,
+                // unknown directives must be ignored
+                _ => continue,
+            };
+            let secs = arg
+                .map(|arg| arg.trim_matches('"'))
+                .ok_or(InvalidCacheControl)?
+                .parse::<u64>()
+                .map_err(|_| InvalidCacheControl)?;
+            *field = Some(Duration::from_secs(secs));
+        }
+        Ok(cc)
+    }
+}
+
+impl TryFrom<HeaderValue> for CacheControl {
+    type Error = InvalidCacheControl;
+    fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+        value.to_str().map_err(|_| InvalidCacheControl)?.try_into()
+    }
+}
+
+impl TryFrom<Value> for CacheControl {
+    type Error = InvalidCacheControl;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        let mut cc = CacheControl::new();
+        for h in value {
+            let item = CacheControl::try_from(h)?;
+            cc.bitflags.0 |= item.bitflags.0;
+            cc.max_age = item.max_age.or(cc.max_age);
+            cc.s_maxage = item.s_maxage.or(cc.s_maxage);
+            cc.stale_while_revalidate =
+                item.stale_while_revalidate.or(cc.stale_while_revalidate);
+            cc.stale_if_error = item.stale_if_error.or(cc.stale_if_error);
+            cc.max_stale = item.max_stale.or(cc.max_stale);
+            cc.min_fresh = item.min_fresh.or(cc.min_fresh);
+        }
+        Ok(cc)
+    }
+}
+
+typed_header!(CacheControl, CACHE_CONTROL, list);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::TypedHeader;
+
+    #[test]
+    fn test_age() {
+        let age = Age::decode([HeaderValue::from_static(" 120 ")].iter()).unwrap();
+        assert_eq!(age.get_duration(), Duration::from_secs(120));
+        assert_eq!(age.encode().unwrap(), "120");
+        assert_eq!(Age::name(), crate::header::AGE);
+
+        assert!(Age::decode([HeaderValue::from_static("-1")].iter()).is_err());
+        assert!(Age::decode([].iter()).is_err());
+        let values = [HeaderValue::from_static("1"), HeaderValue::from_static("2")];
+        assert!(Age::decode(values.iter()).is_err());
+    }
+
+    #[test]
+    fn test_cache_control() {
+        let cc = CacheControl::try_from(
+            "Public, max-age=3600, s-maxage=\"60\", private=\"a, b\", ext=1",
+        )
+        .unwrap();
+        assert!(cc.has_flag(CacheFlags::PUBLIC | CacheFlags::PRIVATE));
+        assert!(!cc.has_flag(CacheFlags::NO_STORE));
+        assert_eq!(cc.get_max_age(), Some(Duration::from_secs(3600)));
+        assert_eq!(cc.get_s_maxage(), Some(Duration::from_secs(60)));
+        assert_eq!(cc.to_string(), "private, public, max-age=3600, s-maxage=60");
+        assert!(CacheControl::try_from("max-age").is_err());
+        assert!(CacheControl::try_from("max-age=abc").is_err());
+        assert!(CacheControl::try_from("=1").is_err());
+
+        let values = [
+            HeaderValue::from_static("no-cache"),
+            HeaderValue::from_static("no-store, min-fresh=10"),
+        ];
+        let cc = CacheControl::decode(values.iter()).unwrap();
+        assert_eq!(
+            cc,
+            CacheControl::new()
+                .set_flag(CacheFlags::NO_CACHE | CacheFlags::NO_STORE)
+                .set_min_fresh(Duration::from_secs(10))
+        );
+        assert_eq!(cc.encode().unwrap(), "no-cache, no-store, min-fresh=10");
+        assert!(CacheControl::decode([].iter()).is_err());
+    }
+}
@@ -0,0 +1,454 @@
+use crate::{HeaderValue, Value};
+use core::convert::{TryFrom, TryInto};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{fmt, str};
+
+/// Entity tag, value of `ETag`, `If-Match` and `If-None-Match` headers
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntityTag {
+    weak: bool,
+    tag: String,
+}
+
+impl EntityTag {
+    /// Create new entity tag.
+    ///
+    /// Panics if tag contains invalid characters.
+    pub fn new<T: Into<String>>(weak: bool, tag: T) -> Self {
+        let tag = tag.into();
+        assert!(
+            is_valid_tag(tag.as_bytes()),
+            "Invalid entity tag: {:?}",
+            tag
+        );
+        EntityTag { weak, tag }
+    }
+
+    /// Create new strong entity tag.
+    pub fn strong<T: Into<String>>(tag: T) -> Self {
+        EntityTag::new(false, tag)
+    }
+
+    /// Create new weak entity tag.
+    pub fn weak<T: Into<String>>(tag: T) -> Self {
+        EntityTag::new(true, tag)
+    }
+
+    /// Check if entity tag is weak.
+    pub fn is_weak(&self) -> bool {
+        self.weak
+    }
+
+    /// Opaque tag, without quotes and weakness indicator.
+    pub fn tag(&self) -> &str {
+        &self.tag
+    }
+
+    /// Strong comparison, both tags must be strong and equal.
+    pub fn strong_eq(&self, other: &EntityTag) -> bool {
+        !self.weak && !other.weak && self.tag == other.tag
+    }
+
+    /// Weak comparison, opaque tags must be equal.
+    pub fn weak_eq(&self, other: &EntityTag) -> bool {
+        self.tag == other.tag
+    }
+}
+
+fn is_valid_tag(tag: &[u8]) -> bool {
+    tag.iter().all(|c| *c == b'!' || (*c >= b'#' && *c != 0x7f))
+}
+
+impl fmt::Display for EntityTag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.weak {
+            write!(f, "W/\"{}\"", self.tag)
+        } else {
+            write!(f, "\"{}\"", self.tag)
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidEntityTag;
+
+/// Parse comma separated list of entity tags
+fn parse_tags(value: &str, tags: &mut Vec<EntityTag>) -> Result<(), InvalidEntityTag> {
+    let mut rest = value.as_bytes();
+    loop {
+        while let [b' ' | b'\t' | b',', tail @ ..] = rest {
+            rest = tail;
+        }
+        if rest.is_empty() {
+            return Ok(());
+        }
+
+        let weak = rest.starts_with(b"W/");
+        if weak {
+            rest = &rest[2..];
+        }
+        if rest.first() != Some(&b'"') {
+            return Err(InvalidEntityTag);
+        }
+        let end = rest[1..]
+            .iter()
+            .position(|c| *c == b'"')
+            .ok_or(InvalidEntityTag)?;
+        let tag = &rest[1..end + 1];
+        if !is_valid_tag(tag) {
+            return Err(InvalidEntityTag);
+        }
+        tags.push(EntityTag {
+            weak,
+            tag: str::from_utf8(tag)
+                .map_err(|_| InvalidEntityTag)?
+                .to_string(),
+        });
+        rest = &rest[end + 2..];
+
+        while let [b' ' | b'\t', tail @ ..] = rest {
+            rest = tail;
+        }
+        match rest {
+            [] | [b',', ..] => (),
+            _ => return Err(InvalidEntityTag),
+        }
+    }
+}
+
+impl TryFrom<&str> for EntityTag {
+    type Error = InvalidEntityTag;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut tags = Vec::new();
+        parse_tags(value, &mut tags)?;
+        if tags.len() == 1 {
+            Ok(tags.pop().unwrap())
+        } else {
+            Err(InvalidEntityTag)
+        }
+    }
+}
+
+impl TryFrom<HeaderValue> for EntityTag {
+    type Error = InvalidEntityTag;
+    fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+        value.to_str().map_err(|_| InvalidEntityTag)?.try_into()
+    }
+}
+
+/// `ETag` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ETag(pub EntityTag);
+
+impl fmt::Display for ETag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(&self.0, f)
+    }
+}
+
+impl TryFrom<HeaderValue> for ETag {
+    type Error = InvalidEntityTag;
+    fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+        Ok(ETag(value.try_into()?))
+    }
+}
+
+impl TryFrom<Value> for ETag {
+    type Error = InvalidEntityTag;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::One(h) => h.try_into(),
+            _ => Err(InvalidEntityTag),
+        }
+    }
+}
+
+typed_header!(ETag, ETAG);
+
+macro_rules! tags_header {
+    ($(#[$attr:meta])* $name:ident, $header:ident, $cmp:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub enum $name {
+            /// `*` value, matches any current representation
+            Any,
+            /// List of entity tags
+            Items(Vec<EntityTag>),
+        }
+
+        impl $name {
+            /// Check if header matches current representation's entity tag.
+            pub fn is_match(&self, etag: Option<&EntityTag>) -> bool {
+                match self {
+                    $name::Any => etag.is_some(),
+                    $name::Items(tags) => etag
+                        .map(|etag| tags.iter().any(|tag| tag.$cmp(etag)))
+                        .unwrap_or(false),
+                }
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                match self {
+                    $name::Any => f.write_str("*"),
+                    $name::Items(tags) => {
+                        for (idx, tag) in tags.iter().enumerate() {
+                            if idx != 0 {
+                                f.write_str(", ")?;
+                            }
+                            fmt::Display::fmt(tag, f)?;
+                        }
+                        Ok(())
+                    }
+                }
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = InvalidEntityTag;
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                if value.trim() == "*" {
+                    Ok($name::Any)
+                } else {
+                    let mut tags = Vec::new();
+                    parse_tags(value, &mut tags)?;
+                    Ok($name::Items(tags))
+                }
+            }
+        }
+
+        impl TryFrom<HeaderValue> for $name {
+            type Error = InvalidEntityTag;
+            fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+                value.to_str().map_err(|_| InvalidEntityTag)?.try_into()
+            }
+        }
+
+        impl TryFrom<Value> for $name {
+            type Error = InvalidEntityTag;
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                let mut tags = Vec::new();
+                for h in value {
+                    match h.try_into()? {
+                        $name::Any => return Ok($name::Any),
+                        $name::Items(items) => tags.extend(items),
+                    }
+                }
+                Ok($name::Items(tags))
+            }
+        }
+
+        typed_header!($name, $header, list);
+    };
+}
+
+tags_header!(
+    /// `If-Match` header, uses strong comparison
+    IfMatch,
+    IF_MATCH,
+    strong_eq
+);
+
+tags_header!(
+    /// `If-None-Match` header, uses weak comparison
+    IfNoneMatch,
+    IF_NONE_MATCH,
+    weak_eq
+);
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidHttpDate;
+
+macro_rules! date_header {
+    ($(#[$attr:meta])* $name:ident, $header:ident) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub struct $name(pub SystemTime);
+
+        impl $name {
+            /// Seconds since unix epoch, http dates have one second precision
+            pub fn timestamp(&self) -> u64 {
+                timestamp(self.0)
+            }
+        }
+
+        impl From<SystemTime> for $name {
+            fn from(time: SystemTime) -> Self {
+                $name(time)
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "{}", httpdate::HttpDate::from(self.0))
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = InvalidHttpDate;
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                httpdate::parse_http_date(value.trim())
+                    .map($name)
+                    .map_err(|_| InvalidHttpDate)
+            }
+        }
+
+        impl TryFrom<HeaderValue> for $name {
+            type Error = InvalidHttpDate;
+            fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+                value.to_str().map_err(|_| InvalidHttpDate)?.try_into()
+            }
+        }
+
+        impl TryFrom<Value> for $name {
+            type Error = InvalidHttpDate;
+            fn try_from(value: Value) -> Result<Self, Self::Error> {
+                match value {
+                    Value::One(h) => h.try_into(),
+                    _ => Err(InvalidHttpDate),
+                }
+            }
+        }
+
+        typed_header!($name, $header);
+    };
+}
+
+fn timestamp(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+date_header!(
+    /// `Last-Modified` header
+    LastModified,
+    LAST_MODIFIED
+);
+
+date_header!(
+    /// `If-Modified-Since` header
+    IfModifiedSince,
+    IF_MODIFIED_SINCE
+);
+
+date_header!(
+    /// `If-Unmodified-Since` header
+    IfUnmodifiedSince,
+    IF_UNMODIFIED_SINCE
+);
+
+impl IfModifiedSince {
+    /// Check if representation has been modified since the header's date.
+    pub fn is_modified(&self, last_modified: SystemTime) -> bool {
+        timestamp(last_modified) > self.timestamp()
+    }
+}
+
+impl IfUnmodifiedSince {
+    /// Check if representation has been modified since the header's date.
+    pub fn is_modified(&self, last_modified: SystemTime) -> bool {
+        timestamp(last_modified) > self.timestamp()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::TypedHeader;
+    use std::time::Duration;
+
+    #[test]
+    fn test_entity_tag() {
+        let tag = EntityTag::try_from("\"xyzzy\"").unwrap();
+        assert!(!tag.is_weak());
+        assert_eq!(tag.tag(), "xyzzy");
+        assert_eq!(tag.to_string(), "\"xyzzy\"");
+
+        let weak = EntityTag::try_from("W/\"xyzzy\"").unwrap();
+        assert!(weak.is_weak());
+        assert_eq!(weak.to_string(), "W/\"xyzzy\"");
+        assert!(!tag.strong_eq(&weak));
+        assert!(tag.weak_eq(&weak));
+        assert!(tag.strong_eq(&EntityTag::strong("xyzzy")));
+
+        assert!(EntityTag::try_from("xyzzy").is_err());
+        assert!(EntityTag::try_from("\"xyzzy").is_err());
+        assert!(EntityTag::try_from("\"a\", \"b\"").is_err());
+        assert!(EntityTag::try_from("\"a\" b").is_err());
+
+        let value = ETag(EntityTag::weak("v1")).encode().unwrap();
+        assert_eq!(ETag::try_from(value).unwrap(), ETag(EntityTag::weak("v1")));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_entity_tag_invalid() {
+        EntityTag::strong("a\"b");
+    }
+
+    #[test]
+    fn test_if_match() {
+        let value = IfMatch::try_from("\"a,b\", W/\"c\",\"d\"").unwrap();
+        assert_eq!(
+            value,
+            IfMatch::Items(vec![
+                EntityTag::strong("a,b"),
+                EntityTag::weak("c"),
+                EntityTag::strong("d")
+            ])
+        );
+        assert_eq!(value.to_string(), "\"a,b\", W/\"c\", \"d\"");
+        assert!(value.is_match(Some(&EntityTag::strong("d"))));
+        assert!(!value.is_match(Some(&EntityTag::strong("c"))));
+        assert!(!value.is_match(None));
+
+        let value = IfMatch::try_from(HeaderValue::from_static("*")).unwrap();
+        assert_eq!(value, IfMatch::Any);
+        assert!(value.is_match(Some(&EntityTag::weak("a"))));
+        assert!(!value.is_match(None));
+
+        let value: Value = vec![
+            HeaderValue::from_static("\"a\""),
+            HeaderValue::from_static("\"b\""),
+        ]
+        .into_iter()
+        .collect();
+        assert_eq!(
+            IfNoneMatch::try_from(value).unwrap(),
+            IfNoneMatch::Items(vec![EntityTag::strong("a"), EntityTag::strong("b")])
+        );
+    }
+
+    #[test]
+    fn test_if_none_match() {
+        let value = IfNoneMatch::try_from("W/\"a\"").unwrap();
+        assert!(value.is_match(Some(&EntityTag::strong("a"))));
+        assert!(value.is_match(Some(&EntityTag::weak("a"))));
+        assert!(!value.is_match(Some(&EntityTag::weak("b"))));
+        assert!(IfNoneMatch::try_from("a").is_err());
+    }
+
+    #[test]
+    fn test_dates() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+
+        let value = LastModified::try_from("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(value, LastModified(date));
+        assert_eq!(value.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert!(LastModified::try_from("yesterday").is_err());
+
+        let value = IfModifiedSince(date).encode().unwrap();
+        let since = IfModifiedSince::try_from(value).unwrap();
+        assert!(!since.is_modified(date));
+        assert!(!since.is_modified(date + Duration::from_millis(500)));
+        assert!(since.is_modified(date + Duration::from_secs(1)));
+
+        let since = IfUnmodifiedSince::from(date);
+        assert!(!since.is_modified(date - Duration::from_secs(1)));
+        assert!(since.is_modified(date + Duration::from_secs(1)));
+    }
+}
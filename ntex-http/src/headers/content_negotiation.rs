@@ -1,4 +1,8 @@
-use std::fmt;
+use super::split_list;
+use crate::HeaderValue;
+use core::convert::{TryFrom, TryInto};
+use mime::Mime;
+use std::{fmt, str::FromStr};
 
 const MQ: u16 = 1000;
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -24,6 +28,14 @@ impl Quality {
         }
         Some(Quality::Value((f * 1000.0) as u16))
     }
+
+    /// Quality value in thousandths, `1000` is most preferred
+    pub fn value(&self) -> u16 {
+        match self {
+            Quality::Value(q) => *q,
+            Quality::Default => MQ,
+        }
+    }
 }
 
 impl Default for Quality {
@@ -48,3 +60,494 @@ impl fmt::Display for Quality {
         }
     }
 }
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidQuality;
+
+/// Parse `qvalue`, up to three digits after the decimal point
+impl TryFrom<&str> for Quality {
+    type Error = InvalidQuality;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim().as_bytes();
+        let (int, frac) = match value.iter().position(|c| *c == b'.') {
+            Some(idx) => (&value[..idx], &value[idx + 1..]),
+            None => (value, &b""[..]),
+        };
+        if frac.len() > 3 || !frac.iter().all(|c| c.is_ascii_digit()) {
+            return Err(InvalidQuality);
+        }
+        let mut q = match int {
+            b"0" => 0,
+            b"1" => MQ,
+            _ => return Err(InvalidQuality),
+        };
+        for (idx, c) in frac.iter().enumerate() {
+            q += u16::from(c - b'0') * [100, 10, 1][idx];
+        }
+        if q > MQ {
+            Err(InvalidQuality)
+        } else {
+            Ok(Quality::Value(q))
+        }
+    }
+}
+
+/// Item of quality list, value with its quality
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QualityItem<T> {
+    pub item: T,
+    pub quality: Quality,
+}
+
+impl<T> QualityItem<T> {
+    pub fn new(item: T, quality: Quality) -> Self {
+        QualityItem { item, quality }
+    }
+
+    /// Most preferred item
+    pub fn max(item: T) -> Self {
+        QualityItem::new(item, Quality::MP)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for QualityItem<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.quality {
+            Quality::Default => fmt::Display::fmt(&self.item, f),
+            ref q => write!(f, "{};{}", self.item, q),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidQualityItem;
+
+/// Parse `item;q=0.5`, parameters after the quality are ignored
+impl<T: FromStr> TryFrom<&str> for QualityItem<T> {
+    type Error = InvalidQualityItem;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let mut end = value.len();
+        let mut quality = Quality::Default;
+        let mut offset = 0;
+        for param in value.split(';') {
+            let start = offset;
+            offset += param.len() + 1;
+            if start == 0 {
+                continue;
+            }
+            let mut parts = param.splitn(2, '=');
+            let name = parts.next().unwrap_or("").trim();
+            if name.eq_ignore_ascii_case("q") {
+                let q = parts.next().ok_or(InvalidQualityItem)?;
+                quality = Quality::try_from(q).map_err(|_| InvalidQualityItem)?;
+                end = start - 1;
+                break;
+            }
+        }
+
+        let item = value[..end].trim();
+        if item.is_empty() {
+            return Err(InvalidQualityItem);
+        }
+        Ok(QualityItem {
+            item: item.parse().map_err(|_| InvalidQualityItem)?,
+            quality,
+        })
+    }
+}
+
+/// Header with comma separated list of quality items
+macro_rules! quality_list_header {
+    ($(#[$attr:meta])* $name:ident, $header:ident, $item:ty) => {
+        $(#[$attr])*
+        #[derive(Debug, Clone, PartialEq, Eq)]
+        pub struct $name(pub Vec<QualityItem<$item>>);
+
+        impl $name {
+            /// Acceptable items ordered by quality, most preferred first.
+            ///
+            /// Items with zero quality are not acceptable and are skipped.
+            pub fn ranked(&self) -> Vec<&$item> {
+                let mut items: Vec<_> =
+                    self.0.iter().filter(|item| item.quality.value() > 0).collect();
+                items.sort_by(|a, b| b.quality.value().cmp(&a.quality.value()));
+                items.into_iter().map(|item| &item.item).collect()
+            }
+
+            /// Most preferred item
+            pub fn preferred(&self) -> Option<&$item> {
+                self.ranked().into_iter().next()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                for (idx, item) in self.0.iter().enumerate() {
+                    if idx != 0 {
+                        f.write_str(", ")?;
+                    }
+                    fmt::Display::fmt(item, f)?;
+                }
+                Ok(())
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = InvalidQualityItem;
+            fn try_from(value: &str) -> Result<Self, Self::Error> {
+                split_list(value)
+                    .map(QualityItem::try_from)
+                    .collect::<Result<_, _>>()
+                    .map($name)
+            }
+        }
+
+        impl TryFrom<HeaderValue> for $name {
+            type Error = InvalidQualityItem;
+            fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+                value.to_str().map_err(|_| InvalidQualityItem)?.try_into()
+            }
+        }
+
+        typed_header!($name, $header, list);
+    };
+}
+
+quality_list_header!(
+    /// `Accept` header, list of acceptable media ranges
+    Accept,
+    ACCEPT,
+    Mime
+);
+
+quality_list_header!(
+    /// `Accept-Charset` header
+    AcceptCharset,
+    ACCEPT_CHARSET,
+    String
+);
+
+quality_list_header!(
+    /// `Accept-Encoding` header
+    AcceptEncoding,
+    ACCEPT_ENCODING,
+    String
+);
+
+quality_list_header!(
+    /// `Accept-Language` header, list of language ranges
+    AcceptLanguage,
+    ACCEPT_LANGUAGE,
+    String
+);
+
+impl Accept {
+    /// Quality of media type.
+    ///
+    /// Quality of the most specific matching media range is used,
+    /// `text/html;level=1` takes precedence over `text/html`, `text/*`
+    /// and `*/*`.
+    pub fn quality(&self, mime: &Mime) -> Quality {
+        match_quality(&self.0, |range| {
+            if range.type_() == mime::STAR {
+                return Some(0);
+            }
+            if range.type_() != mime.type_() {
+                return None;
+            }
+            if range.subtype() == mime::STAR {
+                return Some(1);
+            }
+            if range.subtype() != mime.subtype() {
+                return None;
+            }
+            let mut specificity = 2;
+            for (name, value) in range.params() {
+                if mime.get_param(name) != Some(value) {
+                    return None;
+                }
+                specificity += 1;
+            }
+            Some(specificity)
+        })
+    }
+
+    /// Select most acceptable media type from available ones.
+    ///
+    /// Media types with equal quality are selected in order of `available`.
+    pub fn negotiate<'a, I>(&self, available: I) -> Option<&'a Mime>
+    where
+        I: IntoIterator<Item = &'a Mime>,
+    {
+        negotiate(available, |mime| self.quality(mime))
+    }
+}
+
+impl AcceptCharset {
+    /// Quality of charset
+    pub fn quality(&self, charset: &str) -> Quality {
+        match_quality(&self.0, |item| token_match(item, charset))
+    }
+
+    /// Select most acceptable charset from available ones
+    pub fn negotiate<'a, I>(&self, available: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        negotiate(available, |charset| self.quality(charset))
+    }
+}
+
+impl AcceptEncoding {
+    /// Quality of content coding.
+    ///
+    /// `identity` is acceptable with the least quality unless it is
+    /// excluded explicitly or by `*;q=0`.
+    pub fn quality(&self, encoding: &str) -> Quality {
+        let matched = self
+            .0
+            .iter()
+            .any(|item| token_match(&item.item, encoding).is_some());
+        if !matched && encoding.eq_ignore_ascii_case("identity") {
+            Quality::LP
+        } else {
+            match_quality(&self.0, |item| token_match(item, encoding))
+        }
+    }
+
+    /// Select most acceptable content coding from available ones
+    pub fn negotiate<'a, I>(&self, available: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        negotiate(available, |encoding| self.quality(encoding))
+    }
+}
+
+impl AcceptLanguage {
+    /// Quality of language tag.
+    ///
+    /// Language range matches tag if it is equal to the tag or to its
+    /// prefix followed by `-`, `en` matches `en-US`. Longest matching
+    /// range takes precedence.
+    pub fn quality(&self, tag: &str) -> Quality {
+        match_quality(&self.0, |range| {
+            if range == "*" {
+                return Some(0);
+            }
+            let (range, tag) = (range.as_bytes(), tag.as_bytes());
+            let prefix = tag.len() >= range.len()
+                && tag[..range.len()].eq_ignore_ascii_case(range)
+                && (tag.len() == range.len() || tag[range.len()] == b'-');
+            if prefix {
+                Some(range.len())
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Select most acceptable language from available ones
+    pub fn negotiate<'a, I>(&self, available: I) -> Option<&'a str>
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        negotiate(available, |tag| self.quality(tag))
+    }
+}
+
+/// Match token against list item, `*` matches any token
+fn token_match(item: &str, token: &str) -> Option<usize> {
+    if item == "*" {
+        Some(0)
+    } else if item.eq_ignore_ascii_case(token) {
+        Some(1)
+    } else {
+        None
+    }
+}
+
+/// Quality of the most specific matching item, `NA` if nothing matches
+fn match_quality<T, F>(items: &[QualityItem<T>], specificity: F) -> Quality
+where
+    F: Fn(&T) -> Option<usize>,
+{
+    let mut result: Option<(usize, &Quality)> = None;
+    for item in items {
+        if let Some(s) = specificity(&item.item) {
+            if result.map(|(rs, _)| s > rs).unwrap_or(true) {
+                result = Some((s, &item.quality));
+            }
+        }
+    }
+    result.map(|(_, q)| q.clone()).unwrap_or(Quality::NA)
+}
+
+/// Select available value with highest non-zero quality
+fn negotiate<'a, T, I, F>(available: I, quality: F) -> Option<&'a T>
+where
+    T: ?Sized + 'a,
+    I: IntoIterator<Item = &'a T>,
+    F: Fn(&T) -> Quality,
+{
+    let mut result = None;
+    let mut max = 0;
+    for item in available {
+        let q = quality(item).value();
+        if q > max {
+            max = q;
+            result = Some(item);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::TypedHeader;
+
+    #[test]
+    fn test_quality() {
+        assert_eq!(Quality::try_from("1").unwrap(), Quality::Value(1000));
+        assert_eq!(Quality::try_from("1.000").unwrap(), Quality::Value(1000));
+        assert_eq!(Quality::try_from("0").unwrap(), Quality::NA);
+        assert_eq!(Quality::try_from("0.5").unwrap(), Quality::Value(500));
+        assert_eq!(Quality::try_from("0.05").unwrap(), Quality::Value(50));
+        assert_eq!(Quality::try_from("0.001").unwrap(), Quality::LP);
+        assert!(Quality::try_from("1.001").is_err());
+        assert!(Quality::try_from("0.0001").is_err());
+        assert!(Quality::try_from("2").is_err());
+        assert!(Quality::try_from("").is_err());
+        assert!(Quality::try_from("0.a").is_err());
+        assert_eq!(Quality::Default.value(), 1000);
+    }
+
+    #[test]
+    fn test_quality_item() {
+        let item = QualityItem::<String>::try_from("utf-8 ; Q=0.5; ext=1").unwrap();
+        assert_eq!(
+            item,
+            QualityItem::new("utf-8".to_string(), Quality::Value(500))
+        );
+        assert_eq!(item.to_string(), "utf-8;q=0.5");
+
+        let item = QualityItem::<String>::try_from("text/html;level=1").unwrap();
+        assert_eq!(item, QualityItem::max("text/html;level=1".to_string()));
+        assert_eq!(item.to_string(), "text/html;level=1");
+
+        assert!(QualityItem::<String>::try_from(";q=0.5").is_err());
+        assert!(QualityItem::<String>::try_from("a;q=5").is_err());
+        assert!(QualityItem::<String>::try_from("a;q").is_err());
+        assert!(QualityItem::<u32>::try_from("a").is_err());
+    }
+
+    #[test]
+    fn test_quality_list() {
+        let values = [
+            HeaderValue::from_static("iso-8859-5;q=0.5, unicode-1-1;q=0.8"),
+            HeaderValue::from_static("utf-8, latin1;q=0"),
+        ];
+        let accept = AcceptCharset::decode(values.iter()).unwrap();
+        assert_eq!(accept.0.len(), 4);
+        assert_eq!(accept.ranked(), vec!["utf-8", "unicode-1-1", "iso-8859-5"]);
+        assert_eq!(accept.preferred().unwrap(), "utf-8");
+        assert_eq!(
+            accept.encode().unwrap(),
+            "iso-8859-5;q=0.5, unicode-1-1;q=0.8, utf-8, latin1;q=0"
+        );
+        assert_eq!(AcceptCharset::name(), crate::header::ACCEPT_CHARSET);
+
+        assert!(AcceptCharset::decode([HeaderValue::from_static("a;q=x")].iter()).is_err());
+        assert!(AcceptCharset::decode([].iter()).is_err());
+        assert_eq!(AcceptCharset::try_from("").unwrap().preferred(), None);
+    }
+
+    #[test]
+    fn test_accept() {
+        let accept = Accept::try_from(
+            "text/*;q=0.3, text/html;q=0.7, text/html;level=1, text/html;level=2;q=0.4, */*;q=0.5",
+        )
+        .unwrap();
+        let quality = |mime: &str| accept.quality(&mime.parse().unwrap()).value();
+        assert_eq!(quality("text/html;level=1"), 1000);
+        assert_eq!(quality("text/html"), 700);
+        assert_eq!(quality("text/plain"), 300);
+        assert_eq!(quality("image/jpeg"), 500);
+        assert_eq!(quality("text/html;level=2"), 400);
+        assert_eq!(quality("text/html;level=3"), 700);
+        assert_eq!(accept.preferred().unwrap(), &"text/html;level=1");
+
+        let accept =
+            Accept::try_from("application/json;q=0.5, application/*;q=0.8").unwrap();
+        let available = [
+            mime::APPLICATION_JSON,
+            mime::APPLICATION_MSGPACK,
+            mime::TEXT_PLAIN,
+        ];
+        assert_eq!(
+            accept.negotiate(available.iter()),
+            Some(&mime::APPLICATION_MSGPACK)
+        );
+        let accept = Accept::try_from("*/*").unwrap();
+        assert_eq!(
+            accept.negotiate(available.iter()),
+            Some(&mime::APPLICATION_JSON)
+        );
+        let accept = Accept::try_from("image/*, application/json;q=0").unwrap();
+        assert_eq!(accept.negotiate(available[..2].iter()), None);
+
+        assert!(Accept::try_from("text").is_err());
+        assert_eq!(Accept::name(), crate::header::ACCEPT);
+    }
+
+    #[test]
+    fn test_accept_encoding() {
+        let accept = AcceptEncoding::try_from("gzip;q=0.5, br").unwrap();
+        assert_eq!(accept.negotiate(vec!["gzip", "br"]), Some("br"));
+        assert_eq!(accept.negotiate(vec!["identity", "gzip"]), Some("gzip"));
+        assert_eq!(
+            accept.negotiate(vec!["deflate", "identity"]),
+            Some("identity")
+        );
+        assert_eq!(accept.quality("deflate"), Quality::NA);
+
+        let accept = AcceptEncoding::try_from("GZIP, *;q=0").unwrap();
+        assert_eq!(accept.negotiate(vec!["identity", "gzip"]), Some("gzip"));
+        assert_eq!(accept.negotiate(vec!["identity"]), None);
+
+        let accept = AcceptEncoding::try_from("").unwrap();
+        assert_eq!(accept.negotiate(vec!["gzip", "identity"]), Some("identity"));
+    }
+
+    #[test]
+    fn test_accept_language() {
+        let accept =
+            AcceptLanguage::try_from("da, en-gb;q=0.8, en;q=0.7, *;q=0.1").unwrap();
+        assert_eq!(accept.quality("da").value(), 1000);
+        assert_eq!(accept.quality("en-GB").value(), 800);
+        assert_eq!(accept.quality("en-US").value(), 700);
+        assert_eq!(accept.quality("en").value(), 700);
+        assert_eq!(accept.quality("eng").value(), 100);
+        assert_eq!(
+            accept.negotiate(vec!["fr", "en-US", "en-GB"]),
+            Some("en-GB")
+        );
+
+        let accept = AcceptLanguage::try_from("de-DE").unwrap();
+        assert_eq!(accept.quality("de").value(), 0);
+        assert_eq!(accept.negotiate(vec!["de", "fr"]), None);
+    }
+
+    #[test]
+    fn test_accept_charset() {
+        let accept = AcceptCharset::try_from("iso-8859-5, utf-8;q=0.9").unwrap();
+        assert_eq!(
+            accept.negotiate(vec!["UTF-8", "ISO-8859-5"]),
+            Some("ISO-8859-5")
+        );
+        assert_eq!(accept.negotiate(vec!["latin1"]), None);
+    }
+}
@@ -1,12 +1,175 @@
+use std::{error::Error, fmt};
+
+use crate::{HeaderName, HeaderValue, Value};
+
+/// Implement `TypedHeader` for header types
+macro_rules! typed_header {
+    // single value header
+    ($name:ident, $header:ident) => {
+        impl $crate::headers::TypedHeader for $name {
+            fn name() -> crate::HeaderName {
+                crate::header::$header
+            }
+
+            fn decode<'a, I>(mut values: I) -> Result<Self, $crate::headers::InvalidHeader>
+            where
+                I: Iterator<Item = &'a crate::HeaderValue>,
+            {
+                match (values.next(), values.next()) {
+                    (Some(value), None) => <$name as core::convert::TryFrom<
+                        crate::HeaderValue,
+                    >>::try_from(value.clone())
+                    .map_err(|_| $crate::headers::InvalidHeader),
+                    _ => Err($crate::headers::InvalidHeader),
+                }
+            }
+
+            fn encode(&self) -> Result<crate::HeaderValue, $crate::headers::InvalidHeader> {
+                crate::HeaderValue::from_str(&self.to_string())
+                    .map_err(|_| $crate::headers::InvalidHeader)
+            }
+        }
+    };
+    // comma separated list, multiple values are combined
+    ($name:ident, $header:ident, list) => {
+        impl $crate::headers::TypedHeader for $name {
+            fn name() -> crate::HeaderName {
+                crate::header::$header
+            }
+
+            fn decode<'a, I>(values: I) -> Result<Self, $crate::headers::InvalidHeader>
+            where
+                I: Iterator<Item = &'a crate::HeaderValue>,
+            {
+                let value = $crate::headers::join_values(values)?;
+                <$name as core::convert::TryFrom<&str>>::try_from(value.as_str())
+                    .map_err(|_| $crate::headers::InvalidHeader)
+            }
+
+            fn encode(&self) -> Result<crate::HeaderValue, $crate::headers::InvalidHeader> {
+                crate::HeaderValue::from_str(&self.to_string())
+                    .map_err(|_| $crate::headers::InvalidHeader)
+            }
+        }
+    };
+}
+
 mod caching;
+mod conditional;
 mod content_negotiation;
+mod ranges;
 
 pub use self::caching::{Age, CacheControl, CacheFlags};
-pub use self::content_negotiation::Quality;
-
-use crate::{HeaderName, Value};
+pub use self::conditional::{
+    ETag, EntityTag, IfMatch, IfModifiedSince, IfNoneMatch, IfUnmodifiedSince, LastModified,
+};
+pub use self::content_negotiation::{
+    Accept, AcceptCharset, AcceptEncoding, AcceptLanguage, Quality, QualityItem,
+};
+pub use self::ranges::{AcceptRanges, ByteRangeSpec, ContentRange, IfRange, Range};
 
+#[deprecated(since = "0.1.2", note = "Use `TypedHeader` trait")]
 pub trait Header {
     fn get_headername() -> HeaderName;
     fn build(self) -> (Value, Self);
 }
+
+/// Typed http header
+///
+/// Parsing of header values into typed representation and encoding
+/// of typed representation into header value.
+pub trait TypedHeader: Sized {
+    /// Header name
+    fn name() -> HeaderName;
+
+    /// Parse header from all values of the header, in order.
+    ///
+    /// Empty iterator means missing header and results in error.
+    fn decode<'a, I>(values: I) -> Result<Self, InvalidHeader>
+    where
+        I: Iterator<Item = &'a HeaderValue>;
+
+    /// Encode header value.
+    ///
+    /// Returns error if typed value contains characters that are not
+    /// allowed in header value.
+    fn encode(&self) -> Result<HeaderValue, InvalidHeader>;
+}
+
+/// Typed header parse error
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct InvalidHeader;
+
+impl fmt::Display for InvalidHeader {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Invalid header value")
+    }
+}
+
+impl Error for InvalidHeader {}
+
+/// Join header values to comma separated list
+fn join_values<'a, I>(values: I) -> Result<String, InvalidHeader>
+where
+    I: Iterator<Item = &'a HeaderValue>,
+{
+    let mut result = String::new();
+    let mut empty = true;
+    for value in values {
+        if !empty {
+            result.push_str(", ");
+        }
+        result.push_str(value.to_str().map_err(|_| InvalidHeader)?);
+        empty = false;
+    }
+    if empty {
+        Err(InvalidHeader)
+    } else {
+        Ok(result)
+    }
+}
+
+/// Split comma separated list, commas in quoted strings are ignored.
+///
+/// Items are trimmed, empty items are skipped.
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    let mut quoted = false;
+    let mut escaped = false;
+    value
+        .split(move |c| {
+            if escaped {
+                escaped = false;
+            } else if quoted && c == '\\' {
+                escaped = true;
+            } else if c == '"' {
+                quoted = !quoted;
+            } else if c == ',' && !quoted {
+                return true;
+            }
+            false
+        })
+        .map(|item| item.trim())
+        .filter(|item| !item.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_list() {
+        let items: Vec<_> = split_list(" a, b;q=0.5,, c=\"x, \\\"y\"  ,").collect();
+        assert_eq!(items, vec!["a", "b;q=0.5", "c=\"x, \\\"y\""]);
+        assert_eq!(split_list("").count(), 0);
+    }
+
+    #[test]
+    fn test_join_values() {
+        let values = [
+            HeaderValue::from_static("a"),
+            HeaderValue::from_static("b, c"),
+        ];
+        assert_eq!(join_values(values.iter()).unwrap(), "a, b, c");
+        assert!(join_values([].iter()).is_err());
+    }
+}
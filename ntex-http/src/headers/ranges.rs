@@ -0,0 +1,555 @@
+use crate::{HeaderValue, Value};
+use core::convert::{TryFrom, TryInto};
+use std::{fmt, time::SystemTime};
+
+/// Byte range specification of the `Range` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ByteRangeSpec {
+    /// `first-last` range, both positions are inclusive
+    FromTo(u64, u64),
+    /// `first-` range, up to the end of representation
+    From(u64),
+    /// `-suffix` range, last bytes of representation
+    Last(u64),
+}
+
+impl ByteRangeSpec {
+    /// Resolve range against representation of `size` bytes.
+    ///
+    /// Returns inclusive `(start, end)` positions or `None` if range
+    /// is not satisfiable.
+    pub fn to_satisfiable_range(&self, size: u64) -> Option<(u64, u64)> {
+        if size == 0 {
+            return None;
+        }
+        match *self {
+            ByteRangeSpec::FromTo(first, last) => {
+                if first < size {
+                    Some((first, last.min(size - 1)))
+                } else {
+                    None
+                }
+            }
+            ByteRangeSpec::From(first) => {
+                if first < size {
+                    Some((first, size - 1))
+                } else {
+                    None
+                }
+            }
+            ByteRangeSpec::Last(suffix) => {
+                if suffix > 0 {
+                    Some((size - suffix.min(size), size - 1))
+                } else {
+                    None
+                }
+            }
+        }
+    }
+}
+
+impl fmt::Display for ByteRangeSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            ByteRangeSpec::FromTo(first, last) => write!(f, "{}-{}", first, last),
+            ByteRangeSpec::From(first) => write!(f, "{}-", first),
+            ByteRangeSpec::Last(suffix) => write!(f, "-{}", suffix),
+        }
+    }
+}
+
+/// Maximum number of ranges accepted by `Range` header parser
+const MAX_RANGES: usize = 32;
+
+/// `Range` header, only `bytes` unit is supported
+///
+/// Header values with more than 32 ranges are rejected
+/// by the parser.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Range {
+    ranges: Vec<ByteRangeSpec>,
+}
+
+impl Range {
+    pub fn new(ranges: Vec<ByteRangeSpec>) -> Self {
+        Range { ranges }
+    }
+    /// `bytes=first-last` range
+    pub fn bytes(first: u64, last: u64) -> Self {
+        Range {
+            ranges: vec![ByteRangeSpec::FromTo(first, last)],
+        }
+    }
+    pub fn ranges(&self) -> &[ByteRangeSpec] {
+        &self.ranges
+    }
+    pub fn add_range(mut self, range: ByteRangeSpec) -> Self {
+        self.ranges.push(range);
+        self
+    }
+    /// Inclusive `(start, end)` positions of satisfiable ranges
+    pub fn satisfiable_ranges(&self, size: u64) -> Vec<(u64, u64)> {
+        self.ranges
+            .iter()
+            .filter_map(|r| r.to_satisfiable_range(size))
+            .collect()
+    }
+}
+
+impl fmt::Display for Range {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("bytes=")?;
+        for (idx, range) in self.ranges.iter().enumerate() {
+            if idx != 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", range)?;
+        }
+        Ok(())
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidRange;
+
+fn parse_u64(s: &str) -> Result<u64, InvalidRange> {
+    if s.is_empty() || !s.bytes().all(|b| b.is_ascii_digit()) {
+        return Err(InvalidRange);
+    }
+    s.parse::<u64>().map_err(|_| InvalidRange)
+}
+
+impl TryFrom<&str> for Range {
+    type Error = InvalidRange;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let (unit, specs) = value.trim().split_once('=').ok_or(InvalidRange)?;
+        if !unit.trim().eq_ignore_ascii_case("bytes") {
+            return Err(InvalidRange);
+        }
+
+        let mut ranges = Vec::new();
+        for spec in specs.split(',').map(|s| s.trim()).filter(|s| !s.is_empty()) {
+            let (first, last) = spec.split_once('-').ok_or(InvalidRange)?;
+            let (first, last) = (first.trim(), last.trim());
+            let range = if first.is_empty() {
+                ByteRangeSpec::Last(parse_u64(last)?)
+            } else if last.is_empty() {
+                ByteRangeSpec::From(parse_u64(first)?)
+            } else {
+                let (first, last) = (parse_u64(first)?, parse_u64(last)?);
+                if last < first {
+                    return Err(InvalidRange);
+                }
+                ByteRangeSpec::FromTo(first, last)
+            };
+            if ranges.len() == MAX_RANGES {
+                return Err(InvalidRange);
+            }
+            ranges.push(range);
+        }
+        if ranges.is_empty() {
+            Err(InvalidRange)
+        } else {
+            Ok(Range { ranges })
+        }
+    }
+}
+
+impl TryFrom<HeaderValue> for Range {
+    type Error = InvalidRange;
+    fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+        value.to_str().map_err(|_| InvalidRange)?.try_into()
+    }
+}
+
+impl TryFrom<Value> for Range {
+    type Error = InvalidRange;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::One(h) => h.try_into(),
+            _ => Err(InvalidRange),
+        }
+    }
+}
+
+typed_header!(Range, RANGE);
+
+/// `Content-Range` header, only `bytes` unit is supported
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContentRange {
+    range: Option<(u64, u64)>,
+    complete_length: Option<u64>,
+}
+
+impl ContentRange {
+    /// `bytes first-last/complete_length` value
+    pub fn bytes(first: u64, last: u64, complete_length: u64) -> Self {
+        ContentRange {
+            range: Some((first, last)),
+            complete_length: Some(complete_length),
+        }
+    }
+    /// `bytes first-last/*` value
+    pub fn bytes_unknown_length(first: u64, last: u64) -> Self {
+        ContentRange {
+            range: Some((first, last)),
+            complete_length: None,
+        }
+    }
+    /// `bytes */complete_length` value, used with `416 Range Not Satisfiable`
+    pub fn unsatisfied(complete_length: u64) -> Self {
+        ContentRange {
+            range: None,
+            complete_length: Some(complete_length),
+        }
+    }
+    /// Inclusive `(first, last)` positions of the range
+    pub fn get_range(&self) -> Option<(u64, u64)> {
+        self.range
+    }
+    pub fn get_complete_length(&self) -> Option<u64> {
+        self.complete_length
+    }
+}
+
+impl fmt::Display for ContentRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("bytes ")?;
+        match self.range {
+            Some((first, last)) => write!(f, "{}-{}/", first, last)?,
+            None => f.write_str("*/")?,
+        }
+        match self.complete_length {
+            Some(len) => write!(f, "{}", len),
+            None => f.write_str("*"),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidContentRange;
+
+impl TryFrom<&str> for ContentRange {
+    type Error = InvalidContentRange;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim();
+        let rest = value
+            .get(..6)
+            .filter(|unit| unit.eq_ignore_ascii_case("bytes "))
+            .map(|_| &value[6..])
+            .ok_or(InvalidContentRange)?;
+        let (range, length) = rest.trim().split_once('/').ok_or(InvalidContentRange)?;
+
+        let complete_length = match length.trim() {
+            "*" => None,
+            l => Some(parse_u64(l).map_err(|_| InvalidContentRange)?),
+        };
+        let range = match range.trim() {
+            "*" => None,
+            r => {
+                let (first, last) = r.split_once('-').ok_or(InvalidContentRange)?;
+                let first = parse_u64(first.trim()).map_err(|_| InvalidContentRange)?;
+                let last = parse_u64(last.trim()).map_err(|_| InvalidContentRange)?;
+                if last < first || complete_length.map(|l| last >= l).unwrap_or(false) {
+                    return Err(InvalidContentRange);
+                }
+                Some((first, last))
+            }
+        };
+        if range.is_none() && complete_length.is_none() {
+            return Err(InvalidContentRange);
+        }
+        Ok(ContentRange {
+            range,
+            complete_length,
+        })
+    }
+}
+
+impl TryFrom<HeaderValue> for ContentRange {
+    type Error = InvalidContentRange;
+    fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+        value.to_str().map_err(|_| InvalidContentRange)?.try_into()
+    }
+}
+
+impl TryFrom<Value> for ContentRange {
+    type Error = InvalidContentRange;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::One(h) => h.try_into(),
+            _ => Err(InvalidContentRange),
+        }
+    }
+}
+
+typed_header!(ContentRange, CONTENT_RANGE);
+
+/// `If-Range` header
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum IfRange {
+    /// Entity tag, including quotes and weakness indicator
+    EntityTag(String),
+    /// Http date
+    Date(SystemTime),
+}
+
+impl IfRange {
+    /// Check if validator matches representation.
+    ///
+    /// Entity tags use strong comparison, dates must be equal,
+    /// with one second precision.
+    pub fn is_match(&self, etag: Option<&str>, last_modified: Option<SystemTime>) -> bool {
+        match self {
+            IfRange::EntityTag(tag) => {
+                !tag.starts_with("W/") && etag.map(|etag| etag == tag).unwrap_or(false)
+            }
+            IfRange::Date(date) => last_modified
+                .map(|modified| {
+                    httpdate::HttpDate::from(modified) == httpdate::HttpDate::from(*date)
+                })
+                .unwrap_or(false),
+        }
+    }
+}
+
+impl fmt::Display for IfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IfRange::EntityTag(tag) => f.write_str(tag),
+            IfRange::Date(date) => write!(f, "{}", httpdate::HttpDate::from(*date)),
+        }
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidIfRange;
+
+impl TryFrom<&str> for IfRange {
+    type Error = InvalidIfRange;
+    fn try_from(value: &str) -> Result<Self, Self::Error> {
+        let value = value.trim();
+        if value.starts_with('"') || value.starts_with("W/\"") {
+            if value.len() > 1 && value.ends_with('"') {
+                Ok(IfRange::EntityTag(value.to_string()))
+            } else {
+                Err(InvalidIfRange)
+            }
+        } else {
+            httpdate::parse_http_date(value)
+                .map(IfRange::Date)
+                .map_err(|_| InvalidIfRange)
+        }
+    }
+}
+
+impl TryFrom<HeaderValue> for IfRange {
+    type Error = InvalidIfRange;
+    fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+        value.to_str().map_err(|_| InvalidIfRange)?.try_into()
+    }
+}
+
+impl TryFrom<Value> for IfRange {
+    type Error = InvalidIfRange;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::One(h) => h.try_into(),
+            _ => Err(InvalidIfRange),
+        }
+    }
+}
+
+typed_header!(IfRange, IF_RANGE);
+
+/// `Accept-Ranges` header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AcceptRanges {
+    Bytes,
+    None,
+}
+
+impl AcceptRanges {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AcceptRanges::Bytes => "bytes",
+            AcceptRanges::None => "none",
+        }
+    }
+}
+
+impl fmt::Display for AcceptRanges {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct InvalidAcceptRanges;
+
+impl TryFrom<HeaderValue> for AcceptRanges {
+    type Error = InvalidAcceptRanges;
+    fn try_from(value: HeaderValue) -> Result<Self, Self::Error> {
+        let value = value.to_str().map_err(|_| InvalidAcceptRanges)?.trim();
+        if value.eq_ignore_ascii_case("bytes") {
+            Ok(AcceptRanges::Bytes)
+        } else if value.eq_ignore_ascii_case("none") {
+            Ok(AcceptRanges::None)
+        } else {
+            Err(InvalidAcceptRanges)
+        }
+    }
+}
+
+impl TryFrom<Value> for AcceptRanges {
+    type Error = InvalidAcceptRanges;
+    fn try_from(value: Value) -> Result<Self, Self::Error> {
+        match value {
+            Value::One(h) => h.try_into(),
+            _ => Err(InvalidAcceptRanges),
+        }
+    }
+}
+
+typed_header!(AcceptRanges, ACCEPT_RANGES);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::headers::TypedHeader;
+    use std::time::Duration;
+
+    #[test]
+    fn test_range() {
+        let range =
+            Range::try_from(HeaderValue::from_static("bytes=0-9, 90-, -5")).unwrap();
+        assert_eq!(
+            range.ranges(),
+            &[
+                ByteRangeSpec::FromTo(0, 9),
+                ByteRangeSpec::From(90),
+                ByteRangeSpec::Last(5)
+            ]
+        );
+        assert_eq!(
+            range.satisfiable_ranges(100),
+            vec![(0, 9), (90, 99), (95, 99)]
+        );
+        assert_eq!(range.satisfiable_ranges(50), vec![(0, 9), (45, 49)]);
+        assert!(range.satisfiable_ranges(0).is_empty());
+        assert_eq!(range.to_string(), "bytes=0-9, 90-, -5");
+
+        let value = Range::bytes(0, 0)
+            .add_range(ByteRangeSpec::Last(1))
+            .encode()
+            .unwrap();
+        assert_eq!(value, HeaderValue::from_static("bytes=0-0, -1"));
+        assert_eq!(Range::name(), crate::header::RANGE);
+
+        assert_eq!(
+            ByteRangeSpec::FromTo(10, 200).to_satisfiable_range(100),
+            Some((10, 99))
+        );
+        assert_eq!(ByteRangeSpec::Last(0).to_satisfiable_range(100), None);
+        assert_eq!(
+            ByteRangeSpec::Last(200).to_satisfiable_range(100),
+            Some((0, 99))
+        );
+
+        for value in &[
+            "bytes=",
+            "items=0-1",
+            "bytes=5-1",
+            "bytes=a-b",
+            "bytes=10",
+            "0-1",
+        ] {
+            assert!(Range::try_from(*value).is_err(), "{}", value);
+        }
+
+        let value = vec!["0-0"; MAX_RANGES].join(",");
+        let range = Range::try_from(format!("bytes={}", value).as_str()).unwrap();
+        assert_eq!(range.ranges().len(), MAX_RANGES);
+        let value = vec!["0-0"; MAX_RANGES + 1].join(",");
+        assert!(Range::try_from(format!("bytes={}", value).as_str()).is_err());
+        assert!(Range::try_from(Value::Multi(vec![
+            HeaderValue::from_static("bytes=0-1"),
+            HeaderValue::from_static("bytes=2-3")
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn test_content_range() {
+        let range = ContentRange::bytes(0, 9, 100);
+        assert_eq!(range.to_string(), "bytes 0-9/100");
+        assert_eq!(range.get_range(), Some((0, 9)));
+        assert_eq!(range.get_complete_length(), Some(100));
+        assert_eq!(
+            ContentRange::bytes_unknown_length(0, 9).to_string(),
+            "bytes 0-9/*"
+        );
+        let value = ContentRange::unsatisfied(100).encode().unwrap();
+        assert_eq!(value, HeaderValue::from_static("bytes */100"));
+
+        assert_eq!(
+            ContentRange::try_from(HeaderValue::from_static("bytes 0-9/100")).unwrap(),
+            range
+        );
+        assert_eq!(
+            ContentRange::try_from("bytes */100").unwrap(),
+            ContentRange::unsatisfied(100)
+        );
+        assert_eq!(
+            ContentRange::try_from("bytes 5-9/*").unwrap(),
+            ContentRange::bytes_unknown_length(5, 9)
+        );
+        for value in &[
+            "bytes */*",
+            "bytes 9-0/100",
+            "bytes 0-100/100",
+            "items 0-1/2",
+        ] {
+            assert!(ContentRange::try_from(*value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn test_if_range() {
+        let date = SystemTime::UNIX_EPOCH + Duration::from_secs(784111777);
+
+        let value = IfRange::try_from(HeaderValue::from_static("\"abc\"")).unwrap();
+        assert_eq!(value, IfRange::EntityTag("\"abc\"".to_string()));
+        assert!(value.is_match(Some("\"abc\""), None));
+        assert!(!value.is_match(Some("\"abd\""), None));
+        assert!(!value.is_match(None, Some(date)));
+
+        let value = IfRange::try_from("W/\"abc\"").unwrap();
+        assert!(!value.is_match(Some("W/\"abc\""), None));
+
+        let value = IfRange::try_from("Sun, 06 Nov 1994 08:49:37 GMT").unwrap();
+        assert_eq!(value, IfRange::Date(date));
+        assert!(value.is_match(None, Some(date + Duration::from_millis(100))));
+        assert!(!value.is_match(None, Some(date + Duration::from_secs(1))));
+        assert_eq!(value.to_string(), "Sun, 06 Nov 1994 08:49:37 GMT");
+        assert_eq!(IfRange::name(), crate::header::IF_RANGE);
+
+        assert!(IfRange::try_from("\"abc").is_err());
+        assert!(IfRange::try_from("yesterday").is_err());
+    }
+
+    #[test]
+    fn test_accept_ranges() {
+        let value = AcceptRanges::Bytes.encode().unwrap();
+        assert_eq!(value, HeaderValue::from_static("bytes"));
+        assert_eq!(
+            AcceptRanges::try_from(HeaderValue::from_static("none")).unwrap(),
+            AcceptRanges::None
+        );
+        assert!(AcceptRanges::try_from(HeaderValue::from_static("items")).is_err());
+    }
+}
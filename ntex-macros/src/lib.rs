@@ -42,6 +42,7 @@
 extern crate proc_macro;
 
 mod route;
+mod validate;
 
 use proc_macro::TokenStream;
 use quote::quote;
@@ -337,3 +338,49 @@ pub fn rt_test2(_: TokenStream, item: TokenStream) -> TokenStream {
 
     result.into()
 }
+
+/// Derives `ntex::web::types::Validate` trait.
+///
+/// ## Constraints:
+///
+/// - `length(min = 1, max = 32)` - length of string in chars or length of collection
+/// - `range(min = 1, max = 100)` - range of value, bounds are inclusive
+/// - `regex = "^[a-z]+$"` - string matches regular expression, pattern is
+///   checked at compile time
+/// - `email` - string is an email address
+///
+/// ## Usage
+///
+/// ```rust
+/// use ntex::web::types::Validate;
+///
+/// #[derive(Validate)]
+/// struct User {
+///     #[validate(length(min = 1, max = 32))]
+///     username: String,
+///     #[validate(email)]
+///     email: Option<String>,
+///     #[validate(range(min = "-90.0", max = 90.0))]
+///     latitude: f64,
+/// }
+/// ```
+///
+/// Invalid regular expression fails compilation:
+///
+/// ```compile_fail
+/// use ntex::web::types::Validate;
+///
+/// #[derive(Validate)]
+/// struct User {
+///     #[validate(regex = "[a-z")]
+///     username: String,
+/// }
+/// ```
+#[proc_macro_derive(Validate, attributes(validate))]
+pub fn validate(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as syn::DeriveInput);
+    match validate::generate(input) {
+        Ok(stream) => stream,
+        Err(err) => err.to_compile_error().into(),
+    }
+}
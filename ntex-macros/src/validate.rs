@@ -0,0 +1,213 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::spanned::Spanned;
+use syn::{Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+/// Field constraint
+enum Constraint {
+    Length(Option<TokenStream2>, Option<TokenStream2>),
+    Range(Option<TokenStream2>, Option<TokenStream2>),
+    Regex(syn::LitStr),
+    Email,
+}
+
+impl Constraint {
+    fn parse(meta: NestedMeta) -> syn::Result<Self> {
+        match meta {
+            NestedMeta::Meta(Meta::Path(ref path)) if path.is_ident("email") => {
+                Ok(Constraint::Email)
+            }
+            NestedMeta::Meta(Meta::NameValue(ref nv)) if nv.path.is_ident("regex") => {
+                match nv.lit {
+                    Lit::Str(ref lit) => match regex::Regex::new(&lit.value()) {
+                        Ok(_) => Ok(Constraint::Regex(lit.clone())),
+                        Err(e) => Err(syn::Error::new(
+                            lit.span(),
+                            format!("Invalid regex pattern: {}", e),
+                        )),
+                    },
+                    _ => Err(syn::Error::new(nv.lit.span(), "Regex must be a string")),
+                }
+            }
+            NestedMeta::Meta(Meta::List(ref list))
+                if list.path.is_ident("length") || list.path.is_ident("range") =>
+            {
+                let mut min = None;
+                let mut max = None;
+                for item in &list.nested {
+                    match item {
+                        NestedMeta::Meta(Meta::NameValue(nv))
+                            if nv.path.is_ident("min") =>
+                        {
+                            min = Some(bound(&nv.lit)?);
+                        }
+                        NestedMeta::Meta(Meta::NameValue(nv))
+                            if nv.path.is_ident("max") =>
+                        {
+                            max = Some(bound(&nv.lit)?);
+                        }
+                        _ => {
+                            return Err(syn::Error::new(
+                                item.span(),
+                                "Unknown attribute, expected `min` or `max`",
+                            ))
+                        }
+                    }
+                }
+                if min.is_none() && max.is_none() {
+                    return Err(syn::Error::new(list.span(), "`min` or `max` is required"));
+                }
+                if list.path.is_ident("length") {
+                    Ok(Constraint::Length(min, max))
+                } else {
+                    Ok(Constraint::Range(min, max))
+                }
+            }
+            _ => Err(syn::Error::new(
+                meta.span(),
+                "Unknown constraint, expected `length`, `range`, `regex` or `email`",
+            )),
+        }
+    }
+
+    fn generate(&self, name: &str) -> TokenStream2 {
+        match self {
+            Constraint::Length(min, max) => {
+                let (min, max) = (option(min), option(max));
+                quote! { errors.check_length(#name, value, #min, #max); }
+            }
+            Constraint::Range(min, max) => {
+                let (min, max) = (option(min), option(max));
+                quote! { errors.check_range(#name, value, #min, #max); }
+            }
+            Constraint::Regex(pattern) => quote! {
+                errors.check_regex(#name, ::std::convert::AsRef::<str>::as_ref(value), #pattern);
+            },
+            Constraint::Email => quote! {
+                errors.check_email(#name, ::std::convert::AsRef::<str>::as_ref(value));
+            },
+        }
+    }
+}
+
+/// Bound value, string is parsed as an expression, `min = "-1"`
+fn bound(lit: &Lit) -> syn::Result<TokenStream2> {
+    match lit {
+        Lit::Int(lit) => Ok(quote! { #lit }),
+        Lit::Float(lit) => Ok(quote! { #lit }),
+        Lit::Str(lit) => {
+            let expr: syn::Expr = lit.parse()?;
+            Ok(quote! { #expr })
+        }
+        _ => Err(syn::Error::new(lit.span(), "Bound must be a number")),
+    }
+}
+
+fn option(value: &Option<TokenStream2>) -> TokenStream2 {
+    match value {
+        Some(value) => quote! { ::std::option::Option::Some(#value) },
+        None => quote! { ::std::option::Option::None },
+    }
+}
+
+/// Field name from `#[serde(rename = "..")]` attribute
+fn serde_rename(attrs: &[syn::Attribute]) -> Option<String> {
+    for attr in attrs.iter().filter(|attr| attr.path.is_ident("serde")) {
+        if let Ok(Meta::List(list)) = attr.parse_meta() {
+            for item in list.nested {
+                if let NestedMeta::Meta(Meta::NameValue(nv)) = item {
+                    if let (true, Lit::Str(lit)) = (nv.path.is_ident("rename"), nv.lit) {
+                        return Some(lit.value());
+                    }
+                }
+            }
+        }
+    }
+    None
+}
+
+fn is_option(ty: &syn::Type) -> bool {
+    if let syn::Type::Path(ty) = ty {
+        if let Some(seg) = ty.path.segments.last() {
+            return seg.ident == "Option";
+        }
+    }
+    false
+}
+
+pub fn generate(input: DeriveInput) -> syn::Result<TokenStream> {
+    let fields = match input.data {
+        Data::Struct(ref data) => match data.fields {
+            Fields::Named(ref fields) => &fields.named,
+            _ => {
+                return Err(syn::Error::new(
+                    input.ident.span(),
+                    "Validate could be derived only for structs with named fields",
+                ))
+            }
+        },
+        _ => {
+            return Err(syn::Error::new(
+                input.ident.span(),
+                "Validate could be derived only for structs",
+            ))
+        }
+    };
+
+    let mut checks = Vec::new();
+    for field in fields {
+        let mut constraints = Vec::new();
+        for attr in field.attrs.iter().filter(|a| a.path.is_ident("validate")) {
+            match attr.parse_meta()? {
+                Meta::List(list) => {
+                    for item in list.nested {
+                        constraints.push(Constraint::parse(item)?);
+                    }
+                }
+                meta => {
+                    return Err(syn::Error::new(
+                        meta.span(),
+                        "Expected list of constraints, `#[validate(..)]`",
+                    ))
+                }
+            }
+        }
+        if constraints.is_empty() {
+            continue;
+        }
+
+        let ident = field.ident.as_ref().unwrap();
+        let name = serde_rename(&field.attrs)
+            .unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+        let constraints: Vec<_> = constraints.iter().map(|c| c.generate(&name)).collect();
+        if is_option(&field.ty) {
+            checks.push(quote! {
+                if let ::std::option::Option::Some(ref value) = self.#ident {
+                    #(#constraints)*
+                }
+            });
+        } else {
+            checks.push(quote! {
+                {
+                    let value = &self.#ident;
+                    #(#constraints)*
+                }
+            });
+        }
+    }
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let stream = quote! {
+        impl #impl_generics ntex::web::types::Validate for #name #ty_generics #where_clause {
+            fn validate(&self) -> ::std::result::Result<(), ntex::web::error::ValidationError> {
+                #[allow(unused_mut)]
+                let mut errors = ntex::web::error::ValidationError::new();
+                #(#checks)*
+                errors.into_result()
+            }
+        }
+    };
+    Ok(stream.into())
+}
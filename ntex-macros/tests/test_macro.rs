@@ -1,10 +1,12 @@
 use futures::{future, Future};
 use ntex::http::{Method, StatusCode};
-use ntex::web::{test, types::Path, App, Error, HttpResponse, HttpResponseBuilder};
+use ntex::web::types::{Json, Path, Valid, Validate};
+use ntex::web::{test, App, Error, HttpResponse, HttpResponseBuilder};
 use ntex_macros::{
     web_connect, web_delete, web_get, web_head, web_options, web_patch, web_post, web_put,
     web_trace,
 };
+use serde::Deserialize;
 
 // Make sure that we can name function as 'config'
 #[web_get("/config")]
@@ -150,3 +152,88 @@ async fn test_auto_async() {
     let response = request.send().await.unwrap();
     assert!(response.status().is_success());
 }
+
+#[derive(Validate, Deserialize, Debug)]
+struct User {
+    #[validate(length(min = 3, max = 8), regex = "^[a-z]+$")]
+    name: String,
+    #[validate(email)]
+    email: Option<String>,
+    #[validate(range(min = 18, max = 150))]
+    #[serde(rename = "userAge")]
+    age: u32,
+    #[validate(range(min = "-1.5"))]
+    score: f64,
+    #[validate(length(max = 2))]
+    tags: Vec<String>,
+    #[serde(default)]
+    _ignored: String,
+}
+
+#[web_post("/user")]
+async fn user(info: Valid<Json<User>>) -> String {
+    info.into_inner().into_inner().name
+}
+
+#[test]
+fn test_validate() {
+    let mut value = User {
+        name: "test".to_string(),
+        email: None,
+        age: 21,
+        score: 0.0,
+        tags: vec![],
+        _ignored: String::new(),
+    };
+    assert!(value.validate().is_ok());
+
+    value.email = Some("test@example.com".to_string());
+    assert!(value.validate().is_ok());
+
+    value.name = "Te".to_string();
+    value.email = Some("test".to_string());
+    value.age = 10;
+    value.score = -2.0;
+    value.tags = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+    let err = value.validate().unwrap_err();
+    let fields: Vec<_> = err
+        .fields()
+        .iter()
+        .map(|f| (f.field.as_ref(), f.code.as_ref()))
+        .collect();
+    assert_eq!(
+        fields,
+        vec![
+            ("name", "length"),
+            ("name", "regex"),
+            ("email", "email"),
+            ("userAge", "range"),
+            ("score", "range"),
+            ("tags", "length"),
+        ]
+    );
+}
+
+#[ntex::test]
+async fn test_valid() {
+    let srv = test::server(|| App::new().service(user));
+
+    let request = srv
+        .request(Method::POST, srv.url("/user"))
+        .header("content-type", "application/json");
+    let mut response = request
+        .send_body(r#"{"name":"test","userAge":21,"score":1,"tags":[]}"#)
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.body().await.unwrap(), "test");
+
+    let request = srv
+        .request(Method::POST, srv.url("/user"))
+        .header("content-type", "application/json");
+    let response = request
+        .send_body(r#"{"name":"test","userAge":12,"score":1,"tags":[]}"#)
+        .await
+        .unwrap();
+    assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+}
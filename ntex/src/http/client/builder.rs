@@ -34,6 +34,7 @@ impl ClientBuilder {
             config: ClientConfig {
                 headers: HeaderMap::new(),
                 timeout: Millis(5_000),
+                max_ratio: 0,
                 connector: Box::new(ConnectorWrapper(Connector::default().finish())),
             },
         }
@@ -79,6 +80,17 @@ impl ClientBuilder {
         self
     }
 
+    /// Set max ratio between decoded and encoded response payload sizes.
+    ///
+    /// Decompressed response payload is rejected with
+    /// `PayloadError::DecodedOverflow` if it exceeds 64Kb and is more than
+    /// `ratio` times larger than received payload. By default ratio is
+    /// not checked.
+    pub fn max_ratio(mut self, ratio: usize) -> Self {
+        self.config.max_ratio = ratio;
+        self
+    }
+
     /// Do not add default request headers.
     /// By default `Date` and `User-Agent` headers are set.
     pub fn no_default_headers(mut self) -> Self {
@@ -146,10 +158,12 @@ mod tests {
             .disable_timeout()
             .disable_redirects()
             .max_redirects(10)
+            .max_ratio(200)
             .no_default_headers();
         assert!(!builder.allow_redirects);
         assert!(!builder.default_headers);
         assert_eq!(builder.max_redirects, 10);
+        assert_eq!(builder.config.max_ratio, 200);
     }
 
     #[crate::rt_test]
@@ -77,6 +77,7 @@ pub(self) struct ClientConfig {
     pub(self) connector: Box<dyn HttpConnect>,
     pub(self) headers: HeaderMap,
     pub(self) timeout: Millis,
+    pub(self) max_ratio: usize,
 }
 
 impl Default for Client {
@@ -85,6 +86,7 @@ impl Default for Client {
             connector: Box::new(ConnectorWrapper(Connector::default().finish())),
             headers: HeaderMap::new(),
             timeout: Millis(5_000),
+            max_ratio: 0,
         }))
     }
 }
@@ -16,11 +16,18 @@ use super::error::{FreezeRequestError, InvalidUrl};
 use super::sender::{PrepForSendingError, SendClientRequest};
 use super::{frozen::FrozenClientRequest, ClientConfig};
 
-#[cfg(feature = "compress")]
+#[cfg(feature = "zstd")]
+const HTTPS_ENCODING: &str = "br, zstd, gzip, deflate";
+#[cfg(all(feature = "compress", not(feature = "zstd")))]
 const HTTPS_ENCODING: &str = "br, gzip, deflate";
 #[cfg(not(feature = "compress"))]
 const HTTPS_ENCODING: &str = "br";
 
+#[cfg(feature = "zstd")]
+const HTTP_ENCODING: &str = "zstd, gzip, deflate";
+#[cfg(all(feature = "compress", not(feature = "zstd")))]
+const HTTP_ENCODING: &str = "gzip, deflate";
+
 /// An HTTP Client request builder
 ///
 /// This type can be used to construct an instance of `ClientRequest` through a
@@ -531,7 +538,7 @@ impl ClientRequest {
             } else {
                 #[cfg(any(feature = "compress"))]
                 {
-                    slf = slf.set_header_if_none(header::ACCEPT_ENCODING, "gzip, deflate")
+                    slf = slf.set_header_if_none(header::ACCEPT_ENCODING, HTTP_ENCODING)
                 }
             };
         }
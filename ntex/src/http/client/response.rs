@@ -7,9 +7,14 @@ use serde::de::DeserializeOwned;
 #[cfg(feature = "cookie")]
 use coo_kie::{Cookie, ParseError as CookieParseError};
 
+#[cfg(feature = "compress")]
+use crate::http::encoding::Decoder;
 use crate::http::error::PayloadError;
+#[cfg(feature = "compress")]
+use crate::http::header::ContentEncoding;
 use crate::http::header::{AsName, HeaderValue, CONTENT_LENGTH};
 use crate::http::{HeaderMap, HttpMessage, Payload, ResponseHead, StatusCode, Version};
+use crate::sse::{EventStream, SseError};
 use crate::time::{Deadline, Millis};
 use crate::util::{Bytes, BytesMut, Extensions, Stream};
 
@@ -19,6 +24,10 @@ use super::error::JsonPayloadError;
 pub struct ClientResponse {
     pub(crate) head: ResponseHead,
     pub(crate) payload: Payload,
+    #[cfg(feature = "compress")]
+    pub(crate) decompress: bool,
+    #[cfg(feature = "compress")]
+    pub(crate) max_ratio: usize,
 }
 
 impl HttpMessage for ClientResponse {
@@ -59,7 +68,14 @@ impl HttpMessage for ClientResponse {
 impl ClientResponse {
     /// Create new Request instance
     pub(crate) fn new(head: ResponseHead, payload: Payload) -> Self {
-        ClientResponse { head, payload }
+        ClientResponse {
+            head,
+            payload,
+            #[cfg(feature = "compress")]
+            decompress: false,
+            #[cfg(feature = "compress")]
+            max_ratio: 0,
+        }
     }
 
     pub(crate) fn with_empty_payload(head: ResponseHead) -> Self {
@@ -98,13 +114,34 @@ impl ClientResponse {
     /// Set a body and return previous body value
     pub fn set_payload(&mut self, payload: Payload) {
         self.payload = payload;
+        #[cfg(feature = "compress")]
+        {
+            self.decompress = false;
+        }
     }
 
     /// Get response's payload
     pub fn take_payload(&mut self) -> Payload {
+        #[cfg(feature = "compress")]
+        {
+            if self.decompress {
+                return Payload::from_stream(self.take_decoder());
+            }
+        }
         mem::take(&mut self.payload)
     }
 
+    #[cfg(feature = "compress")]
+    /// Get response's payload wrapped with content decoder
+    fn take_decoder(&mut self) -> Decoder<Payload> {
+        let payload = mem::take(&mut self.payload);
+        if mem::take(&mut self.decompress) {
+            Decoder::from_headers(payload, &self.head.headers).max_ratio(self.max_ratio)
+        } else {
+            Decoder::new(payload, ContentEncoding::Identity)
+        }
+    }
+
     /// Request extensions
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
@@ -134,13 +171,35 @@ impl ClientResponse {
     pub fn json<T: DeserializeOwned>(&mut self) -> JsonBody<T> {
         JsonBody::new(self)
     }
+
+    /// Returns stream of server-sent events.
+    ///
+    /// Stream returns error if content type is not `text/event-stream`
+    pub fn events(&mut self) -> EventStream<Payload> {
+        let is_sse = matches!(
+            self.mime_type(),
+            Ok(Some(ref mt)) if mt.type_() == mime::TEXT && mt.subtype() == mime::EVENT_STREAM
+        );
+        if is_sse {
+            EventStream::new(self.take_payload())
+        } else {
+            EventStream::with_error(self.take_payload(), SseError::ContentType)
+        }
+    }
 }
 
 impl Stream for ClientResponse {
     type Item = Result<Bytes, PayloadError>;
 
     fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
-        Pin::new(&mut self.get_mut().payload).poll_next(cx)
+        let this = self.get_mut();
+        #[cfg(feature = "compress")]
+        {
+            if this.decompress {
+                this.payload = this.take_payload();
+            }
+        }
+        Pin::new(&mut this.payload).poll_next(cx)
     }
 }
 
@@ -181,15 +240,13 @@ impl MessageBody {
         MessageBody {
             length: len,
             err: None,
-            fut: Some(ReadBody::new(res.take_payload(), 262_144)),
+            fut: Some(ReadBody::new(res, 262_144)),
         }
     }
 
     /// Change max size of payload. By default max size is 256Kb
     pub fn limit(mut self, limit: usize) -> Self {
-        if let Some(ref mut fut) = self.fut {
-            fut.limit = limit;
-        }
+        self.fut = self.fut.take().map(|fut| fut.limit(limit));
         self
     }
 
@@ -279,16 +336,14 @@ where
         JsonBody {
             length: len,
             err: None,
-            fut: Some(ReadBody::new(req.take_payload(), 65536)),
+            fut: Some(ReadBody::new(req, 65536)),
             _t: PhantomData,
         }
     }
 
     /// Change max size of payload. By default max size is 64Kb
     pub fn limit(mut self, limit: usize) -> Self {
-        if let Some(ref mut fut) = self.fut {
-            fut.limit = limit;
-        }
+        self.fut = self.fut.take().map(|fut| fut.limit(limit));
         self
     }
 
@@ -332,6 +387,9 @@ where
 }
 
 struct ReadBody {
+    #[cfg(feature = "compress")]
+    stream: Decoder<Payload>,
+    #[cfg(not(feature = "compress"))]
     stream: Payload,
     buf: BytesMut,
     limit: usize,
@@ -339,7 +397,12 @@ struct ReadBody {
 }
 
 impl ReadBody {
-    fn new(stream: Payload, limit: usize) -> Self {
+    fn new(res: &mut ClientResponse, limit: usize) -> Self {
+        #[cfg(feature = "compress")]
+        let stream = res.take_decoder().limit(limit);
+        #[cfg(not(feature = "compress"))]
+        let stream = res.take_payload();
+
         Self {
             stream,
             limit,
@@ -347,6 +410,15 @@ impl ReadBody {
             timeout: Deadline::new(Millis(10000)),
         }
     }
+
+    fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        #[cfg(feature = "compress")]
+        {
+            self.stream = self.stream.limit(limit);
+        }
+        self
+    }
 }
 
 impl Future for ReadBody {
@@ -490,4 +562,52 @@ mod tests {
             }
         );
     }
+
+    #[cfg(feature = "compress")]
+    #[crate::rt_test]
+    async fn test_body_decoded_limit() {
+        use std::io::Write;
+
+        let mut enc =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        enc.write_all(&[b'x'; 4096]).unwrap();
+        let data = enc.finish().unwrap();
+
+        let mut req = TestResponse::with_header(header::CONTENT_ENCODING, "gzip")
+            .set_payload(data.clone())
+            .finish();
+        req.decompress = true;
+        assert_eq!(req.body().await.unwrap().len(), 4096);
+
+        let mut req = TestResponse::with_header(header::CONTENT_ENCODING, "gzip")
+            .set_payload(data)
+            .finish();
+        req.decompress = true;
+        match req.body().limit(1024).await.err().unwrap() {
+            PayloadError::DecodedOverflow => (),
+            _ => unreachable!("error"),
+        }
+
+        // expansion ratio
+        let mut enc =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        enc.write_all(&[0; 1_048_576]).unwrap();
+        let data = enc.finish().unwrap();
+
+        let mut req = TestResponse::with_header(header::CONTENT_ENCODING, "gzip")
+            .set_payload(data.clone())
+            .finish();
+        req.decompress = true;
+        assert_eq!(req.body().limit(2_097_152).await.unwrap().len(), 1_048_576);
+
+        let mut req = TestResponse::with_header(header::CONTENT_ENCODING, "gzip")
+            .set_payload(data)
+            .finish();
+        req.decompress = true;
+        req.max_ratio = 200;
+        match req.body().limit(2_097_152).await.err().unwrap() {
+            PayloadError::DecodedOverflow => (),
+            _ => unreachable!("error"),
+        }
+    }
 }
@@ -10,11 +10,6 @@ use crate::http::RequestHeadType;
 use crate::time::{sleep, Millis, Sleep};
 use crate::util::{Bytes, Stream};
 
-#[cfg(feature = "compress")]
-use crate::http::encoding::Decoder;
-#[cfg(feature = "compress")]
-use crate::http::Payload;
-
 use super::error::{FreezeRequestError, InvalidUrl, SendRequestError};
 use super::response::ClientResponse;
 use super::ClientConfig;
@@ -88,13 +83,7 @@ impl Future for SendClientRequest {
 
                 #[cfg(feature = "compress")]
                 let res = res.map(|mut res| {
-                    if *_response_decompress {
-                        let payload = res.take_payload();
-                        res.set_payload(Payload::from_stream(Decoder::from_headers(
-                            payload,
-                            &res.head.headers,
-                        )))
-                    }
+                    res.decompress = *_response_decompress;
                     res
                 });
 
@@ -142,11 +131,21 @@ impl RequestHeadType {
             timeout = config.timeout;
         }
 
-        SendClientRequest::new(
-            config.connector.send_request(self, body.into(), addr),
-            response_decompress,
-            timeout,
-        )
+        let fut = config.connector.send_request(self, body.into(), addr);
+        #[cfg(feature = "compress")]
+        let fut = if response_decompress && config.max_ratio != 0 {
+            let max_ratio = config.max_ratio;
+            Box::pin(async move {
+                fut.await.map(|mut res| {
+                    res.max_ratio = max_ratio;
+                    res
+                })
+            })
+        } else {
+            fut
+        };
+
+        SendClientRequest::new(fut, response_decompress, timeout)
     }
 
     pub(super) fn send_json<T: Serialize>(
@@ -1,7 +1,9 @@
-use std::{future::Future, io, io::Write, pin::Pin, task::Context, task::Poll};
+use std::{cmp, future::Future, io, io::Write, pin::Pin, task::Context, task::Poll};
 
 use brotli2::write::BrotliDecoder;
 use flate2::write::{GzDecoder, ZlibDecoder};
+#[cfg(feature = "zstd")]
+use zstd_pkg::stream::write::Decoder as ZstdDecoder;
 
 use super::Writer;
 use crate::http::error::PayloadError;
@@ -10,12 +12,16 @@ use crate::rt::{spawn_blocking, JoinHandle};
 use crate::util::{Bytes, Stream};
 
 const INPLACE: usize = 2049;
+const MIN_RATIO_SIZE: usize = 65_536;
 
 pub struct Decoder<S> {
     decoder: Option<ContentDecoder>,
     stream: S,
     eof: bool,
-    fut: Option<JoinHandle<Result<(Option<Bytes>, ContentDecoder), io::Error>>>,
+    limit: usize,
+    max_ratio: usize,
+    encoded: usize,
+    fut: Option<JoinHandle<Result<(Option<Bytes>, ContentDecoder), PayloadError>>>,
 }
 
 impl<S> Decoder<S>
@@ -35,6 +41,10 @@ where
             ContentEncoding::Gzip => Some(ContentDecoder::Gzip(Box::new(GzDecoder::new(
                 Writer::new(),
             )))),
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => ZstdDecoder::new(Writer::new())
+                .ok()
+                .map(|decoder| ContentDecoder::Zstd(Box::new(decoder))),
             _ => None,
         };
         Decoder {
@@ -42,6 +52,9 @@ where
             stream,
             fut: None,
             eof: false,
+            limit: usize::MAX,
+            max_ratio: 0,
+            encoded: 0,
         }
     }
 
@@ -61,6 +74,38 @@ where
 
         Self::new(stream, encoding)
     }
+
+    /// Set max size of decoded payload.
+    ///
+    /// Decoder returns `PayloadError::DecodedOverflow` if decoded payload
+    /// exceeds limit. By default size is not limited.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Set max ratio between decoded and encoded payload sizes.
+    ///
+    /// Decoder returns `PayloadError::DecodedOverflow` if decoded payload
+    /// exceeds 64Kb and is more than `ratio` times larger than received
+    /// encoded payload. Zero value disables check. By default ratio is
+    /// not checked.
+    pub fn max_ratio(mut self, ratio: usize) -> Self {
+        self.max_ratio = ratio;
+        self
+    }
+
+    /// Max allowed decoded size for currently received encoded payload
+    fn max_size(&self) -> usize {
+        if self.max_ratio == 0 {
+            self.limit
+        } else {
+            cmp::min(
+                self.limit,
+                cmp::max(MIN_RATIO_SIZE, self.encoded.saturating_mul(self.max_ratio)),
+            )
+        }
+    }
 }
 
 impl<S> Stream for Decoder<S>
@@ -77,7 +122,7 @@ where
             if let Some(ref mut fut) = self.fut {
                 let (chunk, decoder) = match Pin::new(fut).poll(cx) {
                     Poll::Ready(Ok(Ok(item))) => item,
-                    Poll::Ready(Ok(Err(e))) => return Poll::Ready(Some(Err(e.into()))),
+                    Poll::Ready(Ok(Err(e))) => return Poll::Ready(Some(Err(e))),
                     Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e.into()))),
                     Poll::Pending => return Poll::Pending,
                 };
@@ -96,15 +141,21 @@ where
                 Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err))),
                 Poll::Ready(Some(Ok(chunk))) => {
                     if let Some(mut decoder) = self.decoder.take() {
+                        self.encoded = self.encoded.saturating_add(chunk.len());
+                        decoder.writer().limit = self.max_size();
+
                         if chunk.len() < INPLACE {
-                            let chunk = decoder.feed_data(chunk)?;
+                            let chunk =
+                                decoder.feed_data(chunk).map_err(|e| decoder.error(e))?;
                             self.decoder = Some(decoder);
                             if let Some(chunk) = chunk {
                                 return Poll::Ready(Some(Ok(chunk)));
                             }
                         } else {
                             self.fut = Some(spawn_blocking(move || {
-                                let chunk = decoder.feed_data(chunk)?;
+                                let chunk = decoder
+                                    .feed_data(chunk)
+                                    .map_err(|e| decoder.error(e))?;
                                 Ok((chunk, decoder))
                             }));
                         }
@@ -116,10 +167,11 @@ where
                 Poll::Ready(None) => {
                     self.eof = true;
                     return if let Some(mut decoder) = self.decoder.take() {
+                        decoder.writer().limit = self.max_size();
                         match decoder.feed_eof() {
                             Ok(Some(res)) => Poll::Ready(Some(Ok(res))),
                             Ok(None) => Poll::Ready(None),
-                            Err(err) => Poll::Ready(Some(Err(err.into()))),
+                            Err(err) => Poll::Ready(Some(Err(decoder.error(err)))),
                         }
                     } else {
                         Poll::Ready(None)
@@ -136,9 +188,29 @@ enum ContentDecoder {
     Deflate(Box<ZlibDecoder<Writer>>),
     Gzip(Box<GzDecoder<Writer>>),
     Br(Box<BrotliDecoder<Writer>>),
+    #[cfg(feature = "zstd")]
+    Zstd(Box<ZstdDecoder<'static, Writer>>),
 }
 
 impl ContentDecoder {
+    fn writer(&mut self) -> &mut Writer {
+        match self {
+            ContentDecoder::Br(ref mut decoder) => decoder.get_mut(),
+            ContentDecoder::Gzip(ref mut decoder) => decoder.get_mut(),
+            ContentDecoder::Deflate(ref mut decoder) => decoder.get_mut(),
+            #[cfg(feature = "zstd")]
+            ContentDecoder::Zstd(ref mut decoder) => decoder.get_mut(),
+        }
+    }
+
+    fn error(&mut self, err: io::Error) -> PayloadError {
+        if self.writer().is_overflow() {
+            PayloadError::DecodedOverflow
+        } else {
+            err.into()
+        }
+    }
+
     fn feed_eof(&mut self) -> io::Result<Option<Bytes>> {
         match self {
             ContentDecoder::Br(ref mut decoder) => match decoder.flush() {
@@ -174,6 +246,18 @@ impl ContentDecoder {
                 }
                 Err(e) => Err(e),
             },
+            #[cfg(feature = "zstd")]
+            ContentDecoder::Zstd(ref mut decoder) => match decoder.flush() {
+                Ok(_) => {
+                    let b = decoder.get_mut().take();
+                    if !b.is_empty() {
+                        Ok(Some(b))
+                    } else {
+                        Ok(None)
+                    }
+                }
+                Err(e) => Err(e),
+            },
         }
     }
 
@@ -215,6 +299,89 @@ impl ContentDecoder {
                 }
                 Err(e) => Err(e),
             },
+            #[cfg(feature = "zstd")]
+            ContentDecoder::Zstd(ref mut decoder) => match decoder.write_all(&data) {
+                Ok(_) => {
+                    decoder.flush()?;
+                    let b = decoder.get_mut().take();
+                    if !b.is_empty() {
+                        Ok(Some(b))
+                    } else {
+                        Ok(None)
+                    }
+                }
+                Err(e) => Err(e),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use flate2::{write::GzEncoder, Compression};
+
+    use super::*;
+    use crate::util::stream_recv;
+
+    fn gzip_zeros(size: usize) -> Bytes {
+        let mut enc = GzEncoder::new(Vec::new(), Compression::best());
+        enc.write_all(&vec![0u8; size]).unwrap();
+        Bytes::from(enc.finish().unwrap())
+    }
+
+    async fn decode<S>(mut decoder: Decoder<S>) -> Result<usize, PayloadError>
+    where
+        S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+    {
+        let mut size = 0;
+        while let Some(chunk) = stream_recv(&mut decoder).await {
+            size += chunk?.len();
         }
+        Ok(size)
+    }
+
+    fn stream(data: Bytes) -> impl Stream<Item = Result<Bytes, PayloadError>> + Unpin {
+        futures_util::stream::iter(vec![Ok(data)])
+    }
+
+    #[crate::rt_test]
+    async fn test_decoded_limit() {
+        let data = gzip_zeros(50_000);
+        let size = decode(Decoder::new(stream(data.clone()), ContentEncoding::Gzip))
+            .await
+            .unwrap();
+        assert_eq!(size, 50_000);
+
+        let res =
+            decode(Decoder::new(stream(data.clone()), ContentEncoding::Gzip).limit(10_000))
+                .await;
+        assert!(matches!(res, Err(PayloadError::DecodedOverflow)));
+
+        let res =
+            decode(Decoder::new(stream(data), ContentEncoding::Identity).limit(10)).await;
+        assert!(res.is_ok());
+    }
+
+    #[crate::rt_test]
+    async fn test_decoded_ratio() {
+        let data = gzip_zeros(4_194_304);
+        assert!(data.len() < INPLACE * 4);
+
+        let size = decode(Decoder::new(stream(data.clone()), ContentEncoding::Gzip))
+            .await
+            .unwrap();
+        assert_eq!(size, 4_194_304);
+
+        let res = decode(
+            Decoder::new(stream(data.clone()), ContentEncoding::Gzip).max_ratio(200),
+        )
+        .await;
+        assert!(matches!(res, Err(PayloadError::DecodedOverflow)));
+
+        let size =
+            decode(Decoder::new(stream(data), ContentEncoding::Gzip).max_ratio(2_000))
+                .await
+                .unwrap();
+        assert_eq!(size, 4_194_304);
     }
 }
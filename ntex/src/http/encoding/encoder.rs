@@ -1,8 +1,10 @@
 //! Stream encoder
-use std::{future::Future, io, io::Write, pin::Pin, task::Context, task::Poll};
+use std::{cmp, future::Future, io, io::Write, pin::Pin, task::Context, task::Poll};
 
 use brotli2::write::BrotliEncoder;
 use flate2::write::{GzEncoder, ZlibEncoder};
+#[cfg(feature = "zstd")]
+use zstd_pkg::stream::write::Encoder as ZstdEncoder;
 
 use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
 use crate::http::header::{ContentEncoding, HeaderValue, CONTENT_ENCODING};
@@ -26,6 +28,19 @@ impl<B: MessageBody + 'static> Encoder<B> {
         encoding: ContentEncoding,
         head: &mut ResponseHead,
         body: ResponseBody<B>,
+    ) -> ResponseBody<B> {
+        Encoder::response_with_level(encoding, None, head, body)
+    }
+
+    /// Encode response body with specified compression level.
+    ///
+    /// Level is clamped to the max level supported by the encoding,
+    /// `None` selects default level.
+    pub fn response_with_level(
+        encoding: ContentEncoding,
+        level: Option<u32>,
+        head: &mut ResponseHead,
+        body: ResponseBody<B>,
     ) -> ResponseBody<B> {
         let can_encode = ContentEncoder::can_encode(encoding)
             && !(head.headers().contains_key(&CONTENT_ENCODING)
@@ -34,9 +49,14 @@ impl<B: MessageBody + 'static> Encoder<B> {
                 || encoding == ContentEncoding::Identity
                 || encoding == ContentEncoding::Auto);
 
-        if !can_encode {
-            body
+        // fall back to identity encoding if encoder could not be created
+        let encoder = if can_encode {
+            ContentEncoder::encoder(encoding, level)
         } else {
+            None
+        };
+
+        if let Some(encoder) = encoder {
             let body = match body {
                 ResponseBody::Other(b) => match b {
                     Body::None => return ResponseBody::Other(Body::None),
@@ -47,8 +67,6 @@ impl<B: MessageBody + 'static> Encoder<B> {
                 ResponseBody::Body(stream) => EncoderBody::Stream(stream),
             };
 
-            // Modify response body only if encoder is not None
-            let encoder = ContentEncoder::encoder(encoding).unwrap();
             update_head(encoding, head);
             head.no_chunking(false);
             ResponseBody::Other(Body::from_message(Encoder {
@@ -57,6 +75,8 @@ impl<B: MessageBody + 'static> Encoder<B> {
                 fut: None,
                 encoder: Some(encoder),
             }))
+        } else {
+            body
         }
     }
 }
@@ -170,6 +190,8 @@ enum ContentEncoder {
     Deflate(ZlibEncoder<Writer>),
     Gzip(GzEncoder<Writer>),
     Br(BrotliEncoder<Writer>),
+    #[cfg(feature = "zstd")]
+    Zstd(ZstdEncoder<'static, Writer>),
 }
 
 impl ContentEncoder {
@@ -177,22 +199,34 @@ impl ContentEncoder {
         matches!(
             encoding,
             ContentEncoding::Deflate | ContentEncoding::Gzip | ContentEncoding::Br
-        )
+        ) || (cfg!(feature = "zstd") && encoding == ContentEncoding::Zstd)
     }
 
-    fn encoder(encoding: ContentEncoding) -> Option<Self> {
+    fn encoder(encoding: ContentEncoding, level: Option<u32>) -> Option<Self> {
+        let flate_level = level
+            .map(|level| flate2::Compression::new(cmp::min(level, 9)))
+            .unwrap_or_else(flate2::Compression::fast);
+
         match encoding {
             ContentEncoding::Deflate => Some(ContentEncoder::Deflate(ZlibEncoder::new(
                 Writer::new(),
-                flate2::Compression::fast(),
+                flate_level,
             ))),
             ContentEncoding::Gzip => Some(ContentEncoder::Gzip(GzEncoder::new(
                 Writer::new(),
-                flate2::Compression::fast(),
+                flate_level,
             ))),
-            ContentEncoding::Br => {
-                Some(ContentEncoder::Br(BrotliEncoder::new(Writer::new(), 3)))
-            }
+            ContentEncoding::Br => Some(ContentEncoder::Br(BrotliEncoder::new(
+                Writer::new(),
+                level.map(|level| cmp::min(level, 11)).unwrap_or(3),
+            ))),
+            #[cfg(feature = "zstd")]
+            ContentEncoding::Zstd => ZstdEncoder::new(
+                Writer::new(),
+                level.map(|level| cmp::min(level, 22) as i32).unwrap_or(3),
+            )
+            .ok()
+            .map(ContentEncoder::Zstd),
             _ => None,
         }
     }
@@ -202,6 +236,8 @@ impl ContentEncoder {
             ContentEncoder::Br(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Deflate(ref mut encoder) => encoder.get_mut().take(),
             ContentEncoder::Gzip(ref mut encoder) => encoder.get_mut().take(),
+            #[cfg(feature = "zstd")]
+            ContentEncoder::Zstd(ref mut encoder) => encoder.get_mut().take(),
         }
     }
 
@@ -219,6 +255,11 @@ impl ContentEncoder {
                 Ok(writer) => Ok(writer.buf.freeze()),
                 Err(err) => Err(err),
             },
+            #[cfg(feature = "zstd")]
+            ContentEncoder::Zstd(encoder) => match encoder.finish() {
+                Ok(writer) => Ok(writer.buf.freeze()),
+                Err(err) => Err(err),
+            },
         }
     }
 
@@ -245,6 +286,14 @@ impl ContentEncoder {
                     Err(err)
                 }
             },
+            #[cfg(feature = "zstd")]
+            ContentEncoder::Zstd(ref mut encoder) => match encoder.write_all(data) {
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    trace!("Error decoding zstd encoding: {}", err);
+                    Err(err)
+                }
+            },
         }
     }
 }
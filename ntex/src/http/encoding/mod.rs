@@ -11,15 +11,24 @@ pub use self::encoder::Encoder;
 
 pub(self) struct Writer {
     buf: BytesMut,
+    size: usize,
+    limit: usize,
 }
 
 impl Writer {
     fn new() -> Writer {
         Writer {
             buf: BytesMut::with_capacity(8192),
+            size: 0,
+            limit: usize::MAX,
         }
     }
 
+    /// Check if total written size exceeds limit
+    fn is_overflow(&self) -> bool {
+        self.size > self.limit
+    }
+
     fn take(&mut self) -> Bytes {
         self.buf.split().freeze()
     }
@@ -27,6 +36,13 @@ impl Writer {
 
 impl io::Write for Writer {
     fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.size = self.size.saturating_add(buf.len());
+        if self.is_overflow() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Decoded payload size limit exceeded",
+            ));
+        }
         self.buf.extend_from_slice(buf);
         Ok(buf.len())
     }
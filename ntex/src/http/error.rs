@@ -125,6 +125,9 @@ pub enum PayloadError {
     /// A payload reached size limit.
     #[error("A payload reached size limit.")]
     Overflow,
+    /// Decoded payload reached size or expansion ratio limit.
+    #[error("Decoded payload reached size or expansion ratio limit.")]
+    DecodedOverflow,
     /// A payload length is unknown.
     #[error("A payload length is unknown.")]
     UnknownLength,
@@ -5,6 +5,12 @@ pub use http::header::{HeaderName, HeaderValue, InvalidHeaderValue};
 pub use http::header::*;
 #[doc(hidden)]
 pub use ntex_http::header::{AsName, GetAll, Value};
+pub use ntex_http::headers::{
+    Accept, AcceptCharset, AcceptEncoding, AcceptLanguage, AcceptRanges, Age,
+    ByteRangeSpec, CacheControl, CacheFlags, ContentRange, ETag, EntityTag, IfMatch,
+    IfModifiedSince, IfNoneMatch, IfRange, IfUnmodifiedSince, InvalidHeader, LastModified,
+    Quality, QualityItem, Range, TypedHeader,
+};
 pub use ntex_http::HeaderMap;
 
 /// Represents supported types of content encodings
@@ -20,6 +26,8 @@ pub enum ContentEncoding {
     Gzip,
     /// Indicates the identity function (i.e. no compression, nor modification)
     Identity,
+    /// A format using the Zstandard algorithm
+    Zstd,
 }
 
 impl ContentEncoding {
@@ -36,6 +44,7 @@ impl ContentEncoding {
             ContentEncoding::Br => "br",
             ContentEncoding::Gzip => "gzip",
             ContentEncoding::Deflate => "deflate",
+            ContentEncoding::Zstd => "zstd",
             ContentEncoding::Identity | ContentEncoding::Auto => "identity",
         }
     }
@@ -45,6 +54,7 @@ impl ContentEncoding {
     pub fn quality(self) -> f64 {
         match self {
             ContentEncoding::Br => 1.1,
+            ContentEncoding::Zstd => 1.05,
             ContentEncoding::Gzip => 1.0,
             ContentEncoding::Deflate => 0.9,
             ContentEncoding::Identity | ContentEncoding::Auto => 0.1,
@@ -62,6 +72,8 @@ impl<'a> From<&'a str> for ContentEncoding {
             ContentEncoding::Gzip
         } else if s.eq_ignore_ascii_case("deflate") {
             ContentEncoding::Deflate
+        } else if s.eq_ignore_ascii_case("zstd") {
+            ContentEncoding::Zstd
         } else {
             ContentEncoding::Identity
         }
@@ -78,5 +90,8 @@ mod tests {
         assert!(!ContentEncoding::Identity.is_compressed());
         assert!(!ContentEncoding::Auto.is_compressed());
         assert_eq!(format!("{:?}", ContentEncoding::Identity), "Identity");
+        assert!(ContentEncoding::Zstd.is_compressed());
+        assert_eq!(ContentEncoding::from(" ZSTD"), ContentEncoding::Zstd);
+        assert_eq!(ContentEncoding::Zstd.as_str(), "zstd");
     }
 }
@@ -95,6 +95,21 @@ impl Head for RequestHead {
 }
 
 impl RequestHead {
+    /// Copy of request head, extensions and upgraded io are not copied
+    pub(crate) fn duplicate(&self) -> Message<RequestHead> {
+        let mut msg = Message::<RequestHead>::new();
+        msg.uri = self.uri.clone();
+        msg.method = self.method.clone();
+        msg.version = self.version;
+        msg.headers = self.headers.clone();
+        msg.flags = self.flags;
+        msg.io = match self.io.as_ref() {
+            Some(io) => CurrentIo::Ref(io.clone()),
+            None => CurrentIo::None,
+        };
+        msg
+    }
+
     /// Message extensions
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
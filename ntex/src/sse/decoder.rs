@@ -0,0 +1,310 @@
+use std::{pin::Pin, str, task::Context, task::Poll};
+
+use crate::http::error::PayloadError;
+use crate::time::Millis;
+use crate::util::{Buf, Bytes, BytesMut, Stream};
+
+use super::{Event, SseError};
+
+const BOM: &[u8] = b"\xEF\xBB\xBF";
+
+/// `text/event-stream` decoder
+///
+/// Decoder keeps last event id between events, incomplete event
+/// is discarded at the end of the stream.
+#[derive(Debug)]
+pub struct Decoder {
+    limit: usize,
+    started: bool,
+    skip_lf: bool,
+    has_data: bool,
+    data: String,
+    event: Option<String>,
+    id: Option<String>,
+    retry: Option<Millis>,
+}
+
+impl Default for Decoder {
+    fn default() -> Self {
+        Decoder {
+            limit: 262_144,
+            started: false,
+            skip_lf: false,
+            has_data: false,
+            data: String::new(),
+            event: None,
+            id: None,
+            retry: None,
+        }
+    }
+}
+
+impl Decoder {
+    /// Create new decoder.
+    pub fn new() -> Self {
+        Decoder::default()
+    }
+
+    /// Change max size of the event. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Last event id
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Decode next event from the buffer.
+    pub fn decode(&mut self, src: &mut BytesMut) -> Result<Option<Event>, SseError> {
+        if !self.started {
+            if src.len() < BOM.len() && BOM.starts_with(src) {
+                return Ok(None);
+            }
+            if src.starts_with(BOM) {
+                src.advance(BOM.len());
+            }
+            self.started = true;
+        }
+
+        loop {
+            if self.skip_lf && !src.is_empty() {
+                if src[0] == b'\n' {
+                    src.advance(1);
+                }
+                self.skip_lf = false;
+            }
+
+            let pos = if let Some(pos) = src.iter().position(|c| *c == b'\n' || *c == b'\r')
+            {
+                pos
+            } else {
+                if self.data.len() + src.len() > self.limit {
+                    return Err(SseError::Overflow(self.limit));
+                }
+                return Ok(None);
+            };
+            if self.data.len() + pos > self.limit {
+                return Err(SseError::Overflow(self.limit));
+            }
+
+            let line = src.split_to(pos);
+            if src[0] == b'\r' {
+                if src.len() > 1 {
+                    if src[1] == b'\n' {
+                        src.advance(1);
+                    }
+                } else {
+                    self.skip_lf = true;
+                }
+            }
+            src.advance(1);
+
+            let line = str::from_utf8(&line).map_err(|_| SseError::Utf8)?;
+            if let Some(event) = self.process_line(line) {
+                return Ok(Some(event));
+            }
+        }
+    }
+
+    fn process_line(&mut self, line: &str) -> Option<Event> {
+        if line.is_empty() {
+            // dispatch event
+            if !self.has_data {
+                self.event = None;
+                return None;
+            }
+            self.has_data = false;
+            return Some(Event {
+                id: self.id.clone(),
+                event: self.event.take(),
+                data: std::mem::take(&mut self.data),
+                retry: self.retry.take(),
+            });
+        } else if line.starts_with(':') {
+            // comment
+            return None;
+        }
+
+        let (field, value) = if let Some(idx) = line.find(':') {
+            let value = &line[idx + 1..];
+            (&line[..idx], value.strip_prefix(' ').unwrap_or(value))
+        } else {
+            (line, "")
+        };
+
+        match field {
+            "data" => {
+                if self.has_data {
+                    self.data.push('\n');
+                }
+                self.data.push_str(value);
+                self.has_data = true;
+            }
+            "event" => self.event = Some(value.to_string()),
+            "id" if !value.contains('\0') => self.id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(retry) = value.parse::<u32>() {
+                    self.retry = Some(Millis(retry));
+                }
+            }
+            _ => (),
+        }
+        None
+    }
+}
+
+/// Stream of server-sent events
+///
+/// ```rust
+/// use ntex::{http::client::Client, util::stream_recv};
+///
+/// async fn events() {
+///     let mut res = Client::new().get("http://127.0.0.1/events").send().await.unwrap();
+///     let mut events = res.events();
+///     while let Some(Ok(event)) = stream_recv(&mut events).await {
+///         println!("{:?}: {}", event.get_event(), event.data());
+///     }
+/// }
+/// ```
+pub struct EventStream<S> {
+    stream: S,
+    buf: BytesMut,
+    decoder: Decoder,
+    err: Option<SseError>,
+}
+
+impl<S> EventStream<S> {
+    /// Create new event stream from stream of bytes.
+    pub fn new(stream: S) -> Self {
+        EventStream {
+            stream,
+            buf: BytesMut::new(),
+            decoder: Decoder::new(),
+            err: None,
+        }
+    }
+
+    pub(crate) fn with_error(stream: S, err: SseError) -> Self {
+        EventStream {
+            err: Some(err),
+            ..EventStream::new(stream)
+        }
+    }
+
+    /// Change max size of the event. By default max size is 256Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.decoder = self.decoder.limit(limit);
+        self
+    }
+
+    /// Last event id
+    pub fn last_event_id(&self) -> Option<&str> {
+        self.decoder.last_event_id()
+    }
+}
+
+impl<S> Stream for EventStream<S>
+where
+    S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+{
+    type Item = Result<Event, SseError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+
+        if let Some(err) = this.err.take() {
+            return Poll::Ready(Some(Err(err)));
+        }
+
+        loop {
+            match this.decoder.decode(&mut this.buf) {
+                Ok(Some(event)) => return Poll::Ready(Some(Ok(event))),
+                Ok(None) => (),
+                Err(err) => return Poll::Ready(Some(Err(err))),
+            }
+
+            match Pin::new(&mut this.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(chunk))) => this.buf.extend_from_slice(&chunk),
+                Poll::Ready(Some(Err(err))) => return Poll::Ready(Some(Err(err.into()))),
+                Poll::Ready(None) => return Poll::Ready(None),
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::stream_recv;
+
+    fn decode(data: &[u8]) -> Vec<Event> {
+        let mut decoder = Decoder::new();
+        let mut buf = BytesMut::from(data);
+        let mut events = Vec::new();
+        while let Some(ev) = decoder.decode(&mut buf).unwrap() {
+            events.push(ev);
+        }
+        events
+    }
+
+    #[test]
+    fn test_decode() {
+        let events = decode(
+            b"\xEF\xBB\xBF: comment\nid: 1\nevent: update\ndata: line1\ndata:line2\n\n\
+              data\n\ndata: 3\r\nretry: 100\r\n\r\nevent: skip\n\ndata: 4\rid\r\rdata: 5",
+        );
+        assert_eq!(events.len(), 4);
+        assert_eq!(
+            events[0],
+            Event::new("line1\nline2").id("1").event("update")
+        );
+        assert_eq!(events[1], Event::new("").id("1"));
+        assert_eq!(events[2], Event::new("3").id("1").retry(Millis(100)));
+        assert_eq!(events[3], Event::new("4").id(""));
+
+        let mut buf = BytesMut::new();
+        Event::new("a\nb").id("x").event("y").encode(&mut buf);
+        assert_eq!(decode(&buf), vec![Event::new("a\nb").id("x").event("y")]);
+    }
+
+    #[test]
+    fn test_decode_errors() {
+        let mut decoder = Decoder::new().limit(8);
+        let mut buf = BytesMut::from(&b"data: 0123456789"[..]);
+        assert!(matches!(
+            decoder.decode(&mut buf),
+            Err(SseError::Overflow(8))
+        ));
+
+        let mut decoder = Decoder::new();
+        let mut buf = BytesMut::from(&b"data: \xff\n\n"[..]);
+        assert!(matches!(decoder.decode(&mut buf), Err(SseError::Utf8)));
+    }
+
+    #[crate::rt_test]
+    async fn test_event_stream() {
+        let chunks: Vec<&'static [u8]> = vec![
+            b"\xEF\xBB",
+            b"\xBFdata: 1\r",
+            b"\nid: 5\r",
+            b"\r",
+            b"\ndata: 2\n\nda",
+        ];
+        let stream = futures_util::stream::iter(
+            chunks
+                .into_iter()
+                .map(|c| Ok::<_, PayloadError>(Bytes::from_static(c))),
+        );
+        let mut events = EventStream::new(stream);
+
+        let ev = stream_recv(&mut events).await.unwrap().unwrap();
+        assert_eq!(ev, Event::new("1").id("5"));
+        assert_eq!(events.last_event_id(), Some("5"));
+        let ev = stream_recv(&mut events).await.unwrap().unwrap();
+        assert_eq!(ev, Event::new("2").id("5"));
+        assert!(stream_recv(&mut events).await.is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! Server-sent events support.
+//!
+//! [`Event`] describes single message of `text/event-stream` stream.
+//! Use `web::types::Sse` responder to send events to a client and
+//! [`EventStream`] to receive events from http client's response.
+use std::{fmt::Write, mem};
+
+use crate::http::error::PayloadError;
+use crate::time::Millis;
+use crate::util::{BufMut, BytesMut};
+
+mod decoder;
+
+pub use self::decoder::{Decoder, EventStream};
+
+/// Server-sent event
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Event {
+    id: Option<String>,
+    event: Option<String>,
+    data: String,
+    retry: Option<Millis>,
+}
+
+impl Event {
+    /// Create new event with specified data.
+    pub fn new<T: Into<String>>(data: T) -> Self {
+        Event {
+            data: data.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Create new event with json encoded data.
+    pub fn json<T: serde::Serialize>(data: &T) -> Result<Self, serde_json::Error> {
+        Ok(Event::new(serde_json::to_string(data)?))
+    }
+
+    /// Set event id.
+    ///
+    /// Line breaks are removed from the id.
+    pub fn id<T: Into<String>>(mut self, id: T) -> Self {
+        self.id = Some(single_line(id.into()));
+        self
+    }
+
+    /// Set event type.
+    ///
+    /// Line breaks are removed from the event type.
+    pub fn event<T: Into<String>>(mut self, event: T) -> Self {
+        self.event = Some(single_line(event.into()));
+        self
+    }
+
+    /// Set reconnection time.
+    pub fn retry<T: Into<Millis>>(mut self, retry: T) -> Self {
+        self.retry = Some(retry.into());
+        self
+    }
+
+    /// Get event id.
+    pub fn get_id(&self) -> Option<&str> {
+        self.id.as_deref()
+    }
+
+    /// Get event type.
+    pub fn get_event(&self) -> Option<&str> {
+        self.event.as_deref()
+    }
+
+    /// Get event data.
+    pub fn data(&self) -> &str {
+        &self.data
+    }
+
+    /// Take event data.
+    pub fn take_data(&mut self) -> String {
+        mem::take(&mut self.data)
+    }
+
+    /// Deserialize json encoded event data.
+    pub fn json_data<'a, T: serde::Deserialize<'a>>(&'a self) -> serde_json::Result<T> {
+        serde_json::from_str(&self.data)
+    }
+
+    /// Get reconnection time.
+    pub fn get_retry(&self) -> Option<Millis> {
+        self.retry
+    }
+
+    /// Encode event to `text/event-stream` format.
+    pub fn encode(&self, dst: &mut BytesMut) {
+        if let Some(ref id) = self.id {
+            dst.extend_from_slice(b"id: ");
+            dst.extend_from_slice(id.as_bytes());
+            dst.put_u8(b'\n');
+        }
+        if let Some(ref event) = self.event {
+            dst.extend_from_slice(b"event: ");
+            dst.extend_from_slice(event.as_bytes());
+            dst.put_u8(b'\n');
+        }
+        if let Some(retry) = self.retry {
+            let _ = writeln!(dst, "retry: {}", retry.0);
+        }
+        for line in self
+            .data
+            .split("\r\n")
+            .flat_map(|line| line.split(['\r', '\n']))
+        {
+            dst.extend_from_slice(b"data: ");
+            dst.extend_from_slice(line.as_bytes());
+            dst.put_u8(b'\n');
+        }
+        dst.put_u8(b'\n');
+    }
+}
+
+impl From<String> for Event {
+    fn from(data: String) -> Self {
+        Event::new(data)
+    }
+}
+
+impl<'a> From<&'a str> for Event {
+    fn from(data: &'a str) -> Self {
+        Event::new(data)
+    }
+}
+
+fn single_line(s: String) -> String {
+    if s.contains(['\r', '\n']) {
+        s.replace(['\r', '\n'], "")
+    } else {
+        s
+    }
+}
+
+/// Server-sent events stream error
+#[derive(thiserror::Error, Debug)]
+pub enum SseError {
+    /// Content type is not `text/event-stream`
+    #[error("Content type is not text/event-stream")]
+    ContentType,
+    /// Event stream is not utf-8 encoded
+    #[error("Event stream is not utf-8 encoded")]
+    Utf8,
+    /// Event size is larger than allowed limit
+    #[error("Event size is larger than allowed limit: {0}")]
+    Overflow(usize),
+    /// Payload error
+    #[error("Payload error: {0}")]
+    Payload(#[from] PayloadError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn encode(ev: Event) -> String {
+        let mut buf = BytesMut::new();
+        ev.encode(&mut buf);
+        String::from_utf8(buf.to_vec()).unwrap()
+    }
+
+    #[test]
+    fn test_encode() {
+        assert_eq!(encode(Event::new("hello")), "data: hello\n\n");
+        assert_eq!(encode(Event::default()), "data: \n\n");
+        assert_eq!(
+            encode(
+                Event::new("line1\r\nline2\nline3\rline4")
+                    .id("1\n2")
+                    .event("update")
+                    .retry(Millis(1500))
+            ),
+            "id: 12\nevent: update\nretry: 1500\ndata: line1\ndata: line2\ndata: line3\ndata: line4\n\n"
+        );
+
+        let ev = Event::json(&serde_json::json!({"a": 1})).unwrap();
+        assert_eq!(ev.data(), "{\"a\":1}");
+        let val: serde_json::Value = ev.json_data().unwrap();
+        assert_eq!(val["a"], 1);
+
+        let mut ev = Event::from("data").id("1").event("ev");
+        assert_eq!(ev.get_id(), Some("1"));
+        assert_eq!(ev.get_event(), Some("ev"));
+        assert_eq!(ev.get_retry(), None);
+        assert_eq!(ev.take_data(), "data");
+        assert_eq!(ev.data(), "");
+    }
+}
@@ -1,7 +1,7 @@
 use std::task::{Context, Poll};
 use std::{cell::RefCell, future::Future, marker::PhantomData, pin::Pin, rc::Rc};
 
-use crate::http::{Request, Response};
+use crate::http::{Request, StatusCode};
 use crate::router::{Path, ResourceDef, Router};
 use crate::service::boxed::{self, BoxService, BoxServiceFactory};
 use crate::service::{fn_service, PipelineFactory, Service, ServiceFactory, Transform};
@@ -99,7 +99,9 @@ where
         let default = self.default.clone().unwrap_or_else(|| {
             Rc::new(boxed::factory(fn_service(
                 |req: WebRequest<Err>| async move {
-                    Ok(req.into_response(Response::NotFound().finish()))
+                    let req = req.into_parts().0;
+                    let res = Err::unmatched(StatusCode::NOT_FOUND, &req);
+                    Ok(WebResponse::new(res, req))
                 },
             )))
         });
@@ -152,7 +154,9 @@ where
             // create http services
             for (path, factory, guards) in &mut services.iter() {
                 let service = factory.new_service(()).await?;
-                router.rdef(path.clone(), service).2 = guards.borrow_mut().take();
+                router
+                    .rdef(path.clone(), (service, path.pattern().to_string()))
+                    .2 = guards.borrow_mut().take();
             }
 
             let routing = AppRouting {
@@ -232,6 +236,7 @@ where
             inner.head = head;
             inner.payload = payload;
             inner.app_state = self.state.clone();
+            inner.pattern.clear();
             req
         } else {
             HttpRequest::new(
@@ -259,7 +264,7 @@ where
 }
 
 struct AppRouting<Err: ErrorRenderer> {
-    router: Router<HttpService<Err>, Guards>,
+    router: Router<(HttpService<Err>, String), Guards>,
     default: Option<HttpService<Err>>,
 }
 
@@ -285,13 +290,15 @@ impl<Err: ErrorRenderer> Service<WebRequest<Err>> for AppRouting<Err> {
             true
         });
 
-        if let Some((srv, _info)) = res {
+        if let Some(((srv, pattern), _info)) = res {
+            req.push_match_pattern(pattern);
             srv.call(req)
         } else if let Some(ref default) = self.default {
             default.call(req)
         } else {
             let req = req.into_parts().0;
-            Box::pin(async { Ok(WebResponse::new(Response::NotFound().finish(), req)) })
+            let res = Err::unmatched(StatusCode::NOT_FOUND, &req);
+            Box::pin(async { Ok(WebResponse::new(res, req)) })
         }
     }
 }
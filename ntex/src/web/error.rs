@@ -1,5 +1,5 @@
 //! Web error
-use std::{cell::RefCell, fmt, io::Write, marker::PhantomData};
+use std::{borrow::Cow, cell::RefCell, fmt, io, io::Write, marker::PhantomData};
 
 use thiserror::Error;
 
@@ -8,6 +8,7 @@ pub use serde_json::error::Error as JsonError;
 #[cfg(feature = "url")]
 pub use url_pkg::ParseError as UrlParseError;
 
+use super::types::Challenge;
 use super::{HttpRequest, HttpResponse};
 use crate::http::body::Body;
 use crate::http::helpers::Writer;
@@ -15,10 +16,19 @@ use crate::http::{error, header, StatusCode};
 use crate::util::{BytesMut, Either};
 
 pub use super::error_default::{DefaultError, Error};
+pub use super::error_problem::{Problem, ProblemError, ProblemJson};
 pub use crate::http::error::BlockingError;
 
 pub trait ErrorRenderer: Sized + 'static {
     type Container: ErrorContainer;
+
+    /// Generate response for request that does not match any service.
+    ///
+    /// `404 Not Found` is used if there is no matching resource and
+    /// `405 Method Not Allowed` if resource has no matching route.
+    fn unmatched(status: StatusCode, _: &HttpRequest) -> HttpResponse {
+        HttpResponse::build(status).finish()
+    }
 }
 
 pub trait ErrorContainer: error::ResponseError + Sized {
@@ -157,6 +167,262 @@ pub enum QueryPayloadError {
     Deserialize(#[from] serde::de::value::Error),
 }
 
+/// A set of errors that can occur during authentication
+#[derive(Error, Debug)]
+pub enum AuthError {
+    /// Authorization header is not found or uses other scheme
+    #[error("Authorization header is missing")]
+    Missing(Challenge),
+    /// Can not parse Authorization header
+    #[error("Authorization header is invalid")]
+    Invalid(Challenge),
+    /// Credentials are rejected by validator
+    #[error("Credentials are rejected")]
+    Rejected(Challenge),
+}
+
+impl AuthError {
+    /// `WWW-Authenticate` challenge of the error
+    pub fn challenge(&self) -> &Challenge {
+        match self {
+            AuthError::Missing(ref c)
+            | AuthError::Invalid(ref c)
+            | AuthError::Rejected(ref c) => c,
+        }
+    }
+
+    pub(crate) fn set_realm(mut self, realm: Option<Cow<'static, str>>) -> Self {
+        if let Some(realm) = realm {
+            let c = match self {
+                AuthError::Missing(ref mut c)
+                | AuthError::Invalid(ref mut c)
+                | AuthError::Rejected(ref mut c) => c,
+            };
+            *c = c.clone().realm(realm);
+        }
+        self
+    }
+}
+
+/// A set of errors that can occur during typed header extraction
+#[derive(Error, Debug)]
+pub enum HeaderError {
+    /// Header is not found
+    #[error("Header {0} is missing")]
+    Missing(header::HeaderName),
+    /// Can not parse header value
+    #[error("Header {0} is invalid")]
+    Invalid(header::HeaderName),
+}
+
+/// Field validation error
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize)]
+pub struct FieldError {
+    /// Field name
+    pub field: Cow<'static, str>,
+    /// Failed constraint, like `length` or `email`
+    pub code: Cow<'static, str>,
+    /// Error message
+    pub message: Cow<'static, str>,
+}
+
+/// Validation errors of extracted value
+#[derive(Error, Debug, Clone, Default, PartialEq, Eq)]
+pub struct ValidationError {
+    fields: Vec<FieldError>,
+}
+
+impl ValidationError {
+    /// Create empty validation error
+    pub fn new() -> Self {
+        ValidationError::default()
+    }
+
+    /// Add field error
+    pub fn add<F, C, M>(&mut self, field: F, code: C, message: M)
+    where
+        F: Into<Cow<'static, str>>,
+        C: Into<Cow<'static, str>>,
+        M: Into<Cow<'static, str>>,
+    {
+        self.fields.push(FieldError {
+            field: field.into(),
+            code: code.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Field errors
+    pub fn fields(&self) -> &[FieldError] {
+        &self.fields
+    }
+
+    /// Check if there are no field errors
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// `Ok(())` if there are no field errors
+    pub fn into_result(self) -> Result<(), Self> {
+        if self.is_empty() {
+            Ok(())
+        } else {
+            Err(self)
+        }
+    }
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Validation failed")?;
+        for (idx, err) in self.fields.iter().enumerate() {
+            let sep = if idx == 0 { ": " } else { ", " };
+            write!(f, "{}{} {}", sep, err.field, err.message)?;
+        }
+        Ok(())
+    }
+}
+
+/// A set of errors that can occur during content negotiation
+#[derive(Error, Debug)]
+pub enum NegotiationError {
+    /// None of available representations is acceptable
+    #[error("None of available representations is acceptable")]
+    NotAcceptable,
+    /// Can not serialize response value
+    #[error("Serialization error: {0}")]
+    Serialize(Box<dyn std::error::Error>),
+}
+
+/// A set of errors that can occur during parsing multipart payloads
+#[derive(Error, Debug)]
+pub enum MultipartError {
+    /// Content-Type header is not found
+    #[error("No Content-type header found")]
+    NoContentType,
+    /// Can not parse Content-Type header
+    #[error("Can not parse Content-Type header")]
+    ParseContentType,
+    /// Multipart boundary is not found or malformed
+    #[error("Multipart boundary is not found")]
+    Boundary,
+    /// Can not parse field headers
+    #[error("Can not parse multipart field headers")]
+    Headers,
+    /// Multipart stream is incomplete
+    #[error("Multipart stream is incomplete")]
+    Incomplete,
+    /// Payload size is bigger than allowed. (default: 8Mb)
+    #[error("Multipart payload size is bigger than allowed (limit: {limit} bytes)")]
+    Overflow { limit: usize },
+    /// Field size is bigger than allowed. (default: 4Mb)
+    #[error("Multipart field size is bigger than allowed (limit: {limit} bytes)")]
+    FieldOverflow { limit: usize },
+    /// Payload error
+    #[error("Error that occur during reading payload: {0}")]
+    Payload(#[from] error::PayloadError),
+}
+
+/// A set of errors that can occur during CORS request processing
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CorsError {
+    /// Request origin is not allowed
+    #[error("Origin is not allowed to make this request")]
+    OriginNotAllowed,
+    /// `Access-Control-Request-Method` header is malformed
+    #[error("Access-Control-Request-Method header is malformed")]
+    BadRequestMethod,
+    /// Requested method is not allowed
+    #[error("Requested method is not allowed")]
+    MethodNotAllowed,
+    /// `Access-Control-Request-Headers` header is malformed
+    #[error("Access-Control-Request-Headers header is malformed")]
+    BadRequestHeaders,
+    /// One or more requested headers are not allowed
+    #[error("One or more requested headers are not allowed")]
+    HeadersNotAllowed,
+}
+
+/// Handler panicked during request processing
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+#[error("Request handler panicked")]
+pub struct PanicError;
+
+/// Request processing timeout error
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ServiceTimeoutError {
+    /// Request is not processed in time, respond with gateway timeout
+    #[error("Request processing timed out")]
+    GatewayTimeout,
+    /// Request is not processed in time, respond with service unavailable
+    #[error("Request processing timed out")]
+    ServiceUnavailable,
+}
+
+/// A set of errors that can occur during rate limiting
+#[derive(Error, Debug)]
+pub enum RateLimitError {
+    /// Request rate limit is exceeded
+    #[error("Too many requests")]
+    Limited,
+    /// Rate limit store error
+    #[error("Rate limit store error: {0}")]
+    Store(Box<dyn std::error::Error>),
+}
+
+#[cfg(feature = "cookie")]
+/// A set of errors that can occur during session processing
+#[derive(Error, Debug)]
+pub enum SessionError {
+    /// Can not serialize or deserialize session value
+    #[error("Session value serialization error: {0}")]
+    Serialize(#[from] serde_json::Error),
+    /// Serialized session state is bigger than allowed
+    #[error("Session state size is bigger than allowed (limit: {limit} bytes)")]
+    Overflow { limit: usize },
+    /// Session store error
+    #[error("Session store error: {0}")]
+    Store(Box<dyn std::error::Error>),
+}
+
+#[cfg(feature = "cookie")]
+/// A set of errors that can occur during CSRF protection
+#[derive(Error, Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CsrfError {
+    /// Origin of unsafe request is not allowed
+    #[error("Cross-origin request")]
+    Origin,
+    /// Token is not submitted or not issued
+    #[error("CSRF token is missing")]
+    MissingToken,
+    /// Submitted token does not match issued token
+    #[error("CSRF token is invalid")]
+    InvalidToken,
+}
+
+/// A set of errors that can occur during serving static files
+#[derive(Error, Debug)]
+pub enum FilesError {
+    /// Request path is malformed or points outside of served directory
+    #[error("Request path is invalid")]
+    InvalidPath,
+    /// Only `GET` and `HEAD` methods are allowed
+    #[error("Request method is not allowed")]
+    MethodNotAllowed,
+    /// Request path points to a directory
+    #[error("Request path points to a directory")]
+    IsDirectory,
+    /// Request path points to a hidden file or directory
+    #[error("Request path points to a hidden file")]
+    Hidden,
+    /// Blocking task is canceled
+    #[error("Thread pool is gone")]
+    Canceled,
+    /// Io error
+    #[error("{0}")]
+    Io(#[from] io::Error),
+}
+
 #[derive(Error, Debug)]
 pub enum PayloadError {
     /// Http error.
@@ -850,6 +1116,39 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_header_error() {
+        let req = TestRequest::default().to_http_request();
+        let err = HeaderError::Missing(header::AGE);
+        assert_eq!(err.to_string(), "Header age is missing");
+        let resp: HttpResponse =
+            WebResponseError::<DefaultError>::error_response(&err, &req);
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+        let err = HeaderError::Invalid(header::AGE);
+        assert_eq!(
+            WebResponseError::<DefaultError>::status_code(&err),
+            StatusCode::BAD_REQUEST
+        );
+    }
+
+    #[test]
+    fn test_negotiation_error() {
+        let req = TestRequest::default().to_http_request();
+        let err = NegotiationError::NotAcceptable;
+        let resp: HttpResponse =
+            WebResponseError::<DefaultError>::error_response(&err, &req);
+        assert_eq!(resp.status(), StatusCode::NOT_ACCEPTABLE);
+        let err = NegotiationError::Serialize(Box::new(fmt::Error));
+        assert_eq!(
+            err.to_string(),
+            "Serialization error: an error occurred when formatting an argument"
+        );
+        assert_eq!(
+            WebResponseError::<DefaultError>::status_code(&err),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+    }
+
     #[test]
     fn test_path_error() {
         let req = TestRequest::default().to_http_request();
@@ -156,7 +156,11 @@ impl WebResponseError<DefaultError> for error::UrlGenerationError {}
 impl WebResponseError<DefaultError> for error::UrlencodedError {
     fn status_code(&self) -> StatusCode {
         match *self {
-            error::UrlencodedError::Overflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            error::UrlencodedError::Overflow { .. }
+            | error::UrlencodedError::Payload(http::error::PayloadError::Overflow)
+            | error::UrlencodedError::Payload(http::error::PayloadError::DecodedOverflow) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
             error::UrlencodedError::UnknownLength => StatusCode::LENGTH_REQUIRED,
             _ => StatusCode::BAD_REQUEST,
         }
@@ -167,12 +171,115 @@ impl WebResponseError<DefaultError> for error::UrlencodedError {
 impl WebResponseError<DefaultError> for error::JsonPayloadError {
     fn status_code(&self) -> StatusCode {
         match *self {
-            error::JsonPayloadError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            error::JsonPayloadError::Overflow
+            | error::JsonPayloadError::Payload(http::error::PayloadError::Overflow)
+            | error::JsonPayloadError::Payload(
+                http::error::PayloadError::DecodedOverflow,
+            ) => StatusCode::PAYLOAD_TOO_LARGE,
             _ => StatusCode::BAD_REQUEST,
         }
     }
 }
 
+/// `MultipartError` returns following results:
+///
+/// - `Overflow` and `FieldOverflow` return `PayloadTooLarge`
+/// - Other errors return `BadRequest`
+impl WebResponseError<DefaultError> for error::MultipartError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::MultipartError::Overflow { .. }
+            | error::MultipartError::FieldOverflow { .. } => StatusCode::PAYLOAD_TOO_LARGE,
+            error::MultipartError::Payload(ref e) => {
+                WebResponseError::<DefaultError>::status_code(e)
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
+/// `CorsError` returns following results:
+///
+/// - Malformed preflight request headers return `BadRequest`
+/// - Other errors return `Forbidden`
+impl WebResponseError<DefaultError> for error::CorsError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::CorsError::BadRequestMethod | error::CorsError::BadRequestHeaders => {
+                StatusCode::BAD_REQUEST
+            }
+            _ => StatusCode::FORBIDDEN,
+        }
+    }
+}
+
+/// Return `InternalServerError` for `PanicError`
+impl WebResponseError<DefaultError> for error::PanicError {}
+
+/// `ServiceTimeoutError` returns following results:
+///
+/// - `GatewayTimeout` returns `GatewayTimeout`
+/// - `ServiceUnavailable` returns `ServiceUnavailable`
+impl WebResponseError<DefaultError> for error::ServiceTimeoutError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::ServiceTimeoutError::GatewayTimeout => StatusCode::GATEWAY_TIMEOUT,
+            error::ServiceTimeoutError::ServiceUnavailable => {
+                StatusCode::SERVICE_UNAVAILABLE
+            }
+        }
+    }
+}
+
+/// `RateLimitError` returns following results:
+///
+/// - `Limited` returns `TooManyRequests`
+/// - `Store` returns `InternalServerError`
+impl WebResponseError<DefaultError> for error::RateLimitError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::RateLimitError::Limited => StatusCode::TOO_MANY_REQUESTS,
+            error::RateLimitError::Store(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+#[cfg(feature = "cookie")]
+/// Return `InternalServerError` for `SessionError`
+impl WebResponseError<DefaultError> for error::SessionError {}
+
+#[cfg(feature = "cookie")]
+/// Return `Forbidden` for `CsrfError`
+impl WebResponseError<DefaultError> for error::CsrfError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::FORBIDDEN
+    }
+}
+
+/// `FilesError` returns following results:
+///
+/// - `InvalidPath` returns `BadRequest`
+/// - `MethodNotAllowed` returns `MethodNotAllowed`
+/// - `IsDirectory`, `Hidden` and not found io errors return `NotFound`
+/// - Permission denied io errors return `Forbidden`
+impl WebResponseError<DefaultError> for error::FilesError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::FilesError::InvalidPath => StatusCode::BAD_REQUEST,
+            error::FilesError::MethodNotAllowed => StatusCode::METHOD_NOT_ALLOWED,
+            error::FilesError::IsDirectory | error::FilesError::Hidden => {
+                StatusCode::NOT_FOUND
+            }
+            error::FilesError::Canceled => StatusCode::INTERNAL_SERVER_ERROR,
+            error::FilesError::Io(ref e) => match e.kind() {
+                io::ErrorKind::NotFound => StatusCode::NOT_FOUND,
+                io::ErrorKind::PermissionDenied => StatusCode::FORBIDDEN,
+                _ => StatusCode::INTERNAL_SERVER_ERROR,
+            },
+        }
+    }
+}
+
 /// Error renderer for `PathError`
 impl WebResponseError<DefaultError> for error::PathError {
     fn status_code(&self) -> StatusCode {
@@ -187,20 +294,76 @@ impl WebResponseError<DefaultError> for error::QueryPayloadError {
     }
 }
 
-impl WebResponseError<DefaultError> for error::PayloadError {
+/// `AuthError` returns `Unauthorized` with `WWW-Authenticate` challenge
+impl WebResponseError<DefaultError> for error::AuthError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNAUTHORIZED
+    }
+
+    fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+        HttpResponse::Unauthorized()
+            .header(header::WWW_AUTHENTICATE, self.challenge().to_string())
+            .content_type("text/plain; charset=utf-8")
+            .body(self.to_string())
+    }
+}
+
+/// Error renderer `HeaderError`
+impl WebResponseError<DefaultError> for error::HeaderError {
     fn status_code(&self) -> StatusCode {
         StatusCode::BAD_REQUEST
     }
 }
 
+/// `NegotiationError` returns following results:
+///
+/// - `NotAcceptable` returns `NotAcceptable`
+/// - `Serialize` returns `InternalServerError`
+impl WebResponseError<DefaultError> for error::NegotiationError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::NegotiationError::NotAcceptable => StatusCode::NOT_ACCEPTABLE,
+            error::NegotiationError::Serialize(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Return `UnprocessableEntity` with json list of field errors
+/// for `ValidationError`
+impl WebResponseError<DefaultError> for error::ValidationError {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNPROCESSABLE_ENTITY
+    }
+
+    fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+        HttpResponse::UnprocessableEntity().json(&serde_json::json!({
+            "message": "Validation failed",
+            "errors": self.fields(),
+        }))
+    }
+}
+
+impl WebResponseError<DefaultError> for error::PayloadError {
+    fn status_code(&self) -> StatusCode {
+        match *self {
+            error::PayloadError::Payload(http::error::PayloadError::Overflow)
+            | error::PayloadError::Payload(http::error::PayloadError::DecodedOverflow) => {
+                StatusCode::PAYLOAD_TOO_LARGE
+            }
+            _ => StatusCode::BAD_REQUEST,
+        }
+    }
+}
+
 /// `PayloadError` returns two possible results:
 ///
-/// - `Overflow` returns `PayloadTooLarge`
+/// - `Overflow` and `DecodedOverflow` return `PayloadTooLarge`
 /// - Other errors returns `BadRequest`
 impl WebResponseError<DefaultError> for http::error::PayloadError {
     fn status_code(&self) -> StatusCode {
         match *self {
-            http::error::PayloadError::Overflow => StatusCode::PAYLOAD_TOO_LARGE,
+            http::error::PayloadError::Overflow
+            | http::error::PayloadError::DecodedOverflow => StatusCode::PAYLOAD_TOO_LARGE,
             _ => StatusCode::BAD_REQUEST,
         }
     }
@@ -0,0 +1,541 @@
+//! Problem details error renderer, rfc7807
+use std::{borrow::Cow, convert::Infallible, fmt, io, str::Utf8Error};
+
+use serde::de::value::Error as DeError;
+use serde::Serialize;
+use serde_json::error::Error as JsonError;
+use serde_json::{Map, Value};
+use serde_urlencoded::ser::Error as FormError;
+
+use crate::http::body::Body;
+use crate::http::{self, header, StatusCode};
+use crate::util::timeout::TimeoutError;
+use crate::ws::error::HandshakeError;
+
+use super::error::{self, ErrorContainer, ErrorRenderer, WebResponseError};
+use super::{DefaultError, HttpRequest, HttpResponse};
+
+const CONTENT_TYPE: &str = "application/problem+json";
+
+/// Problem details error renderer, rfc7807.
+///
+/// Errors are rendered as `application/problem+json` documents with `type`,
+/// `title`, `status` and `detail` members. Built-in errors use status code
+/// of `DefaultError` renderer and error message as `detail`, responses for
+/// unmatched requests (`404 Not Found` and `405 Method Not Allowed`) are
+/// rendered as problem documents as well.
+///
+/// Custom errors implement `WebResponseError<ProblemJson>`. If error
+/// response is not a problem document, response body is replaced with
+/// problem document for response status, status code and headers are
+/// preserved. Use `Problem` to specify problem type and extension members.
+///
+/// ```rust
+/// use std::fmt;
+///
+/// use ntex::http::StatusCode;
+/// use ntex::web::error::{Problem, ProblemJson, WebResponseError};
+/// use ntex::web::{self, App, HttpRequest, HttpResponse};
+///
+/// #[derive(Debug)]
+/// struct OutOfCredit {
+///     balance: u32,
+/// }
+///
+/// impl fmt::Display for OutOfCredit {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         write!(f, "Your current balance is {}", self.balance)
+///     }
+/// }
+///
+/// impl WebResponseError<ProblemJson> for OutOfCredit {
+///     fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+///         Problem::new(StatusCode::FORBIDDEN)
+///             .problem_type("https://example.com/probs/out-of-credit")
+///             .title("You do not have enough credit")
+///             .detail(self.to_string())
+///             .extension("balance", self.balance)
+///             .into_response()
+///     }
+/// }
+///
+/// async fn index() -> Result<HttpResponse, OutOfCredit> {
+///     Err(OutOfCredit { balance: 30 })
+/// }
+///
+/// fn main() {
+///     let app = App::with(ProblemJson).service(web::resource("/").to(index));
+/// }
+/// ```
+#[derive(Clone, Copy, Default, Debug)]
+pub struct ProblemJson;
+
+impl ErrorRenderer for ProblemJson {
+    type Container = ProblemError;
+
+    fn unmatched(status: StatusCode, _: &HttpRequest) -> HttpResponse {
+        Problem::new(status).into_response()
+    }
+}
+
+/// Problem details document
+#[derive(Debug, Clone, Serialize)]
+pub struct Problem {
+    #[serde(rename = "type")]
+    problem_type: Cow<'static, str>,
+    title: Cow<'static, str>,
+    status: u16,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    detail: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instance: Option<String>,
+    #[serde(flatten)]
+    extensions: Map<String, Value>,
+}
+
+impl Problem {
+    /// Create problem document for status code.
+    ///
+    /// Problem type is `about:blank` and title is canonical reason
+    /// of the status code.
+    pub fn new(status: StatusCode) -> Self {
+        Problem {
+            problem_type: Cow::Borrowed("about:blank"),
+            title: Cow::Borrowed(status.canonical_reason().unwrap_or("Unknown")),
+            status: status.as_u16(),
+            detail: None,
+            instance: None,
+            extensions: Map::new(),
+        }
+    }
+
+    /// Set problem type uri
+    pub fn problem_type<T: Into<Cow<'static, str>>>(mut self, uri: T) -> Self {
+        self.problem_type = uri.into();
+        self
+    }
+
+    /// Set short summary of the problem type
+    pub fn title<T: Into<Cow<'static, str>>>(mut self, title: T) -> Self {
+        self.title = title.into();
+        self
+    }
+
+    /// Set explanation of this occurrence of the problem
+    pub fn detail<T: Into<String>>(mut self, detail: T) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+
+    /// Set uri of this occurrence of the problem
+    pub fn instance<T: Into<String>>(mut self, instance: T) -> Self {
+        self.instance = Some(instance.into());
+        self
+    }
+
+    /// Add extension member
+    pub fn extension<V: Into<Value>>(mut self, name: &str, value: V) -> Self {
+        self.extensions.insert(name.to_string(), value.into());
+        self
+    }
+
+    /// Status code
+    pub fn status(&self) -> StatusCode {
+        StatusCode::from_u16(self.status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+    }
+
+    /// Convert problem document to a response
+    pub fn into_response(self) -> HttpResponse {
+        let res = HttpResponse::new(self.status());
+        self.render(res)
+    }
+
+    /// Replace response body with problem document
+    fn render(&self, mut res: HttpResponse) -> HttpResponse {
+        let body = match serde_json::to_vec(self) {
+            Ok(body) => body,
+            Err(e) => {
+                log::error!("Cannot serialize problem document: {}", e);
+                return res;
+            }
+        };
+        res.headers_mut().insert(
+            header::CONTENT_TYPE,
+            header::HeaderValue::from_static(CONTENT_TYPE),
+        );
+        res.set_body(Body::from(body))
+    }
+}
+
+/// Error container for errors that supports `ProblemJson` renderer.
+///
+/// Built-in errors are rendered by `DefaultError` renderer and converted
+/// to problem documents.
+#[derive(thiserror::Error)]
+pub struct ProblemError {
+    cause: Cause,
+}
+
+enum Cause {
+    Default(Box<dyn WebResponseError<DefaultError>>),
+    Problem(Box<dyn WebResponseError<ProblemJson>>),
+}
+
+impl ProblemError {
+    pub fn new<T: WebResponseError<ProblemJson> + 'static>(err: T) -> ProblemError {
+        ProblemError {
+            cause: Cause::Problem(Box::new(err)),
+        }
+    }
+
+    fn default<T: WebResponseError<DefaultError> + 'static>(err: T) -> ProblemError {
+        ProblemError {
+            cause: Cause::Default(Box::new(err)),
+        }
+    }
+
+    /// Response's status code
+    pub fn status_code(&self) -> StatusCode {
+        match self.cause {
+            Cause::Default(ref e) => e.status_code(),
+            Cause::Problem(ref e) => e.status_code(),
+        }
+    }
+}
+
+/// `ProblemError` for any error which implements `WebResponseError<ProblemJson>`
+impl<T: WebResponseError<ProblemJson>> From<T> for ProblemError {
+    fn from(err: T) -> Self {
+        ProblemError::new(err)
+    }
+}
+
+impl ErrorContainer for ProblemError {
+    fn error_response(&self, req: &HttpRequest) -> HttpResponse {
+        let res = match self.cause {
+            Cause::Default(ref e) => e.error_response(req),
+            Cause::Problem(ref e) => e.error_response(req),
+        };
+        let is_problem = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .and_then(|ct| ct.to_str().ok())
+            .map(|ct| ct.starts_with(CONTENT_TYPE))
+            .unwrap_or(false);
+        if is_problem {
+            res
+        } else {
+            Problem::new(res.status())
+                .detail(self.to_string())
+                .render(res)
+        }
+    }
+}
+
+impl crate::http::error::ResponseError for ProblemError {
+    fn error_response(&self) -> HttpResponse {
+        Problem::new(self.status_code())
+            .detail(self.to_string())
+            .into_response()
+    }
+}
+
+impl fmt::Display for ProblemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cause {
+            Cause::Default(ref e) => fmt::Display::fmt(e, f),
+            Cause::Problem(ref e) => fmt::Display::fmt(e, f),
+        }
+    }
+}
+
+impl fmt::Debug for ProblemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.cause {
+            Cause::Default(ref e) => write!(f, "web::ProblemError({:?})", e),
+            Cause::Problem(ref e) => write!(f, "web::ProblemError({:?})", e),
+        }
+    }
+}
+
+/// Render built-in errors with status code and headers of `DefaultError` renderer
+macro_rules! default_problem {
+    ($($(#[$attr:meta])* $name:ty),* $(,)?) => {
+        $(
+            $(#[$attr])*
+            impl From<$name> for ProblemError {
+                fn from(err: $name) -> Self {
+                    ProblemError::default(err)
+                }
+            }
+        )*
+    };
+}
+
+default_problem!(
+    error::DataExtractorError,
+    error::UrlGenerationError,
+    error::UrlencodedError,
+    error::JsonPayloadError,
+    error::PathError,
+    error::QueryPayloadError,
+    error::MultipartError,
+    error::AuthError,
+    error::HeaderError,
+    error::NegotiationError,
+    error::CorsError,
+    error::PanicError,
+    error::ServiceTimeoutError,
+    error::RateLimitError,
+    #[cfg(feature = "cookie")]
+    error::SessionError,
+    #[cfg(feature = "cookie")]
+    error::CsrfError,
+    error::FilesError,
+    error::PayloadError,
+    http::error::PayloadError,
+    http::error::ContentTypeError,
+    http::error::Canceled,
+    http::error::HttpError,
+    http::client::error::SendRequestError,
+    #[cfg(feature = "cookie")]
+    coo_kie::ParseError,
+    #[cfg(feature = "openssl")]
+    tls_openssl::ssl::Error,
+    JsonError,
+    FormError,
+    DeError,
+    Utf8Error,
+    io::Error,
+    HandshakeError,
+);
+
+/// Field errors of `ValidationError` are rendered as `errors` extension member
+impl From<error::ValidationError> for ProblemError {
+    fn from(err: error::ValidationError) -> Self {
+        ProblemError::new(Validation(err))
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+#[error(transparent)]
+struct Validation(error::ValidationError);
+
+impl WebResponseError<ProblemJson> for Validation {
+    fn status_code(&self) -> StatusCode {
+        StatusCode::UNPROCESSABLE_ENTITY
+    }
+
+    fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+        let errors = serde_json::to_value(self.0.fields()).unwrap_or(Value::Null);
+        Problem::new(StatusCode::UNPROCESSABLE_ENTITY)
+            .detail("Validation failed")
+            .extension("errors", errors)
+            .into_response()
+    }
+}
+
+/// Return `GATEWAY_TIMEOUT` for `TimeoutError`
+impl<E: Into<ProblemError>> From<TimeoutError<E>> for ProblemError {
+    fn from(err: TimeoutError<E>) -> Self {
+        match err {
+            TimeoutError::Service(e) => e.into(),
+            TimeoutError::Timeout => {
+                ProblemError::default(TimeoutError::<Infallible>::Timeout)
+            }
+        }
+    }
+}
+
+/// `InternalServerError` for `BlockingError`
+impl<E: fmt::Debug + 'static> From<crate::http::error::BlockingError<E>> for ProblemError {
+    fn from(err: crate::http::error::BlockingError<E>) -> Self {
+        ProblemError::default(err)
+    }
+}
+
+#[cfg(feature = "openssl")]
+/// `InternalServerError` for `openssl::ssl::HandshakeError`
+impl<T: fmt::Debug + 'static> From<tls_openssl::ssl::HandshakeError<T>> for ProblemError {
+    fn from(err: tls_openssl::ssl::HandshakeError<T>) -> Self {
+        ProblemError::default(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::Bytes;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, types, App};
+
+    #[derive(serde::Deserialize)]
+    struct Info {
+        id: u32,
+    }
+
+    async fn index(info: Info) -> String {
+        info.id.to_string()
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    #[error("Not enough credit")]
+    struct OutOfCredit;
+
+    impl WebResponseError<ProblemJson> for OutOfCredit {
+        fn error_response(&self, _: &HttpRequest) -> HttpResponse {
+            Problem::new(StatusCode::FORBIDDEN)
+                .problem_type("https://example.com/probs/out-of-credit")
+                .title("You do not have enough credit")
+                .instance("/account/12345")
+                .extension("balance", 30)
+                .into_response()
+        }
+    }
+
+    #[derive(thiserror::Error, Debug)]
+    #[error("Conflict")]
+    struct Conflict;
+
+    impl WebResponseError<ProblemJson> for Conflict {
+        fn status_code(&self) -> StatusCode {
+            StatusCode::CONFLICT
+        }
+    }
+
+    async fn body(res: web::WebResponse) -> Value {
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+        serde_json::from_slice(&read_body(res).await).unwrap()
+    }
+
+    #[crate::rt_test]
+    async fn test_problem_json() {
+        let srv = init_service(
+            App::with(ProblemJson)
+                .service(web::resource("/json").route(
+                    web::post().to(|info: types::Json<Info>| index(info.into_inner())),
+                ))
+                .service(
+                    web::resource("/path/{id}")
+                        .to(|info: types::Path<Info>| index(info.into_inner())),
+                )
+                .service(
+                    web::resource("/query")
+                        .to(|info: types::Query<Info>| index(info.into_inner())),
+                )
+                .service(
+                    web::resource("/credit")
+                        .to(|| async { Err::<HttpResponse, _>(OutOfCredit) }),
+                )
+                .service(
+                    web::resource("/conflict")
+                        .to(|| async { Err::<HttpResponse, _>(Conflict) }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/json")
+            .method(http::Method::POST)
+            .header(header::CONTENT_TYPE, "application/json")
+            .header(header::CONTENT_LENGTH, "7")
+            .set_payload(Bytes::from_static(b"{\"id\":"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        let doc = body(res).await;
+        assert_eq!(doc["type"], "about:blank");
+        assert_eq!(doc["title"], "Bad Request");
+        assert_eq!(doc["status"], 400);
+        assert!(doc["detail"]
+            .as_str()
+            .unwrap()
+            .starts_with("Json deserialize error"));
+
+        let req = TestRequest::with_uri("/path/abc").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        assert_eq!(body(res).await["status"], 404);
+
+        let req = TestRequest::with_uri("/query?id=abc").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+        assert!(body(res).await["detail"]
+            .as_str()
+            .unwrap()
+            .starts_with("Query deserialize error"));
+
+        // unmatched requests
+        let req = TestRequest::with_uri("/unknown").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+        let doc = body(res).await;
+        assert_eq!(doc["title"], "Not Found");
+        assert!(doc.get("detail").is_none());
+
+        let req = TestRequest::with_uri("/json").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+        assert_eq!(body(res).await["status"], 405);
+
+        // custom errors
+        let req = TestRequest::with_uri("/credit").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        let doc = body(res).await;
+        assert_eq!(doc["type"], "https://example.com/probs/out-of-credit");
+        assert_eq!(doc["title"], "You do not have enough credit");
+        assert_eq!(doc["instance"], "/account/12345");
+        assert_eq!(doc["balance"], 30);
+
+        let req = TestRequest::with_uri("/conflict").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::CONFLICT);
+        let doc = body(res).await;
+        assert_eq!(doc["title"], "Conflict");
+        assert_eq!(doc["detail"], "Conflict");
+    }
+
+    #[test]
+    fn test_problem_error() {
+        let req = TestRequest::default().to_http_request();
+
+        // headers of default renderer are preserved
+        let err = ProblemError::from(error::AuthError::Missing(
+            types::Challenge::new("Basic").realm("test"),
+        ));
+        let res = err.error_response(&req);
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Basic realm=\"test\""
+        );
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+
+        let err = ProblemError::from(TimeoutError::<Conflict>::Timeout);
+        assert_eq!(err.status_code(), StatusCode::GATEWAY_TIMEOUT);
+        let res = crate::http::error::ResponseError::error_response(&err);
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(
+            format!("{:?}", err),
+            "web::ProblemError(TimeoutError::Timeout)"
+        );
+
+        let err = ProblemError::from(TimeoutError::Service(Conflict));
+        assert_eq!(err.status_code(), StatusCode::CONFLICT);
+        assert_eq!(format!("{:?}", err), "web::ProblemError(Conflict)");
+
+        let problem = Problem::new(StatusCode::IM_A_TEAPOT).detail("short and stout");
+        assert_eq!(problem.status(), StatusCode::IM_A_TEAPOT);
+        assert_eq!(
+            serde_json::to_string(&problem).unwrap(),
+            "{\"type\":\"about:blank\",\"title\":\"I'm a teapot\",\"status\":418,\
+             \"detail\":\"short and stout\"}"
+        );
+    }
+}
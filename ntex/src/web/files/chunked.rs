@@ -0,0 +1,118 @@
+use std::io::{self, Read, Seek};
+use std::task::{Context, Poll};
+use std::{cmp, error::Error, fmt, fs::File, future::Future, pin::Pin};
+
+use crate::http::error::BlockingError;
+use crate::rt::{spawn_blocking, JoinHandle};
+use crate::util::{ready, Bytes, Stream};
+
+const CHUNK_SIZE: u64 = 65_536;
+
+/// Stream of file chunks
+///
+/// File is read on a blocking thread pool, one chunk at a time.
+pub struct ChunkedReadFile {
+    size: u64,
+    offset: u64,
+    file: Option<File>,
+    fut: Option<JoinHandle<io::Result<(File, Bytes)>>>,
+}
+
+impl ChunkedReadFile {
+    /// Create stream for `size` bytes of the file starting at `offset`
+    pub fn new(file: File, offset: u64, size: u64) -> Self {
+        ChunkedReadFile {
+            size,
+            offset,
+            file: Some(file),
+            fut: None,
+        }
+    }
+}
+
+impl fmt::Debug for ChunkedReadFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ChunkedReadFile")
+            .field("size", &self.size)
+            .field("offset", &self.offset)
+            .finish()
+    }
+}
+
+impl Stream for ChunkedReadFile {
+    type Item = Result<Bytes, Box<dyn Error>>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        loop {
+            if let Some(ref mut fut) = self.fut {
+                let result = ready!(Pin::new(fut).poll(cx));
+                self.fut = None;
+
+                return match result {
+                    Ok(Ok((file, chunk))) => {
+                        self.file = Some(file);
+                        self.offset += chunk.len() as u64;
+                        self.size -= chunk.len() as u64;
+                        Poll::Ready(Some(Ok(chunk)))
+                    }
+                    Ok(Err(e)) => Poll::Ready(Some(Err(e.into()))),
+                    Err(_) => Poll::Ready(Some(Err(Box::new(
+                        BlockingError::<io::Error>::Canceled,
+                    )))),
+                };
+            }
+
+            if self.size == 0 {
+                return Poll::Ready(None);
+            }
+            let mut file = if let Some(file) = self.file.take() {
+                file
+            } else {
+                return Poll::Ready(None);
+            };
+
+            let offset = self.offset;
+            let max = cmp::min(self.size, CHUNK_SIZE);
+            self.fut = Some(spawn_blocking(move || {
+                file.seek(io::SeekFrom::Start(offset))?;
+
+                let mut buf = Vec::with_capacity(max as usize);
+                (&mut file).take(max).read_to_end(&mut buf)?;
+                if buf.is_empty() {
+                    Err(io::ErrorKind::UnexpectedEof.into())
+                } else {
+                    Ok((file, Bytes::from(buf)))
+                }
+            }));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::load_stream;
+
+    #[crate::rt_test]
+    async fn test_chunked() {
+        let data = std::fs::read("Cargo.toml").unwrap();
+
+        let file = File::open("Cargo.toml").unwrap();
+        let size = data.len() as u64;
+        let stream = ChunkedReadFile::new(file, 0, size);
+        assert!(format!("{:?}", stream).contains("ChunkedReadFile"));
+        assert_eq!(load_stream(stream).await.unwrap(), data);
+
+        let file = File::open("Cargo.toml").unwrap();
+        let stream = ChunkedReadFile::new(file, 10, 20);
+        assert_eq!(load_stream(stream).await.unwrap(), data[10..30]);
+
+        // file is shorter than expected
+        let file = File::open("Cargo.toml").unwrap();
+        let stream = ChunkedReadFile::new(file, size - 5, 10);
+        assert!(load_stream(stream).await.is_err());
+    }
+}
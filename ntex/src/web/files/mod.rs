@@ -0,0 +1,426 @@
+//! Static files support
+use std::task::{Context, Poll};
+use std::{future::Future, path::PathBuf, pin::Pin, rc::Rc};
+
+use percent_encoding::percent_decode_str;
+
+use crate::http::Method;
+use crate::router::ResourceDef;
+use crate::service::{Service, ServiceFactory};
+use crate::util::Ready;
+use crate::web::dev::{WebServiceConfig, WebServiceFactory};
+use crate::web::error::{ErrorRenderer, FilesError};
+use crate::web::{WebRequest, WebResponse};
+
+mod chunked;
+mod named;
+
+pub use self::chunked::ChunkedReadFile;
+pub use self::named::NamedFile;
+
+/// Static files handling service
+///
+/// `Files` service must be registered with `App::service()` method.
+/// Mount path is used as a prefix, rest of the request path is resolved
+/// relative to the served directory. Files are read on a blocking
+/// thread pool. Hidden files and directories, names starting with `.`,
+/// are not served by default.
+///
+/// ```rust
+/// use ntex::web::{files, App};
+///
+/// fn main() {
+///     let app = App::new()
+///         .service(files::Files::new("/static", ".").index_file("index.html"));
+/// }
+/// ```
+pub struct Files {
+    path: String,
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    directory: PathBuf,
+    index: Option<String>,
+    use_etag: bool,
+    use_last_modified: bool,
+    show_hidden: bool,
+    #[cfg(feature = "compress")]
+    precompressed: bool,
+}
+
+impl Files {
+    /// Create new `Files` instance for specified base directory.
+    ///
+    /// `path` - the url path prefix, `dir` - the base directory to serve
+    /// files from.
+    pub fn new<T: Into<PathBuf>>(path: &str, dir: T) -> Files {
+        let dir = dir.into();
+        let directory = dir.canonicalize().unwrap_or_else(|e| {
+            log::error!("Specified path is not a directory: {:?}: {}", dir, e);
+            dir
+        });
+
+        Files {
+            path: path.trim_end_matches('/').to_string(),
+            inner: Rc::new(Inner {
+                directory,
+                index: None,
+                use_etag: true,
+                use_last_modified: true,
+                show_hidden: false,
+                #[cfg(feature = "compress")]
+                precompressed: false,
+            }),
+        }
+    }
+
+    fn inner(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Set index file
+    ///
+    /// Index file is served for directory requests, otherwise directory
+    /// requests return `NotFound`.
+    pub fn index_file<T: Into<String>>(mut self, index: T) -> Self {
+        self.inner().index = Some(index.into());
+        self
+    }
+
+    /// Specifies whether to use `ETag` or not.
+    ///
+    /// Default is true.
+    pub fn use_etag(mut self, value: bool) -> Self {
+        self.inner().use_etag = value;
+        self
+    }
+
+    /// Specifies whether to use `Last-Modified` or not.
+    ///
+    /// Default is true.
+    pub fn use_last_modified(mut self, value: bool) -> Self {
+        self.inner().use_last_modified = value;
+        self
+    }
+
+    /// Specifies whether to serve hidden files and directories.
+    ///
+    /// Names of hidden files start with `.`, like `.git` or `.env`.
+    /// Default is false.
+    pub fn show_hidden_files(mut self, value: bool) -> Self {
+        self.inner().show_hidden = value;
+        self
+    }
+
+    #[cfg(feature = "compress")]
+    /// Serve precompressed `.br` and `.gz` siblings of the files.
+    ///
+    /// Siblings are served by `Compress` middleware with enabled
+    /// precompressed mode. Default is false.
+    pub fn precompressed(mut self, value: bool) -> Self {
+        self.inner().precompressed = value;
+        self
+    }
+}
+
+impl Inner {
+    /// Resolve request path relative to served directory
+    fn file_path(&self, path: &str) -> Result<PathBuf, FilesError> {
+        let path = percent_decode_str(path)
+            .decode_utf8()
+            .map_err(|_| FilesError::InvalidPath)?;
+
+        let mut buf = self.directory.clone();
+        for segment in path.split('/') {
+            if segment == ".." || segment.contains('\\') || segment.contains('\0') {
+                return Err(FilesError::InvalidPath);
+            } else if segment.is_empty() || segment == "." {
+                continue;
+            } else if segment.starts_with('.') && !self.show_hidden {
+                return Err(FilesError::Hidden);
+            } else {
+                buf.push(segment);
+            }
+        }
+        Ok(buf)
+    }
+}
+
+impl<Err> WebServiceFactory<Err> for Files
+where
+    Err: ErrorRenderer,
+    FilesError: Into<Err::Container>,
+{
+    fn register(self, config: &mut WebServiceConfig<Err>) {
+        let rdef = if config.is_root() || !self.path.is_empty() {
+            ResourceDef::root_prefix(self.path.as_str())
+        } else {
+            ResourceDef::prefix(self.path.as_str())
+        };
+        config.register_service(rdef, None, self, None)
+    }
+}
+
+impl<Err> ServiceFactory<WebRequest<Err>> for Files
+where
+    Err: ErrorRenderer,
+    FilesError: Into<Err::Container>,
+{
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type Service = FilesService;
+    type InitError = ();
+    type Future = Ready<Self::Service, Self::InitError>;
+
+    fn new_service(&self, _: ()) -> Self::Future {
+        Ready::Ok(FilesService {
+            inner: self.inner.clone(),
+        })
+    }
+}
+
+pub struct FilesService {
+    inner: Rc<Inner>,
+}
+
+impl<Err> Service<WebRequest<Err>> for FilesService
+where
+    Err: ErrorRenderer,
+    FilesError: Into<Err::Container>,
+{
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type Future = Pin<Box<dyn Future<Output = Result<WebResponse, Err::Container>>>>;
+
+    #[inline]
+    fn poll_ready(&self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&self, req: WebRequest<Err>) -> Self::Future {
+        let inner = self.inner.clone();
+
+        Box::pin(async move {
+            if req.method() != Method::GET && req.method() != Method::HEAD {
+                return Ok(req.error_response(FilesError::MethodNotAllowed));
+            }
+
+            let path = match inner.file_path(req.match_info().unprocessed()) {
+                Ok(path) => path,
+                Err(e) => return Ok(req.error_response(e)),
+            };
+
+            let index = inner.index.clone();
+            #[cfg(feature = "compress")]
+            let precompressed = inner.precompressed;
+            let result = crate::rt::spawn_blocking(move || {
+                let mut path = path;
+                if path.is_dir() {
+                    if let Some(index) = index {
+                        path.push(index);
+                    } else {
+                        return Err(FilesError::IsDirectory);
+                    }
+                }
+                let file = NamedFile::open(path).map_err(FilesError::from)?;
+                #[cfg(feature = "compress")]
+                let file = if precompressed {
+                    file.precompressed()
+                } else {
+                    file
+                };
+                Ok(file)
+            })
+            .await;
+
+            match result {
+                Ok(Ok(file)) => {
+                    let (req, _) = req.into_parts();
+                    let res = file
+                        .use_etag(inner.use_etag)
+                        .use_last_modified(inner.use_last_modified)
+                        .into_response(&req);
+                    Ok(WebResponse::new(res, req))
+                }
+                Ok(Err(e)) => Ok(req.error_response(e)),
+                Err(_) => Ok(req.error_response(FilesError::Canceled)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App};
+
+    #[crate::rt_test]
+    async fn test_files() {
+        let srv = init_service(App::new().service(Files::new("/static", "."))).await;
+
+        let req = TestRequest::with_uri("/static/Cargo.toml").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/x-toml"
+        );
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        let data = std::fs::read("Cargo.toml").unwrap();
+        assert_eq!(read_body(res).await, data);
+
+        let req = TestRequest::with_uri("/static/src/../Cargo.toml").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/static/src/%2e%2e/Cargo.toml").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/static/./src/lib.rs").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/static/unknown").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::with_uri("/static/src").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+        let req = TestRequest::with_uri("/static/Cargo.toml")
+            .method(Method::POST)
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::METHOD_NOT_ALLOWED);
+
+        let req = TestRequest::with_uri("/static/Cargo.toml")
+            .header(header::IF_NONE_MATCH, etag)
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_uri("/static/Cargo.toml")
+            .header(header::RANGE, "bytes=0-9")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(read_body(res).await, &data[..10]);
+    }
+
+    #[crate::rt_test]
+    async fn test_hidden_files() {
+        let srv = init_service(
+            App::new()
+                .service(Files::new("/static", ".."))
+                .service(Files::new("/hidden", "..").show_hidden_files(true)),
+        )
+        .await;
+
+        for uri in &[
+            "/static/.gitignore",
+            "/static/.github/workflows",
+            "/static/%2egitignore",
+            "/static/.git/config",
+        ] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::NOT_FOUND, "{}", uri);
+        }
+
+        let req = TestRequest::with_uri("/static/Cargo.toml").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/hidden/.gitignore").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let data = std::fs::read("../.gitignore").unwrap();
+        assert_eq!(read_body(res).await, data);
+    }
+
+    #[crate::rt_test]
+    async fn test_index_file() {
+        let srv = init_service(
+            App::new().service(
+                web::scope("/files").service(
+                    Files::new("", "src")
+                        .index_file("lib.rs")
+                        .use_etag(false)
+                        .use_last_modified(false),
+                ),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/files/").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ETAG));
+        assert!(!res.headers().contains_key(header::LAST_MODIFIED));
+        let data = std::fs::read("src/lib.rs").unwrap();
+        assert_eq!(read_body(res).await, data);
+
+        let req = TestRequest::with_uri("/files/web").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[cfg(feature = "compress")]
+    #[crate::rt_test]
+    async fn test_precompressed() {
+        use crate::web::middleware::Compress;
+
+        let dir = std::env::temp_dir().join(format!("ntex-files-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("app.js"), "plain").unwrap();
+        std::fs::write(dir.join("app.js.gz"), "gzipped").unwrap();
+
+        let srv = init_service(
+            App::new()
+                .wrap(Compress::default().precompressed(true))
+                .service(Files::new("/static", &dir).precompressed(true)),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/static/app.js")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::CONTENT_ENCODING).unwrap(), "gzip");
+        assert!(!res.headers().contains_key(header::ACCEPT_RANGES));
+        assert!(res
+            .headers()
+            .get(header::ETAG)
+            .unwrap()
+            .as_bytes()
+            .starts_with(b"W/"));
+        assert_eq!(read_body(res).await, "gzipped");
+
+        let req = TestRequest::with_uri("/static/app.js").to_request();
+        let res = call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept-encoding");
+        assert_eq!(read_body(res).await, "plain");
+
+        let req = TestRequest::with_uri("/static/app.js")
+            .header(header::ACCEPT_ENCODING, "gzip")
+            .header(header::RANGE, "bytes=0-1")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert!(!res.headers().contains_key(header::CONTENT_ENCODING));
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 0-1/5"
+        );
+        assert_eq!(read_body(res).await, "pl");
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,407 @@
+use std::fs::{File, Metadata};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use std::{convert::TryFrom, fmt, io};
+
+#[cfg(feature = "compress")]
+use crate::http::header::ContentEncoding;
+use crate::http::header::{self, EntityTag, IfModifiedSince, IfNoneMatch, LastModified};
+use crate::http::RequestHead;
+use crate::web::error::ErrorRenderer;
+use crate::web::responder::{Ready, Responder};
+use crate::web::types::Ranged;
+use crate::web::{HttpRequest, HttpResponse};
+
+/// A file with an associated name and metadata.
+///
+/// `NamedFile` could be used as a responder, response supports conditional
+/// and range requests.
+///
+/// ```rust
+/// use ntex::web::files::NamedFile;
+///
+/// async fn index() -> std::io::Result<NamedFile> {
+///     NamedFile::open("Cargo.toml")
+/// }
+/// ```
+pub struct NamedFile {
+    path: PathBuf,
+    file: File,
+    md: Metadata,
+    modified: Option<SystemTime>,
+    content_type: mime::Mime,
+    use_etag: bool,
+    use_last_modified: bool,
+    #[cfg(feature = "compress")]
+    precompressed: Vec<(ContentEncoding, File)>,
+}
+
+impl NamedFile {
+    /// Attempt to open a file in read-only mode.
+    ///
+    /// This method performs blocking io operations.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<NamedFile> {
+        let file = File::open(&path)?;
+        Self::from_file(file, path)
+    }
+
+    /// Create `NamedFile` from already opened file.
+    ///
+    /// Path is used for content type detection.
+    pub fn from_file<P: AsRef<Path>>(file: File, path: P) -> io::Result<NamedFile> {
+        let md = file.metadata()?;
+        if md.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Path is a directory",
+            ));
+        }
+        let modified = md.modified().ok();
+        let content_type = mime_guess::from_path(&path).first_or_octet_stream();
+
+        Ok(NamedFile {
+            md,
+            file,
+            modified,
+            content_type,
+            path: path.as_ref().to_path_buf(),
+            use_etag: true,
+            use_last_modified: true,
+            #[cfg(feature = "compress")]
+            precompressed: Vec::new(),
+        })
+    }
+
+    /// Returns reference to the underlying `File` object.
+    pub fn file(&self) -> &File {
+        &self.file
+    }
+
+    /// Retrieve the path of this file.
+    pub fn path(&self) -> &Path {
+        self.path.as_path()
+    }
+
+    /// Get file's metadata
+    pub fn metadata(&self) -> &Metadata {
+        &self.md
+    }
+
+    /// Get content type of the file.
+    pub fn content_type(&self) -> &mime::Mime {
+        &self.content_type
+    }
+
+    /// Set content type of the file.
+    ///
+    /// By default content type is guessed from file extension.
+    pub fn set_content_type(mut self, mime_type: mime::Mime) -> Self {
+        self.content_type = mime_type;
+        self
+    }
+
+    /// Specifies whether to use `ETag` or not.
+    ///
+    /// Default is true.
+    pub fn use_etag(mut self, value: bool) -> Self {
+        self.use_etag = value;
+        self
+    }
+
+    /// Specifies whether to use `Last-Modified` or not.
+    ///
+    /// Default is true.
+    pub fn use_last_modified(mut self, value: bool) -> Self {
+        self.use_last_modified = value;
+        self
+    }
+
+    #[cfg(feature = "compress")]
+    /// Open precompressed `.br` and `.gz` siblings of the file.
+    ///
+    /// Siblings are attached to `200 OK` response as `Precompressed`
+    /// body variants, `Compress` middleware with enabled precompressed
+    /// mode sends variant accepted by the client.
+    ///
+    /// This method performs blocking io operations.
+    pub fn precompressed(mut self) -> Self {
+        for &(ext, encoding) in
+            &[("br", ContentEncoding::Br), ("gz", ContentEncoding::Gzip)]
+        {
+            let mut path = self.path.clone().into_os_string();
+            path.push(".");
+            path.push(ext);
+            if let Ok(file) = File::open(&path) {
+                if file.metadata().map(|md| md.is_file()).unwrap_or(false) {
+                    self.precompressed.push((encoding, file));
+                }
+            }
+        }
+        self
+    }
+
+    #[cfg(feature = "compress")]
+    fn take_precompressed(mut self) -> (Self, Vec<(ContentEncoding, File)>) {
+        let precompressed = std::mem::take(&mut self.precompressed);
+        (self, precompressed)
+    }
+
+    /// Strong entity tag of the file, build from file size and
+    /// modification time.
+    pub fn etag(&self) -> Option<String> {
+        self.modified.map(|modified| {
+            let dur = modified.duration_since(UNIX_EPOCH).unwrap_or_default();
+            format!(
+                "\"{:x}-{:x}.{:x}\"",
+                self.md.len(),
+                dur.as_secs(),
+                dur.subsec_nanos()
+            )
+        })
+    }
+
+    /// File modification time
+    pub fn last_modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    /// Check `If-None-Match` and `If-Modified-Since` headers
+    fn not_modified(&self, head: &RequestHead, etag: Option<&str>) -> bool {
+        if let Some(val) = head.headers.get(&header::IF_NONE_MATCH) {
+            let etag = etag.and_then(|etag| EntityTag::try_from(etag).ok());
+            return IfNoneMatch::try_from(val.clone())
+                .map(|val| val.is_match(etag.as_ref()))
+                .unwrap_or(false);
+        }
+
+        if let (Some(val), Some(modified)) =
+            (head.headers.get(&header::IF_MODIFIED_SINCE), self.modified)
+        {
+            if let Ok(since) = IfModifiedSince::try_from(val.clone()) {
+                return !since.is_modified(modified);
+            }
+        }
+        false
+    }
+
+    /// Build response for the request
+    pub fn into_response(self, req: &HttpRequest) -> HttpResponse {
+        let etag = if self.use_etag { self.etag() } else { None };
+        let modified = if self.use_last_modified {
+            self.modified
+        } else {
+            None
+        };
+        let file = NamedFile { modified, ..self };
+        #[cfg(feature = "compress")]
+        let (file, precompressed) = file.take_precompressed();
+
+        if file.not_modified(req.head(), etag.as_deref()) {
+            let mut res = HttpResponse::NotModified();
+            if let Some(ref etag) = etag {
+                res.header(header::ETAG, etag.as_str());
+            }
+            if let Some(modified) = file.modified {
+                res.header(header::LAST_MODIFIED, LastModified(modified).to_string());
+            }
+            return res.finish();
+        }
+
+        let mut ranged = Ranged::new(file.file).content_type(file.content_type.to_string());
+        if let Some(etag) = etag {
+            ranged = ranged.etag(etag);
+        }
+        if let Some(modified) = file.modified {
+            ranged = ranged.last_modified(modified);
+        }
+        let res = ranged.into_response(req);
+
+        #[cfg(feature = "compress")]
+        if res.status() == crate::http::StatusCode::OK && !precompressed.is_empty() {
+            use crate::web::{middleware::Precompressed, types::RangeSource};
+
+            let variants = precompressed
+                .into_iter()
+                .fold(Precompressed::new(), |variants, (encoding, file)| {
+                    variants.variant(encoding, file.into_body())
+                });
+            res.head().extensions_mut().insert(variants);
+        }
+        res
+    }
+}
+
+impl fmt::Debug for NamedFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("NamedFile")
+            .field("path", &self.path)
+            .field("content_type", &self.content_type)
+            .field("size", &self.md.len())
+            .finish()
+    }
+}
+
+impl<Err: ErrorRenderer> Responder<Err> for NamedFile {
+    type Error = Err::Container;
+    type Future = Ready<HttpResponse>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        self.into_response(req).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::body::{BodySize, MessageBody};
+    use crate::http::StatusCode;
+    use crate::web::test::{load_stream, TestRequest};
+
+    async fn body(res: HttpResponse) -> Vec<u8> {
+        let mut res = res;
+        let body = res.take_body();
+        load_stream(body).await.unwrap().to_vec()
+    }
+
+    #[crate::rt_test]
+    async fn test_named_file() {
+        let data = std::fs::read("Cargo.toml").unwrap();
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        assert_eq!(file.path(), Path::new("Cargo.toml"));
+        assert_eq!(
+            file.content_type(),
+            &"text/x-toml".parse::<mime::Mime>().unwrap()
+        );
+        assert_eq!(file.metadata().len(), data.len() as u64);
+        assert!(file.file().metadata().is_ok());
+        assert!(file.last_modified().is_some());
+        assert!(format!("{:?}", file).contains("Cargo.toml"));
+        let etag = file.etag().unwrap();
+
+        let req = TestRequest::default().to_http_request();
+        let res = file.into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), etag.as_str());
+        assert_eq!(res.headers().get(header::ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/x-toml"
+        );
+        assert!(res.headers().contains_key(header::LAST_MODIFIED));
+        assert_eq!(res.body().size(), BodySize::Sized(data.len() as u64));
+        assert_eq!(body(res).await, data);
+
+        let file = NamedFile::open("Cargo.toml")
+            .unwrap()
+            .set_content_type(mime::TEXT_PLAIN)
+            .use_etag(false)
+            .use_last_modified(false);
+        let res = file.into_response(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        assert!(!res.headers().contains_key(header::ETAG));
+        assert!(!res.headers().contains_key(header::LAST_MODIFIED));
+
+        assert!(NamedFile::open("src").is_err());
+        assert!(NamedFile::open("unknown-file").is_err());
+    }
+
+    #[crate::rt_test]
+    async fn test_not_modified() {
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let etag = file.etag().unwrap();
+        let modified = httpdate::HttpDate::from(file.last_modified().unwrap()).to_string();
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, etag.as_str())
+            .to_http_request();
+        let res = file.into_response(&req);
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), etag.as_str());
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(
+            header::IF_NONE_MATCH,
+            format!("\"other\", W/{}", etag),
+        )
+        .to_http_request();
+        assert_eq!(file.into_response(&req).status(), StatusCode::NOT_MODIFIED);
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "\"other\"")
+            .header(header::IF_MODIFIED_SINCE, modified.as_str())
+            .to_http_request();
+        assert_eq!(file.into_response(&req).status(), StatusCode::OK);
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(header::IF_MODIFIED_SINCE, modified.as_str())
+            .to_http_request();
+        assert_eq!(file.into_response(&req).status(), StatusCode::NOT_MODIFIED);
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(
+            header::IF_MODIFIED_SINCE,
+            "Sun, 06 Nov 1994 08:49:37 GMT",
+        )
+        .to_http_request();
+        assert_eq!(file.into_response(&req).status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_range() {
+        let data = std::fs::read("Cargo.toml").unwrap();
+        let len = data.len();
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(header::RANGE, "bytes=10-19").to_http_request();
+        let res = file.into_response(&req);
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            format!("bytes 10-19/{}", len).as_str()
+        );
+        assert_eq!(res.body().size(), BodySize::Sized(10));
+        assert_eq!(body(res).await, &data[10..20]);
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(header::RANGE, "bytes=-5").to_http_request();
+        let res = file.into_response(&req);
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(body(res).await, &data[len - 5..]);
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(header::RANGE, format!("bytes={}-", len))
+            .to_http_request();
+        let res = file.into_response(&req);
+        assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            format!("bytes */{}", len).as_str()
+        );
+
+        // malformed range is ignored
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(header::RANGE, "bytes=5-1").to_http_request();
+        assert_eq!(file.into_response(&req).status(), StatusCode::OK);
+
+        // if-range
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let etag = file.etag().unwrap();
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-1")
+            .header(header::IF_RANGE, etag.as_str())
+            .to_http_request();
+        assert_eq!(
+            file.into_response(&req).status(),
+            StatusCode::PARTIAL_CONTENT
+        );
+
+        let file = NamedFile::open("Cargo.toml").unwrap();
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-1")
+            .header(header::IF_RANGE, "\"other\"")
+            .to_http_request();
+        let res = file.into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body(res).await, data);
+    }
+}
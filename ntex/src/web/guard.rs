@@ -28,7 +28,8 @@
 #![allow(non_snake_case)]
 use std::convert::TryFrom;
 
-use crate::http::{header, RequestHead, Uri};
+use crate::http::header::{self, TypedHeader};
+use crate::http::{RequestHead, Uri};
 
 /// Trait defines resource guards. Guards are used for route selection.
 ///
@@ -257,6 +258,47 @@ impl Guard for HeaderGuard {
     }
 }
 
+/// Return predicate that matches if request accepts specified media type.
+///
+/// Media type is acceptable if it matches one of media ranges of `Accept`
+/// header with non-zero quality. Requests without `Accept` header or with
+/// malformed header accept any media type.
+///
+/// ```rust
+/// use ntex::web::{self, guard, App, HttpResponse};
+///
+/// fn main() {
+///     App::new().service(
+///         web::resource("/index.html")
+///             .route(
+///                 web::get()
+///                     .guard(guard::Accept(mime::APPLICATION_JSON))
+///                     .to(|| async { HttpResponse::Ok().json(&"json") }),
+///             )
+///             .route(web::get().to(|| async { HttpResponse::Ok().body("text") })),
+///     );
+/// }
+/// ```
+pub fn Accept(mime: mime::Mime) -> AcceptGuard {
+    AcceptGuard(mime)
+}
+
+#[doc(hidden)]
+pub struct AcceptGuard(mime::Mime);
+
+impl Guard for AcceptGuard {
+    fn check(&self, req: &RequestHead) -> bool {
+        let mut values = req.headers.get_all(header::ACCEPT).peekable();
+        if values.peek().is_none() {
+            return true;
+        }
+        match header::Accept::decode(values) {
+            Ok(accept) => accept.quality(&self.0).value() > 0,
+            Err(_) => true,
+        }
+    }
+}
+
 /// Return predicate that matches if request contains specified Host name.
 ///
 /// ```rust
@@ -342,6 +384,28 @@ mod tests {
         assert!(!pred.check(req.head()));
     }
 
+    #[test]
+    fn test_accept() {
+        let pred = Accept(mime::APPLICATION_JSON);
+
+        let req = TestRequest::default().to_http_request();
+        assert!(pred.check(req.head()));
+
+        let req =
+            TestRequest::with_header(header::ACCEPT, "text/html, application/*;q=0.5")
+                .to_http_request();
+        assert!(pred.check(req.head()));
+        assert!(!Accept(mime::IMAGE_PNG).check(req.head()));
+
+        let req = TestRequest::with_header(header::ACCEPT, "*/*, application/json;q=0")
+            .to_http_request();
+        assert!(!pred.check(req.head()));
+        assert!(Accept(mime::TEXT_PLAIN).check(req.head()));
+
+        let req = TestRequest::with_header(header::ACCEPT, "invalid").to_http_request();
+        assert!(pred.check(req.head()));
+    }
+
     #[test]
     fn test_host() {
         let req = TestRequest::default()
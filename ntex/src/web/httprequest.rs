@@ -22,6 +22,7 @@ pub(crate) struct HttpRequestInner {
     pub(crate) path: Path<Uri>,
     pub(crate) payload: Payload,
     pub(crate) app_state: Rc<Extensions>,
+    pub(crate) pattern: String,
     rmap: Rc<ResourceMap>,
     config: AppConfig,
     pool: &'static HttpRequestPool,
@@ -46,11 +47,30 @@ impl HttpRequest {
             rmap,
             config,
             pool,
+            pattern: String::new(),
         }))
     }
 }
 
 impl HttpRequest {
+    /// Create copy of the request without payload, extensions and match
+    /// information.
+    ///
+    /// Copy does not share state with original request, so original request
+    /// could be modified by services.
+    pub(crate) fn duplicate(&self) -> HttpRequest {
+        let head = self.0.head.duplicate();
+        HttpRequest::new(
+            Path::new(head.uri.clone()),
+            head,
+            Payload::None,
+            self.0.rmap.clone(),
+            self.0.config.clone(),
+            self.0.app_state.clone(),
+            self.0.pool,
+        )
+    }
+
     /// This method returns reference to the request head
     #[inline]
     pub fn head(&self) -> &RequestHead {
@@ -139,6 +159,25 @@ impl HttpRequest {
         &mut Rc::get_mut(&mut self.0).unwrap().path
     }
 
+    /// Get pattern of the matched resource.
+    ///
+    /// Pattern includes prefixes of all matched scopes, for example
+    /// `/api/users/{id}`. Returns `None` if request did not match
+    /// any resource.
+    #[inline]
+    pub fn match_pattern(&self) -> Option<&str> {
+        if self.0.pattern.is_empty() {
+            None
+        } else {
+            Some(&self.0.pattern)
+        }
+    }
+
+    #[inline]
+    pub(crate) fn push_match_pattern(&mut self, pattern: &str) {
+        Rc::get_mut(&mut self.0).unwrap().pattern.push_str(pattern)
+    }
+
     /// Request extensions
     #[inline]
     pub fn extensions(&self) -> Ref<'_, Extensions> {
@@ -339,7 +378,7 @@ mod tests {
     use crate::http::{header, StatusCode};
     use crate::router::ResourceDef;
     use crate::web::dev::ResourceMap;
-    use crate::web::test::{call_service, init_service, TestRequest};
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
     use crate::web::{self, App, HttpResponse};
 
     #[test]
@@ -532,4 +571,38 @@ mod tests {
 
         assert!(tracker.borrow().dropped);
     }
+
+    #[crate::rt_test]
+    async fn test_match_pattern() {
+        let srv = init_service(
+            App::new()
+                .service(web::scope("/api").service(web::resource("/users/{id}").to(
+                    |req: HttpRequest| async move {
+                        HttpResponse::Ok().body(req.match_pattern().unwrap().to_string())
+                    },
+                )))
+                .service(web::resource("/").to(|req: HttpRequest| async move {
+                    HttpResponse::Ok().body(req.match_pattern().unwrap().to_string())
+                }))
+                .default_service(web::to(|req: HttpRequest| async move {
+                    assert!(req.match_pattern().is_none());
+                    HttpResponse::NotFound()
+                })),
+        )
+        .await;
+
+        for _ in 0..2 {
+            let req = TestRequest::with_uri("/api/users/10").to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(read_body(resp).await, "/api/users/{id}");
+
+            let req = TestRequest::with_uri("/").to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(read_body(resp).await, "/");
+
+            let req = TestRequest::with_uri("/unknown").to_request();
+            let resp = call_service(&srv, req).await;
+            assert_eq!(resp.status(), StatusCode::NOT_FOUND);
+        }
+    }
 }
@@ -0,0 +1,284 @@
+//! Authentication middleware
+use std::task::{Context, Poll};
+use std::{borrow::Cow, future::Future, marker::PhantomData, pin::Pin, rc::Rc};
+
+use crate::service::{IntoService, Service, Transform};
+use crate::web::error::{AuthError, ErrorRenderer};
+use crate::web::types::{AuthScheme, BasicAuth, BearerAuth};
+use crate::web::{WebRequest, WebResponse};
+
+/// `Middleware` for request authentication.
+///
+/// Middleware extracts credentials from `Authorization` header and calls
+/// validator service. Validator returns identity for accepted credentials
+/// and `None` for rejected ones. Identity is stored in request extensions
+/// and could be accessed with `HttpRequest::extensions()`.
+///
+/// Missing, invalid or rejected credentials result in `AuthError`, by default
+/// `401 Unauthorized` response with `WWW-Authenticate` challenge is generated.
+/// Validator errors are rendered by application's `ErrorRenderer`.
+///
+/// ```rust
+/// use ntex::web::{self, middleware::Authentication, types::BasicAuth};
+/// use ntex::web::{App, HttpRequest, HttpResponse};
+///
+/// struct User(String);
+///
+/// async fn validator(auth: BasicAuth) -> Result<Option<User>, web::Error> {
+///     if auth.password() == Some("secret") {
+///         Ok(Some(User(auth.user_id().to_string())))
+///     } else {
+///         Ok(None)
+///     }
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(Authentication::basic(validator).realm("Restricted area"))
+///         .service(web::resource("/").to(|req: HttpRequest| async move {
+///             let name = req.extensions().get::<User>().unwrap().0.clone();
+///             HttpResponse::Ok().body(name)
+///         }));
+/// }
+/// ```
+pub struct Authentication<A, V> {
+    validator: Rc<V>,
+    realm: Option<Cow<'static, str>>,
+    _t: PhantomData<A>,
+}
+
+impl<A: AuthScheme, V: Service<A>> Authentication<A, V> {
+    /// Construct `Authentication` middleware with credentials validator.
+    pub fn new<F>(validator: F) -> Self
+    where
+        F: IntoService<V, A>,
+    {
+        Authentication {
+            validator: Rc::new(validator.into_service()),
+            realm: None,
+            _t: PhantomData,
+        }
+    }
+
+    /// Set protection space of `WWW-Authenticate` challenge.
+    pub fn realm<T: Into<Cow<'static, str>>>(mut self, realm: T) -> Self {
+        self.realm = Some(realm.into());
+        self
+    }
+}
+
+impl<V: Service<BasicAuth>> Authentication<BasicAuth, V> {
+    /// Construct middleware for `Basic` authentication scheme.
+    pub fn basic<F>(validator: F) -> Self
+    where
+        F: IntoService<V, BasicAuth>,
+    {
+        Authentication::new(validator)
+    }
+}
+
+impl<V: Service<BearerAuth>> Authentication<BearerAuth, V> {
+    /// Construct middleware for `Bearer` authentication scheme.
+    pub fn bearer<F>(validator: F) -> Self
+    where
+        F: IntoService<V, BearerAuth>,
+    {
+        Authentication::new(validator)
+    }
+}
+
+impl<S, A, V> Transform<S> for Authentication<A, V> {
+    type Service = AuthenticationMiddleware<S, A, V>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        AuthenticationMiddleware {
+            service: Rc::new(service),
+            validator: self.validator.clone(),
+            realm: self.realm.clone(),
+            _t: PhantomData,
+        }
+    }
+}
+
+pub struct AuthenticationMiddleware<S, A, V> {
+    service: Rc<S>,
+    validator: Rc<V>,
+    realm: Option<Cow<'static, str>>,
+    _t: PhantomData<A>,
+}
+
+impl<S, A, V, I, E> Service<WebRequest<E>> for AuthenticationMiddleware<S, A, V>
+where
+    S: Service<WebRequest<E>, Response = WebResponse, Error = E::Container> + 'static,
+    A: AuthScheme,
+    V: Service<A, Response = Option<I>> + 'static,
+    V::Future: 'static,
+    V::Error: Into<E::Container>,
+    I: 'static,
+    E: ErrorRenderer,
+    AuthError: Into<E::Container>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let ready = self
+            .validator
+            .poll_ready(cx)
+            .map_err(Into::into)?
+            .is_ready();
+        if self.service.poll_ready(cx)?.is_ready() && ready {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        let ready = self.validator.poll_shutdown(cx, is_error).is_ready();
+        if self.service.poll_shutdown(cx, is_error).is_ready() && ready {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let realm = self.realm.clone();
+        let credentials = match A::from_headers(req.headers()) {
+            Ok(credentials) => credentials,
+            Err(e) => {
+                let res = req.error_response(e.set_realm(realm));
+                return Box::pin(async move { Ok(res) });
+            }
+        };
+        let srv = self.service.clone();
+        let fut = self.validator.call(credentials);
+
+        Box::pin(async move {
+            match fut.await {
+                Ok(Some(identity)) => {
+                    req.extensions_mut().insert(identity);
+                    srv.call(req).await
+                }
+                Ok(None) => {
+                    log::trace!("Credentials are rejected");
+                    let err = AuthError::Rejected(A::rejected()).set_realm(realm);
+                    Ok(req.error_response(err))
+                }
+                Err(e) => Ok(req.error_response(e)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, Error, HttpRequest, HttpResponse};
+
+    struct User(String);
+
+    async fn index(req: HttpRequest) -> HttpResponse {
+        let name = req.extensions().get::<User>().unwrap().0.clone();
+        HttpResponse::Ok().body(name)
+    }
+
+    #[crate::rt_test]
+    async fn test_basic() {
+        let srv = init_service(
+            App::new()
+                .wrap(
+                    Authentication::basic(|auth: BasicAuth| async move {
+                        match auth.password() {
+                            Some("secret") => Ok(Some(User(auth.user_id().to_string()))),
+                            Some("error") => Err(Error::new(web::error::PanicError)),
+                            _ => Ok(None),
+                        }
+                    })
+                    .realm("test"),
+                )
+                .service(web::resource("/").to(index)),
+        )
+        .await;
+
+        // user:secret
+        let req = TestRequest::default()
+            .header(header::AUTHORIZATION, "Basic dXNlcjpzZWNyZXQ=")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "user");
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Basic realm=\"test\""
+        );
+
+        // user:other
+        let req = TestRequest::default()
+            .header(header::AUTHORIZATION, "Basic dXNlcjpvdGhlcg==")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(read_body(res).await, "Credentials are rejected");
+
+        // user:error
+        let req = TestRequest::default()
+            .header(header::AUTHORIZATION, "Basic dXNlcjplcnJvcg==")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
+    #[crate::rt_test]
+    async fn test_bearer() {
+        let srv = init_service(
+            App::new()
+                .wrap(Authentication::bearer(|auth: BearerAuth| async move {
+                    if auth.token() == "token" {
+                        Ok::<_, Error>(Some(User("bearer".to_string())))
+                    } else {
+                        Ok(None)
+                    }
+                }))
+                .service(web::resource("/").to(index)),
+        )
+        .await;
+
+        let req = TestRequest::default()
+            .header(header::AUTHORIZATION, "Bearer token")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "bearer");
+
+        let req = TestRequest::default()
+            .header(header::AUTHORIZATION, "Bearer other")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer error=\"invalid_token\""
+        );
+
+        let req = TestRequest::default()
+            .header(header::AUTHORIZATION, "Bearer a b")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+        assert_eq!(
+            res.headers().get(header::WWW_AUTHENTICATE).unwrap(),
+            "Bearer"
+        );
+    }
+}
@@ -0,0 +1,273 @@
+//! Request body size limit middleware
+use std::task::{Context, Poll};
+use std::{cell::Cell, pin::Pin, rc::Rc};
+
+use crate::http::error::PayloadError;
+use crate::http::{header, Payload};
+use crate::service::{Service, Transform};
+use crate::util::{Bytes, Stream};
+use crate::web::{WebRequest, WebResponse};
+
+/// `Middleware` for request body size limit.
+///
+/// Middleware wraps request payload and fails payload stream with
+/// `PayloadError::Overflow` error as soon as size of the received body
+/// exceeds the limit. Limit applies to any consumer of the payload:
+/// extractors, handlers that read `Payload` directly or proxy it.
+///
+/// Request with `Content-Length` above the limit fails payload stream
+/// with `PayloadError::Overflow` error on first read, before any data is
+/// received. Extractors render it as `413 Payload Too Large` error.
+///
+/// Middleware could be registered for application, scope or resource.
+/// Nested middleware overrides limit of the outer one, so scope could
+/// allow bigger uploads than the rest of the application.
+///
+/// ```rust
+/// use ntex::web::{self, middleware::BodyLimit, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(BodyLimit::new(64 * 1024))
+///         .service(
+///             web::scope("/upload")
+///                 .wrap(BodyLimit::new(16 * 1024 * 1024))
+///                 .service(web::resource("/").to(|| async { HttpResponse::Ok() })),
+///         );
+/// }
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct BodyLimit {
+    limit: usize,
+}
+
+impl BodyLimit {
+    /// Construct `BodyLimit` middleware with max body size in bytes.
+    pub fn new(limit: usize) -> Self {
+        BodyLimit { limit }
+    }
+}
+
+impl<S> Transform<S> for BodyLimit {
+    type Service = BodyLimitMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        BodyLimitMiddleware {
+            service,
+            limit: self.limit,
+        }
+    }
+}
+
+/// Body limit state shared between middlewares and payload stream
+#[derive(Clone)]
+struct LimitState(Rc<Cell<usize>>);
+
+pub struct BodyLimitMiddleware<S> {
+    service: S,
+    limit: usize,
+}
+
+impl<S, E> Service<WebRequest<E>> for BodyLimitMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = S::Future;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, mut req: WebRequest<E>) -> Self::Future {
+        let length = req
+            .headers()
+            .get(&header::CONTENT_LENGTH)
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| val.parse::<u64>().ok());
+
+        let state = req.extensions().get::<LimitState>().cloned();
+        if let Some(state) = state {
+            // nested middleware overrides limit
+            state.0.set(self.limit);
+        } else {
+            let state = LimitState(Rc::new(Cell::new(self.limit)));
+            let payload = req.take_payload();
+            if !matches!(payload, Payload::None) {
+                req.set_payload(Payload::from_stream(Limited {
+                    payload,
+                    length,
+                    size: 0,
+                    checked: false,
+                    state: state.clone(),
+                }));
+            }
+            req.extensions_mut().insert(state);
+        }
+        self.service.call(req)
+    }
+}
+
+/// Payload stream with size limit
+struct Limited {
+    payload: Payload,
+    length: Option<u64>,
+    size: usize,
+    checked: bool,
+    state: LimitState,
+}
+
+impl Limited {
+    fn overflow(&mut self) -> Poll<Option<Result<Bytes, PayloadError>>> {
+        self.payload = Payload::None;
+        Poll::Ready(Some(Err(PayloadError::Overflow)))
+    }
+}
+
+impl Stream for Limited {
+    type Item = Result<Bytes, PayloadError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let limit = this.state.0.get();
+
+        // limit is final when payload is read first time
+        if !this.checked {
+            this.checked = true;
+            if this.length.map(|l| l > limit as u64).unwrap_or(false) {
+                return this.overflow();
+            }
+        }
+
+        match this.payload.poll_recv(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                this.size += chunk.len();
+                if this.size > limit {
+                    this.overflow()
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                }
+            }
+            res => res,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::util::BytesMut;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    async fn echo(mut payload: web::types::Payload) -> Result<HttpResponse, web::Error> {
+        let mut body = BytesMut::new();
+        while let Some(chunk) = crate::util::stream_recv(&mut payload).await {
+            body.extend_from_slice(&chunk?);
+        }
+        Ok(HttpResponse::Ok().body(body.freeze()))
+    }
+
+    #[crate::rt_test]
+    async fn test_body_limit() {
+        let srv = init_service(
+            App::new()
+                .wrap(BodyLimit::new(10))
+                .service(web::resource("/").to(echo))
+                .default_service(web::to(echo))
+                .service(
+                    web::scope("/upload")
+                        .wrap(BodyLimit::new(100))
+                        .service(web::resource("/").to(echo))
+                        .service(web::resource("/small").wrap(BodyLimit::new(20)).to(echo)),
+                )
+                .service(
+                    web::resource("/json")
+                        .app_state(web::types::JsonConfig::default().limit(1024))
+                        .to(|_: web::types::Json<Vec<u32>>| async { HttpResponse::Ok() }),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/").set_payload("12345").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "12345");
+
+        // stream without content-length
+        let req = TestRequest::with_uri("/")
+            .set_payload("x".repeat(20))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // content-length check
+        let req = TestRequest::with_uri("/")
+            .header(header::CONTENT_LENGTH, "20")
+            .set_payload("x".repeat(20))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // default service reads payload
+        let req = TestRequest::with_uri("/unknown")
+            .header(header::CONTENT_LENGTH, "20")
+            .set_payload("x".repeat(20))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // scope override
+        let req = TestRequest::with_uri("/upload/")
+            .header(header::CONTENT_LENGTH, "50")
+            .set_payload("x".repeat(50))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await.len(), 50);
+
+        let req = TestRequest::with_uri("/upload/")
+            .set_payload("x".repeat(150))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        // innermost middleware decides
+        let req = TestRequest::with_uri("/upload/small")
+            .header(header::CONTENT_LENGTH, "50")
+            .set_payload("x".repeat(50))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::with_uri("/upload/small")
+            .header(header::CONTENT_LENGTH, "15")
+            .set_payload("x".repeat(15))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // extractor with bigger limit
+        let req = TestRequest::with_uri("/json")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload("[1, 2, 3, 4, 5]")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let req = TestRequest::with_uri("/json")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload("[1]")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+}
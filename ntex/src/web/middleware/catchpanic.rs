@@ -0,0 +1,224 @@
+//! Middleware for catching handler panics
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::task::{Context, Poll};
+use std::{any::Any, future::Future, pin::Pin, rc::Rc};
+
+use crate::service::{Service, Transform};
+use crate::web::error::{ErrorRenderer, PanicError};
+use crate::web::{WebRequest, WebResponse};
+
+type Hook = Rc<dyn Fn(&(dyn Any + Send))>;
+
+/// `Middleware` for catching panics in request handlers.
+///
+/// Middleware catches unwinding panics of the wrapped service, panicked
+/// service future is dropped and `PanicError` is returned, error is rendered
+/// by application's `ErrorRenderer`. By default `500 Internal Server Error`
+/// response is generated. Optional hook is called with the panic payload.
+///
+/// Panics are caught only if panic strategy is `unwind`.
+///
+/// ```rust
+/// use ntex::web::{self, middleware::CatchPanic, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(CatchPanic::new().hook(|payload| {
+///             if let Some(msg) = payload.downcast_ref::<&str>() {
+///                 log::error!("Handler panicked: {}", msg);
+///             }
+///         }))
+///         .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+/// }
+/// ```
+#[derive(Clone, Default)]
+pub struct CatchPanic {
+    hook: Option<Hook>,
+}
+
+impl CatchPanic {
+    /// Construct `CatchPanic` middleware.
+    pub fn new() -> Self {
+        CatchPanic::default()
+    }
+
+    /// Set panic hook.
+    ///
+    /// Hook is called with the payload of caught panic.
+    pub fn hook<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&(dyn Any + Send)) + 'static,
+    {
+        self.hook = Some(Rc::new(f));
+        self
+    }
+}
+
+impl<S> Transform<S> for CatchPanic {
+    type Service = CatchPanicMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        CatchPanicMiddleware {
+            service,
+            hook: self.hook.clone(),
+        }
+    }
+}
+
+pub struct CatchPanicMiddleware<S> {
+    service: S,
+    hook: Option<Hook>,
+}
+
+fn call_hook(hook: &Option<Hook>, payload: Box<dyn Any + Send>) {
+    if let Some(ref hook) = hook {
+        hook(&*payload);
+    }
+}
+
+impl<S, E> Service<WebRequest<E>> for CatchPanicMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse, Error = E::Container>,
+    E: ErrorRenderer,
+    PanicError: Into<E::Container>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = CatchPanicResponse<S, E>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        match catch_unwind(AssertUnwindSafe(|| self.service.call(req))) {
+            Ok(fut) => CatchPanicResponse {
+                fut: Some(fut),
+                hook: self.hook.clone(),
+            },
+            Err(payload) => {
+                call_hook(&self.hook, payload);
+                CatchPanicResponse {
+                    fut: None,
+                    hook: None,
+                }
+            }
+        }
+    }
+}
+
+pin_project_lite::pin_project! {
+    pub struct CatchPanicResponse<S: Service<WebRequest<E>>, E> {
+        #[pin]
+        fut: Option<S::Future>,
+        hook: Option<Hook>,
+    }
+}
+
+impl<S, E> Future for CatchPanicResponse<S, E>
+where
+    S: Service<WebRequest<E>, Response = WebResponse, Error = E::Container>,
+    E: ErrorRenderer,
+    PanicError: Into<E::Container>,
+{
+    type Output = Result<WebResponse, S::Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+
+        let fut = if let Some(fut) = this.fut.as_mut().as_pin_mut() {
+            fut
+        } else {
+            return Poll::Ready(Err(PanicError.into()));
+        };
+
+        match catch_unwind(AssertUnwindSafe(|| fut.poll(cx))) {
+            Ok(res) => res,
+            Err(payload) => {
+                // drop panicked future
+                this.fut.set(None);
+                call_hook(this.hook, payload);
+                Poll::Ready(Err(PanicError.into()))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+
+    use super::*;
+    use crate::http::{error::ResponseError, StatusCode};
+    use crate::service::{fn_service, IntoService};
+    use crate::util::lazy;
+    use crate::web::test::{call_service, init_service, TestRequest};
+    use crate::web::{self, App, DefaultError, Error, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_catch_panic() {
+        let messages = Rc::new(RefCell::new(Vec::new()));
+        let messages2 = messages.clone();
+        let srv = init_service(
+            App::new()
+                .wrap(CatchPanic::new().hook(move |payload| {
+                    if let Some(msg) = payload.downcast_ref::<&str>() {
+                        messages2.borrow_mut().push(msg.to_string());
+                    }
+                }))
+                .service(web::resource("/").to(|| async { HttpResponse::Ok() }))
+                .service(web::resource("/panic").to(|| async {
+                    if true {
+                        panic!("handler panic");
+                    }
+                    HttpResponse::Ok()
+                })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/panic").to_request();
+        let err = srv.call(req).await.err().unwrap();
+        assert_eq!(
+            err.error_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+        assert_eq!(err.to_string(), "Request handler panicked");
+        assert_eq!(&*messages.borrow(), &["handler panic".to_string()]);
+
+        // service is still usable
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_panic_in_call() {
+        let srv = fn_service(|req: WebRequest<DefaultError>| {
+            if req.path() == "/panic" {
+                panic!("call panic");
+            }
+            async move { Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish())) }
+        });
+        let mw = CatchPanic::new().new_transform(srv.into_service());
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let res = mw
+            .call(TestRequest::with_uri("/panic").to_srv_request())
+            .await;
+        let err = res.err().unwrap();
+        assert_eq!(
+            err.error_response().status(),
+            StatusCode::INTERNAL_SERVER_ERROR
+        );
+
+        let res = mw.call(TestRequest::default().to_srv_request()).await;
+        assert_eq!(res.unwrap().status(), StatusCode::OK);
+    }
+}
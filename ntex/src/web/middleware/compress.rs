@@ -1,24 +1,56 @@
 //! `Middleware` for compressing response body.
 use std::task::{Context, Poll};
-use std::{cmp, future::Future, marker, pin::Pin, str::FromStr};
+use std::{cmp, convert::TryFrom, future::Future, marker, pin::Pin, rc::Rc, str::FromStr};
 
+use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
 use crate::http::encoding::Encoder;
-use crate::http::header::{ContentEncoding, ACCEPT_ENCODING};
+use crate::http::header::{self, ContentEncoding, EntityTag, HeaderValue, ACCEPT_ENCODING};
+use crate::http::{Response, StatusCode};
 use crate::service::{Service, Transform};
 use crate::web::{BodyEncoding, ErrorRenderer, WebRequest, WebResponse};
 
+/// Content types that are not compressed by default
+const COMPRESSED_CONTENT_TYPES: [&str; 17] = [
+    "image/png",
+    "image/jpeg",
+    "image/gif",
+    "image/webp",
+    "image/avif",
+    "audio/*",
+    "video/*",
+    "font/woff",
+    "font/woff2",
+    "application/zip",
+    "application/gzip",
+    "application/x-gzip",
+    "application/zstd",
+    "application/x-bzip2",
+    "application/x-xz",
+    "application/x-7z-compressed",
+    "application/x-rar-compressed",
+];
+
 #[derive(Debug, Clone)]
 /// `Middleware` for compressing response body.
 ///
 /// Use `BodyEncoding` trait for overriding response compression.
 /// To disable compression set encoding to `ContentEncoding::Identity` value.
 ///
+/// Responses with already compressed content types (images, audio, video,
+/// archives) are sent as is, use `allow_content_type()` and
+/// `deny_content_type()` methods to change content type filters.
+///
 /// ```rust
+/// use ntex::http::header::ContentEncoding;
 /// use ntex::web::{self, middleware, App, HttpResponse};
 ///
 /// fn main() {
 ///     let app = App::new()
-///         .wrap(middleware::Compress::default())
+///         .wrap(
+///             middleware::Compress::default()
+///                 .min_size(1024)
+///                 .level(ContentEncoding::Gzip, 6)
+///         )
 ///         .service(
 ///             web::resource("/test")
 ///                 .route(web::get().to(|| async { HttpResponse::Ok() }))
@@ -27,13 +59,92 @@ use crate::web::{BodyEncoding, ErrorRenderer, WebRequest, WebResponse};
 /// }
 /// ```
 pub struct Compress {
+    inner: Rc<Inner>,
+}
+
+#[derive(Debug)]
+struct Inner {
     enc: ContentEncoding,
+    min_size: u64,
+    levels: Vec<(ContentEncoding, u32)>,
+    allow: Vec<String>,
+    deny: Vec<String>,
+    precompressed: bool,
 }
 
 impl Compress {
     /// Create new `Compress` middleware with default encoding.
     pub fn new(encoding: ContentEncoding) -> Self {
-        Compress { enc: encoding }
+        Compress {
+            inner: Rc::new(Inner {
+                enc: encoding,
+                min_size: 0,
+                levels: Vec::new(),
+                allow: Vec::new(),
+                deny: COMPRESSED_CONTENT_TYPES
+                    .iter()
+                    .map(|s| s.to_string())
+                    .collect(),
+                precompressed: false,
+            }),
+        }
+    }
+
+    /// Set minimum body size for compression.
+    ///
+    /// Responses with known body size smaller than `size` are sent
+    /// uncompressed, streaming responses are always compressed.
+    /// Default is 0.
+    pub fn min_size(mut self, size: usize) -> Self {
+        self.inner_mut().min_size = size as u64;
+        self
+    }
+
+    /// Set compression level for the encoding.
+    ///
+    /// Level is clamped to max level of the encoding, it is 9 for gzip
+    /// and deflate, 11 for br and 22 for zstd. By default, fastest level
+    /// is used for gzip and deflate and level 3 for br and zstd.
+    pub fn level(mut self, encoding: ContentEncoding, level: u32) -> Self {
+        let levels = &mut self.inner_mut().levels;
+        levels.retain(|(enc, _)| *enc != encoding);
+        levels.push((encoding, level));
+        self
+    }
+
+    /// Compress only responses with matching content type.
+    ///
+    /// Value is either full mime type, like `text/html`, or mime type
+    /// prefix, like `text/*`. If allow list is empty, all content types
+    /// except denied ones are compressed. Responses without content type
+    /// are always compressed.
+    pub fn allow_content_type(mut self, content_type: &str) -> Self {
+        self.inner_mut().allow.push(content_type.to_string());
+        self
+    }
+
+    /// Do not compress responses with matching content type.
+    ///
+    /// Value is either full mime type, like `image/png`, or mime type
+    /// prefix, like `image/*`.
+    pub fn deny_content_type(mut self, content_type: &str) -> Self {
+        self.inner_mut().deny.push(content_type.to_string());
+        self
+    }
+
+    /// Serve precompressed response bodies.
+    ///
+    /// If response carries [`Precompressed`] bodies and one of them
+    /// matches accepted encodings, matching body is sent instead of
+    /// compressing response body. Strong `ETag` of the response is
+    /// replaced with weak one for precompressed body. Default is false.
+    pub fn precompressed(mut self, value: bool) -> Self {
+        self.inner_mut().precompressed = value;
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
     }
 }
 
@@ -49,14 +160,62 @@ impl<S> Transform<S> for Compress {
     fn new_transform(&self, service: S) -> Self::Service {
         CompressMiddleware {
             service,
-            encoding: self.enc,
+            inner: self.inner.clone(),
         }
     }
 }
 
+/// Precompressed variants of response body
+///
+/// Handler could attach already compressed variants of the response body
+/// to response extensions. `Compress` middleware with enabled precompressed
+/// mode sends variant that matches client's accepted encodings.
+///
+/// ```rust
+/// use ntex::http::header::ContentEncoding;
+/// use ntex::web::{middleware::Precompressed, HttpResponse};
+///
+/// async fn index() -> HttpResponse {
+///     let mut res = HttpResponse::Ok().body("data");
+///     res.extensions_mut().insert(
+///         Precompressed::new().variant(ContentEncoding::Br, &b"\x0b\x01\x80data\x03"[..]),
+///     );
+///     res
+/// }
+/// ```
+#[derive(Debug, Default)]
+pub struct Precompressed {
+    variants: Vec<(ContentEncoding, Body)>,
+}
+
+impl Precompressed {
+    /// Create empty set of body variants.
+    pub fn new() -> Self {
+        Precompressed::default()
+    }
+
+    /// Add body variant compressed with `encoding`.
+    pub fn variant<B: Into<Body>>(mut self, encoding: ContentEncoding, body: B) -> Self {
+        self.variants.retain(|(enc, _)| *enc != encoding);
+        self.variants.push((encoding, body.into()));
+        self
+    }
+
+    /// Check if body variant for the encoding exists.
+    pub fn contains(&self, encoding: ContentEncoding) -> bool {
+        self.variants.iter().any(|(enc, _)| *enc == encoding)
+    }
+
+    /// Take body variant compressed with `encoding`.
+    pub fn take(&mut self, encoding: ContentEncoding) -> Option<Body> {
+        let idx = self.variants.iter().position(|(enc, _)| *enc == encoding)?;
+        Some(self.variants.remove(idx).1)
+    }
+}
+
 pub struct CompressMiddleware<S> {
     service: S,
-    encoding: ContentEncoding,
+    inner: Rc<Inner>,
 }
 
 impl<S, E> Service<WebRequest<E>> for CompressMiddleware<S>
@@ -80,18 +239,26 @@ where
 
     fn call(&self, req: WebRequest<E>) -> Self::Future {
         // negotiate content-encoding
-        let encoding = if let Some(val) = req.headers().get(&ACCEPT_ENCODING) {
+        let accept = req.headers().get(&ACCEPT_ENCODING);
+        let encoding = if let Some(val) = accept {
             if let Ok(enc) = val.to_str() {
-                AcceptEncoding::parse(enc, self.encoding)
+                AcceptEncoding::parse(enc, self.inner.enc)
             } else {
                 ContentEncoding::Identity
             }
         } else {
             ContentEncoding::Identity
         };
+        let accept = if self.inner.precompressed {
+            accept.cloned()
+        } else {
+            None
+        };
 
         CompressResponse {
             encoding,
+            accept,
+            inner: self.inner.clone(),
             fut: self.service.call(req),
             _t: marker::PhantomData,
         }
@@ -105,6 +272,8 @@ pin_project_lite::pin_project! {
         #[pin]
         fut: S::Future,
         encoding: ContentEncoding,
+        accept: Option<HeaderValue>,
+        inner: Rc<Inner>,
         _t: marker::PhantomData<E>,
     }
 }
@@ -120,22 +289,136 @@ where
         let this = self.project();
 
         match this.fut.poll(cx)? {
-            Poll::Ready(resp) => {
+            Poll::Ready(mut resp) => {
+                if this.inner.precompressed {
+                    let variants = resp
+                        .response_mut()
+                        .extensions_mut()
+                        .remove::<Precompressed>();
+                    if let Some(variants) = variants {
+                        resp = this.inner.precompressed(resp, variants, this.accept.take());
+                    }
+                }
+
                 let enc = if let Some(enc) = resp.response().get_encoding() {
                     enc
-                } else {
+                } else if this.inner.is_compressible(resp.response()) {
                     *this.encoding
+                } else {
+                    ContentEncoding::Identity
                 };
+                let level = this.inner.level(enc);
 
-                Poll::Ready(Ok(
-                    resp.map_body(move |head, body| Encoder::response(enc, head, body))
-                ))
+                Poll::Ready(Ok(resp.map_body(move |head, body| {
+                    Encoder::response_with_level(enc, level, head, body)
+                })))
             }
             Poll::Pending => Poll::Pending,
         }
     }
 }
 
+impl Inner {
+    fn level(&self, encoding: ContentEncoding) -> Option<u32> {
+        self.levels
+            .iter()
+            .find(|(enc, _)| *enc == encoding)
+            .map(|(_, level)| *level)
+    }
+
+    fn is_compressible(&self, res: &Response) -> bool {
+        // compressed partial content is not a range of compressed representation
+        if res.status() == StatusCode::PARTIAL_CONTENT
+            || res.headers().contains_key(&header::CONTENT_RANGE)
+        {
+            return false;
+        }
+        if let BodySize::Sized(size) = res.body().size() {
+            if size < self.min_size {
+                return false;
+            }
+        }
+
+        if let Some(ct) = res
+            .headers()
+            .get(&header::CONTENT_TYPE)
+            .and_then(|val| val.to_str().ok())
+        {
+            let ct = ct.split(';').next().unwrap_or("").trim();
+            !self.deny.iter().any(|pattern| mime_matches(pattern, ct))
+                && (self.allow.is_empty()
+                    || self.allow.iter().any(|pattern| mime_matches(pattern, ct)))
+        } else {
+            true
+        }
+    }
+
+    /// Replace response body with precompressed variant
+    fn precompressed(
+        &self,
+        mut resp: WebResponse,
+        mut variants: Precompressed,
+        accept: Option<HeaderValue>,
+    ) -> WebResponse {
+        if resp.status() != StatusCode::OK
+            || resp.headers().contains_key(&header::CONTENT_ENCODING)
+        {
+            return resp;
+        }
+        resp.headers_mut()
+            .append(header::VARY, HeaderValue::from_static("accept-encoding"));
+
+        let encoding = accept
+            .as_ref()
+            .and_then(|val| val.to_str().ok())
+            .and_then(|val| {
+                AcceptEncoding::negotiate(val, |enc| {
+                    (self.enc == ContentEncoding::Auto || self.enc == enc)
+                        && variants.contains(enc)
+                })
+            });
+
+        if let Some((encoding, body)) =
+            encoding.and_then(|enc| variants.take(enc).map(|body| (enc, body)))
+        {
+            resp.map_body(move |head, _| {
+                let headers = head.headers_mut();
+                headers.insert(
+                    header::CONTENT_ENCODING,
+                    HeaderValue::from_static(encoding.as_str()),
+                );
+                headers.remove(&header::CONTENT_LENGTH);
+                headers.remove(&header::ACCEPT_RANGES);
+
+                // encoded variant is not byte-for-byte identical to
+                // identity representation, entity tag must not be strong
+                let etag = headers
+                    .get(&header::ETAG)
+                    .and_then(|val| EntityTag::try_from(val.clone()).ok());
+                if let Some(etag) = etag {
+                    if let Ok(val) =
+                        HeaderValue::from_str(&EntityTag::weak(etag.tag()).to_string())
+                    {
+                        headers.insert(header::ETAG, val);
+                    }
+                }
+                ResponseBody::Other(body)
+            })
+        } else {
+            resp
+        }
+    }
+}
+
+/// Check if content type matches `type/subtype` or `type/*` pattern
+fn mime_matches(pattern: &str, content_type: &str) -> bool {
+    if let Some(prefix) = pattern.strip_suffix("/*") {
+        matches!(content_type.split_once('/'), Some((tp, _)) if tp.eq_ignore_ascii_case(prefix))
+    } else {
+        pattern.eq_ignore_ascii_case(content_type)
+    }
+}
+
 struct AcceptEncoding {
     encoding: ContentEncoding,
     quality: f64,
@@ -175,6 +458,9 @@ impl AcceptEncoding {
             0 => return None,
             _ => ContentEncoding::from(parts[0]),
         };
+        if encoding == ContentEncoding::Zstd && !cfg!(feature = "zstd") {
+            return None;
+        }
         let quality = match parts.len() {
             1 => encoding.quality(),
             _ => f64::from_str(parts[1]).unwrap_or(0.0),
@@ -184,6 +470,17 @@ impl AcceptEncoding {
 
     /// Parse a raw Accept-Encoding header value into an ordered list.
     fn parse(raw: &str, encoding: ContentEncoding) -> ContentEncoding {
+        AcceptEncoding::negotiate(raw, |enc| {
+            encoding == ContentEncoding::Auto || encoding == enc
+        })
+        .unwrap_or(ContentEncoding::Identity)
+    }
+
+    /// Select most preferred accepted encoding that passes the filter.
+    fn negotiate<F>(raw: &str, f: F) -> Option<ContentEncoding>
+    where
+        F: Fn(ContentEncoding) -> bool,
+    {
         let mut encodings: Vec<_> = raw
             .replace(' ', "")
             .split(',')
@@ -191,13 +488,177 @@ impl AcceptEncoding {
             .collect();
         encodings.sort();
 
-        for enc in encodings.into_iter().flatten() {
-            if encoding == ContentEncoding::Auto {
-                return enc.encoding;
-            } else if encoding == enc.encoding {
-                return encoding;
-            }
-        }
-        ContentEncoding::Identity
+        encodings
+            .into_iter()
+            .flatten()
+            .map(|enc| enc.encoding)
+            .find(|enc| f(*enc))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+
+    use super::*;
+    use crate::http::header::{CONTENT_ENCODING, CONTENT_TYPE, VARY};
+    use crate::util::Bytes;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    const DATA: &str = "Hello World Hello World Hello World Hello World Hello World";
+
+    fn gunzip(data: &[u8]) -> String {
+        let mut s = String::new();
+        flate2::read::GzDecoder::new(data)
+            .read_to_string(&mut s)
+            .unwrap();
+        s
+    }
+
+    async fn service(
+        compress: Compress,
+    ) -> impl Service<crate::http::Request, Response = WebResponse, Error = web::Error>
+    {
+        init_service(
+            App::new()
+                .wrap(compress)
+                .service(web::resource("/text").to(|| async {
+                    HttpResponse::Ok().content_type("text/plain").body(DATA)
+                }))
+                .service(web::resource("/png").to(|| async {
+                    HttpResponse::Ok().content_type("image/png").body(DATA)
+                }))
+                .service(
+                    web::resource("/small")
+                        .to(|| async { HttpResponse::Ok().body("data") }),
+                )
+                .service(web::resource("/range").to(|| async {
+                    HttpResponse::PartialContent()
+                        .content_type("text/plain")
+                        .header(header::CONTENT_RANGE, "bytes 0-99/1000")
+                        .body(DATA)
+                }))
+                .service(web::resource("/pre").to(|| async {
+                    let res = HttpResponse::Ok()
+                        .content_type("text/plain")
+                        .header(header::ETAG, "\"v1\"")
+                        .body(DATA);
+                    res.head().extensions_mut().insert(
+                        Precompressed::new()
+                            .variant(ContentEncoding::Br, Bytes::from_static(b"br-data")),
+                    );
+                    res
+                })),
+        )
+        .await
+    }
+
+    #[crate::rt_test]
+    async fn test_filters() {
+        let srv = service(Compress::default().min_size(10)).await;
+
+        let req = TestRequest::with_uri("/text")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(gunzip(&read_body(res).await), DATA);
+
+        let req = TestRequest::with_uri("/png")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(CONTENT_ENCODING));
+        assert_eq!(read_body(res).await, DATA);
+
+        let req = TestRequest::with_uri("/small")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(CONTENT_ENCODING));
+
+        // partial content is not compressed
+        let req = TestRequest::with_uri("/range")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(CONTENT_ENCODING));
+        assert_eq!(read_body(res).await, DATA);
+
+        let srv = service(
+            Compress::default()
+                .allow_content_type("image/*")
+                .deny_content_type("image/jpeg"),
+        )
+        .await;
+        let req = TestRequest::with_uri("/text")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(CONTENT_ENCODING));
+
+        let req = TestRequest::with_uri("/small")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+
+        assert!(mime_matches("text/*", "TEXT/html"));
+        assert!(mime_matches("text/html", "text/HTML"));
+        assert!(!mime_matches("text/*", "textual"));
+        assert!(!mime_matches("text/html", "text/plain"));
+    }
+
+    #[crate::rt_test]
+    async fn test_level() {
+        let srv =
+            service(Compress::new(ContentEncoding::Gzip).level(ContentEncoding::Gzip, 100))
+                .await;
+
+        let req = TestRequest::with_uri("/text")
+            .header(ACCEPT_ENCODING, "gzip, br")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(gunzip(&read_body(res).await), DATA);
+    }
+
+    #[crate::rt_test]
+    async fn test_precompressed() {
+        let srv = service(Compress::default().precompressed(true)).await;
+
+        let req = TestRequest::with_uri("/pre")
+            .header(ACCEPT_ENCODING, "br, gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "br");
+        assert_eq!(res.headers().get(VARY).unwrap(), "accept-encoding");
+        assert_eq!(res.headers().get(CONTENT_TYPE).unwrap(), "text/plain");
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "W/\"v1\"");
+        assert_eq!(read_body(res).await, "br-data");
+
+        let req = TestRequest::with_uri("/pre").to_request();
+        let res = call_service(&srv, req).await;
+        assert!(!res.headers().contains_key(CONTENT_ENCODING));
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"v1\"");
+
+        let req = TestRequest::with_uri("/pre")
+            .header(ACCEPT_ENCODING, "gzip")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "gzip");
+        assert_eq!(res.headers().get(VARY).unwrap(), "accept-encoding");
+        assert_eq!(gunzip(&read_body(res).await), DATA);
+
+        // precompressed mode is disabled
+        let srv = service(Compress::default()).await;
+        let req = TestRequest::with_uri("/pre")
+            .header(ACCEPT_ENCODING, "br")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get(CONTENT_ENCODING).unwrap(), "br");
+        assert!(!res.headers().contains_key(VARY));
+        assert_ne!(read_body(res).await, "br-data");
     }
 }
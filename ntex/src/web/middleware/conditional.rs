@@ -0,0 +1,387 @@
+//! Middleware for conditional requests handling
+use std::task::{Context, Poll};
+use std::{convert::TryFrom, future::Future, pin::Pin, rc::Rc};
+
+use crate::http::body::{Body, ResponseBody};
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue, Value};
+use crate::http::header::{EntityTag, IfMatch, IfModifiedSince, IfNoneMatch};
+use crate::http::header::{IfUnmodifiedSince, LastModified};
+use crate::http::{Method, RequestHead};
+use crate::service::{Service, Transform};
+use crate::web::{HttpResponse, WebRequest, WebResponse};
+
+/// Response headers that are preserved in `304 Not Modified` response
+const NOT_MODIFIED_HEADERS: [HeaderName; 7] = [
+    header::CACHE_CONTROL,
+    header::CONTENT_LOCATION,
+    header::DATE,
+    header::ETAG,
+    header::EXPIRES,
+    header::LAST_MODIFIED,
+    header::VARY,
+];
+
+/// `Middleware` for conditional requests handling.
+///
+/// Middleware evaluates `If-Match`, `If-Unmodified-Since`, `If-None-Match`
+/// and `If-Modified-Since` request headers against `ETag` and `Last-Modified`
+/// headers of successful responses. Depending on evaluation result, response
+/// is replaced with `304 Not Modified` or `412 Precondition Failed` response.
+///
+/// Preconditions are evaluated after request handling, so middleware handles
+/// only safe `GET` and `HEAD` requests. Requests with other methods are passed
+/// through unchanged, their handlers must check preconditions themselves
+/// before applying any changes.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::Conditional::new().etag(true))
+///         .service(
+///             web::resource("/test")
+///                 .route(web::get().to(|| async { HttpResponse::Ok().body("data") }))
+///         );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Conditional {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    etag: bool,
+}
+
+impl Default for Conditional {
+    fn default() -> Self {
+        Conditional {
+            inner: Rc::new(Inner { etag: false }),
+        }
+    }
+}
+
+impl Conditional {
+    /// Construct `Conditional` middleware.
+    pub fn new() -> Conditional {
+        Conditional::default()
+    }
+
+    /// Compute strong `ETag` for responses with in-memory body.
+    ///
+    /// Tag is computed only for successful responses without `ETag` header.
+    /// Default is false.
+    pub fn etag(mut self, value: bool) -> Self {
+        Rc::get_mut(&mut self.inner)
+            .expect("Multiple copies exist")
+            .etag = value;
+        self
+    }
+}
+
+impl<S> Transform<S> for Conditional {
+    type Service = ConditionalMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        ConditionalMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct ConditionalMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, E> Service<WebRequest<E>> for ConditionalMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        // unsafe methods change state before preconditions could be evaluated
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return Box::pin(self.service.call(req));
+        }
+
+        let inner = self.inner.clone();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+            if !res.status().is_success() {
+                return Ok(res);
+            }
+
+            if inner.etag && !res.headers().contains_key(header::ETAG) {
+                if let Some(etag) = body_etag(res.response().body()) {
+                    res.headers_mut().insert(header::ETAG, etag);
+                }
+            }
+
+            match evaluate(res.request().head(), res.headers()) {
+                Precondition::Passed => Ok(res),
+                Precondition::NotModified => {
+                    let mut not_modified = HttpResponse::NotModified().finish();
+                    for name in NOT_MODIFIED_HEADERS.iter() {
+                        for value in res.headers().get_all(name) {
+                            not_modified
+                                .headers_mut()
+                                .append(name.clone(), value.clone());
+                        }
+                    }
+                    Ok(res.into_response(not_modified))
+                }
+                Precondition::Failed => {
+                    Ok(res.into_response(HttpResponse::PreconditionFailed().finish()))
+                }
+            }
+        })
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Precondition {
+    Passed,
+    NotModified,
+    Failed,
+}
+
+/// Strong entity tag computed from in-memory body
+fn body_etag(body: &ResponseBody<Body>) -> Option<HeaderValue> {
+    use sha1::Digest;
+
+    match body {
+        ResponseBody::Body(Body::Bytes(ref bytes))
+        | ResponseBody::Other(Body::Bytes(ref bytes)) => {
+            let hash = sha1::Sha1::digest(bytes);
+            let tag = base64::encode_config(hash, base64::URL_SAFE_NO_PAD);
+            HeaderValue::try_from(EntityTag::strong(tag).to_string()).ok()
+        }
+        _ => None,
+    }
+}
+
+fn typed<T: TryFrom<Value>>(headers: &HeaderMap, name: HeaderName) -> Option<T> {
+    let mut values = headers.get_all(name).cloned().peekable();
+    values.peek()?;
+    T::try_from(values.collect()).ok()
+}
+
+/// Evaluate preconditions of `GET` or `HEAD` request, rfc7232 section 6
+fn evaluate(head: &RequestHead, headers: &HeaderMap) -> Precondition {
+    let etag = headers
+        .get(header::ETAG)
+        .and_then(|val| EntityTag::try_from(val.clone()).ok());
+    let modified =
+        typed::<LastModified>(headers, header::LAST_MODIFIED).map(|modified| modified.0);
+
+    if let Some(if_match) = typed::<IfMatch>(&head.headers, header::IF_MATCH) {
+        if !if_match.is_match(etag.as_ref()) {
+            return Precondition::Failed;
+        }
+    } else if let Some(since) =
+        typed::<IfUnmodifiedSince>(&head.headers, header::IF_UNMODIFIED_SINCE)
+    {
+        if modified.map(|m| since.is_modified(m)).unwrap_or(false) {
+            return Precondition::Failed;
+        }
+    }
+
+    if let Some(if_none_match) = typed::<IfNoneMatch>(&head.headers, header::IF_NONE_MATCH)
+    {
+        if if_none_match.is_match(etag.as_ref()) {
+            return Precondition::NotModified;
+        }
+    } else if let Some(since) =
+        typed::<IfModifiedSince>(&head.headers, header::IF_MODIFIED_SINCE)
+    {
+        if modified.map(|m| !since.is_modified(m)).unwrap_or(false) {
+            return Precondition::NotModified;
+        }
+    }
+    Precondition::Passed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::IntoService;
+    use crate::util::lazy;
+    use crate::web::request::WebRequest;
+    use crate::web::test::{ok_service, read_body, TestRequest};
+    use crate::web::{DefaultError, Error};
+
+    const MODIFIED: &str = "Sun, 06 Nov 1994 08:49:37 GMT";
+
+    fn srv() -> impl Service<WebRequest<DefaultError>, Response = WebResponse, Error = Error>
+    {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(
+                req.into_response(
+                    HttpResponse::Ok()
+                        .header(header::ETAG, "\"v1\"")
+                        .header(header::LAST_MODIFIED, MODIFIED)
+                        .header(header::CACHE_CONTROL, "max-age=60")
+                        .header(header::CONTENT_TYPE, "text/plain")
+                        .body("data"),
+                ),
+            )
+        };
+        Conditional::new().new_transform(srv.into_service())
+    }
+
+    async fn status(req: TestRequest) -> StatusCode {
+        srv().call(req.to_srv_request()).await.unwrap().status()
+    }
+
+    #[crate::rt_test]
+    async fn test_if_none_match() {
+        let srv = srv();
+        assert!(lazy(|cx| srv.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| srv.poll_shutdown(cx, true).is_ready()).await);
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "W/\"v1\"");
+        let res = srv.call(req.to_srv_request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"v1\"");
+        assert_eq!(res.headers().get(header::LAST_MODIFIED).unwrap(), MODIFIED);
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "max-age=60"
+        );
+        assert!(!res.headers().contains_key(header::CONTENT_TYPE));
+        assert!(read_body(res).await.is_empty());
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "\"v2\"");
+        assert_eq!(status(req).await, StatusCode::OK);
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "*");
+        assert_eq!(status(req).await, StatusCode::NOT_MODIFIED);
+
+        let req =
+            TestRequest::with_header(header::IF_NONE_MATCH, "\"v1\"").method(Method::HEAD);
+        assert_eq!(status(req).await, StatusCode::NOT_MODIFIED);
+
+        // if-none-match takes precedence over if-modified-since
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "\"v2\"")
+            .header(header::IF_MODIFIED_SINCE, MODIFIED);
+        assert_eq!(status(req).await, StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_if_modified_since() {
+        let req = TestRequest::with_header(header::IF_MODIFIED_SINCE, MODIFIED);
+        assert_eq!(status(req).await, StatusCode::NOT_MODIFIED);
+
+        let req = TestRequest::with_header(
+            header::IF_MODIFIED_SINCE,
+            "Sat, 05 Nov 1994 08:49:37 GMT",
+        );
+        assert_eq!(status(req).await, StatusCode::OK);
+
+        let req = TestRequest::with_header(header::IF_MODIFIED_SINCE, MODIFIED)
+            .method(Method::POST);
+        assert_eq!(status(req).await, StatusCode::OK);
+
+        let req = TestRequest::with_header(header::IF_MODIFIED_SINCE, "invalid");
+        assert_eq!(status(req).await, StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_if_match() {
+        let req = TestRequest::with_header(header::IF_MATCH, "\"v1\"");
+        assert_eq!(status(req).await, StatusCode::OK);
+
+        let req = TestRequest::with_header(header::IF_MATCH, "W/\"v1\"");
+        assert_eq!(status(req).await, StatusCode::PRECONDITION_FAILED);
+
+        let req = TestRequest::with_header(header::IF_MATCH, "\"v2\"")
+            .header(header::IF_UNMODIFIED_SINCE, MODIFIED);
+        assert_eq!(status(req).await, StatusCode::PRECONDITION_FAILED);
+
+        let req = TestRequest::with_header(header::IF_UNMODIFIED_SINCE, MODIFIED);
+        assert_eq!(status(req).await, StatusCode::OK);
+
+        let req = TestRequest::with_header(
+            header::IF_UNMODIFIED_SINCE,
+            "Sat, 05 Nov 1994 08:49:37 GMT",
+        );
+        assert_eq!(status(req).await, StatusCode::PRECONDITION_FAILED);
+    }
+
+    #[crate::rt_test]
+    async fn test_unsafe_methods() {
+        let req = TestRequest::with_header(header::IF_MATCH, "\"v2\"").method(Method::PUT);
+        assert_eq!(status(req).await, StatusCode::OK);
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, "*").method(Method::POST);
+        assert_eq!(status(req).await, StatusCode::OK);
+
+        let req = TestRequest::with_header(
+            header::IF_UNMODIFIED_SINCE,
+            "Sat, 05 Nov 1994 08:49:37 GMT",
+        )
+        .method(Method::DELETE);
+        assert_eq!(status(req).await, StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_etag() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().body("data")))
+        };
+        let srv = Conditional::new()
+            .etag(true)
+            .new_transform(srv.into_service());
+
+        let res = srv
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        let etag = res.headers().get(header::ETAG).unwrap().clone();
+        assert!(!EntityTag::try_from(etag.clone()).unwrap().is_weak());
+
+        let req = TestRequest::with_header(header::IF_NONE_MATCH, etag.clone());
+        let res = srv.call(req.to_srv_request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_MODIFIED);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), etag);
+
+        // etag is not computed by default
+        let res = Conditional::new()
+            .new_transform(ok_service())
+            .call(TestRequest::default().to_srv_request())
+            .await
+            .unwrap();
+        assert!(!res.headers().contains_key(header::ETAG));
+
+        // errors are not changed
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::NotFound().finish()))
+        };
+        let srv = Conditional::new()
+            .etag(true)
+            .new_transform(srv.into_service());
+        let req = TestRequest::with_header(header::IF_MATCH, "\"v1\"");
+        let res = srv.call(req.to_srv_request()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
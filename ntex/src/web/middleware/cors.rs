@@ -0,0 +1,645 @@
+//! Cross-origin resource sharing (CORS) middleware
+use std::task::{Context, Poll};
+use std::{convert::TryFrom, fmt, future::Future, pin::Pin, rc::Rc};
+
+use crate::http::error::HttpError;
+use crate::http::header::{self, HeaderName, HeaderValue};
+use crate::http::{Method, RequestHead};
+use crate::service::{Service, Transform};
+use crate::util::{Either, HashSet, Ready};
+use crate::web::error::{CorsError, ErrorRenderer};
+use crate::web::{HttpResponse, WebRequest, WebResponse};
+
+/// `Middleware` for cross-origin resource sharing (CORS).
+///
+/// Middleware answers preflight `OPTIONS` requests itself, actual requests
+/// are passed to the inner service and response is decorated with CORS
+/// headers. Responses get `Vary: Origin` header unless any origin is
+/// allowed, so cached response is not served to other origins.
+///
+/// Allowed origin could be defined as exact value (`https://example.com`),
+/// wildcard pattern (`https://*.example.com`), any origin (`*`) or predicate.
+/// By default no origin is allowed. Any origin could not be combined with
+/// credentials support.
+///
+/// ```rust
+/// use ntex::http::{header, Method};
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(
+///             middleware::Cors::new()
+///                 .allowed_origin("https://www.rust-lang.org")
+///                 .allowed_origin("https://*.ntex.rs")
+///                 .allowed_methods(vec![Method::GET, Method::POST])
+///                 .allowed_header(header::CONTENT_TYPE)
+///                 .expose_header("x-version")
+///                 .supports_credentials()
+///                 .max_age(3600)
+///         )
+///         .service(
+///             web::resource("/test")
+///                 .route(web::get().to(|| async { HttpResponse::Ok() }))
+///         );
+/// }
+/// ```
+pub struct Cors {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    any_origin: bool,
+    origins: HashSet<String>,
+    wildcards: Vec<(String, String)>,
+    predicates: Vec<Box<dyn Fn(&str, &RequestHead) -> bool>>,
+    methods: Vec<Method>,
+    headers: Option<HashSet<HeaderName>>,
+    expose: Vec<HeaderName>,
+    credentials: bool,
+    max_age: Option<usize>,
+}
+
+impl Default for Cors {
+    fn default() -> Self {
+        Cors {
+            inner: Rc::new(Inner {
+                any_origin: false,
+                origins: HashSet::default(),
+                wildcards: Vec::new(),
+                predicates: Vec::new(),
+                methods: vec![
+                    Method::GET,
+                    Method::HEAD,
+                    Method::POST,
+                    Method::PUT,
+                    Method::PATCH,
+                    Method::DELETE,
+                ],
+                headers: None,
+                expose: Vec::new(),
+                credentials: false,
+                max_age: None,
+            }),
+        }
+    }
+}
+
+impl Cors {
+    /// Construct `Cors` middleware.
+    pub fn new() -> Cors {
+        Cors::default()
+    }
+
+    fn inner(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Add allowed origin.
+    ///
+    /// Origin is either exact value, pattern with one `*` wildcard,
+    /// or `*` which allows any origin.
+    pub fn allowed_origin(mut self, origin: &str) -> Self {
+        let origin = origin.trim();
+        if origin == "*" {
+            assert!(
+                !self.inner().credentials,
+                "Any origin could not be allowed with credentials support"
+            );
+            self.inner().any_origin = true;
+        } else if let Some(idx) = origin.find('*') {
+            let prefix = origin[..idx].to_ascii_lowercase();
+            let suffix = origin[idx + 1..].to_ascii_lowercase();
+            self.inner().wildcards.push((prefix, suffix));
+        } else {
+            self.inner().origins.insert(origin.to_ascii_lowercase());
+        }
+        self
+    }
+
+    /// Add predicate for allowed origins.
+    ///
+    /// Predicate receives value of request's `Origin` header.
+    pub fn allowed_origin_fn<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&str, &RequestHead) -> bool + 'static,
+    {
+        self.inner().predicates.push(Box::new(f));
+        self
+    }
+
+    /// Set allowed methods.
+    ///
+    /// By default `GET`, `HEAD`, `POST`, `PUT`, `PATCH` and `DELETE`
+    /// methods are allowed.
+    pub fn allowed_methods<U>(mut self, methods: U) -> Self
+    where
+        U: IntoIterator<Item = Method>,
+    {
+        self.inner().methods = methods.into_iter().collect();
+        self
+    }
+
+    /// Add allowed request header.
+    ///
+    /// By default any header requested in preflight request is allowed.
+    pub fn allowed_header<H>(mut self, header: H) -> Self
+    where
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        match HeaderName::try_from(header) {
+            Ok(header) => {
+                self.inner()
+                    .headers
+                    .get_or_insert_with(HashSet::default)
+                    .insert(header);
+            }
+            Err(_) => panic!("Cannot create header name"),
+        }
+        self
+    }
+
+    /// Set allowed request headers.
+    pub fn allowed_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        for h in headers {
+            self = self.allowed_header(h);
+        }
+        self
+    }
+
+    /// Add header that is exposed to the client.
+    pub fn expose_header<H>(mut self, header: H) -> Self
+    where
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        match HeaderName::try_from(header) {
+            Ok(header) => self.inner().expose.push(header),
+            Err(_) => panic!("Cannot create header name"),
+        }
+        self
+    }
+
+    /// Set headers that are exposed to the client.
+    pub fn expose_headers<U, H>(mut self, headers: U) -> Self
+    where
+        U: IntoIterator<Item = H>,
+        HeaderName: TryFrom<H>,
+        <HeaderName as TryFrom<H>>::Error: Into<HttpError>,
+    {
+        for h in headers {
+            self = self.expose_header(h);
+        }
+        self
+    }
+
+    /// Allow requests with credentials (cookies, authorization headers).
+    ///
+    /// Panics if any origin (`*`) is allowed.
+    pub fn supports_credentials(mut self) -> Self {
+        assert!(
+            !self.inner().any_origin,
+            "Any origin could not be allowed with credentials support"
+        );
+        self.inner().credentials = true;
+        self
+    }
+
+    /// Set max time in seconds the preflight response could be cached.
+    pub fn max_age(mut self, secs: usize) -> Self {
+        self.inner().max_age = Some(secs);
+        self
+    }
+}
+
+impl Inner {
+    fn is_origin_allowed(&self, origin: &str, head: &RequestHead) -> bool {
+        if self.any_origin {
+            return true;
+        }
+        let lower = origin.to_ascii_lowercase();
+        self.origins.contains(&lower)
+            || self.wildcards.iter().any(|(prefix, suffix)| {
+                lower.len() > prefix.len() + suffix.len()
+                    && lower.starts_with(prefix.as_str())
+                    && lower.ends_with(suffix.as_str())
+            })
+            || self.predicates.iter().any(|f| f(origin, head))
+    }
+
+    /// Value for `Access-Control-Allow-Origin` header
+    fn allow_origin(&self, origin: &HeaderValue) -> HeaderValue {
+        if self.any_origin {
+            HeaderValue::from_static("*")
+        } else {
+            origin.clone()
+        }
+    }
+
+    /// Check preflight request and build response
+    fn preflight(&self, head: &RequestHead) -> Result<HttpResponse, CorsError> {
+        let origin = head.headers.get(&header::ORIGIN).unwrap();
+        match origin.to_str() {
+            Ok(s) if self.is_origin_allowed(s, head) => (),
+            _ => return Err(CorsError::OriginNotAllowed),
+        }
+
+        let method = head
+            .headers
+            .get(&header::ACCESS_CONTROL_REQUEST_METHOD)
+            .unwrap()
+            .to_str()
+            .ok()
+            .and_then(|m| Method::try_from(m).ok())
+            .ok_or(CorsError::BadRequestMethod)?;
+        if !self.methods.contains(&method) {
+            return Err(CorsError::MethodNotAllowed);
+        }
+
+        let mut res = HttpResponse::Ok();
+
+        if let Some(hdrs) = head.headers.get(&header::ACCESS_CONTROL_REQUEST_HEADERS) {
+            let hdrs = hdrs.to_str().map_err(|_| CorsError::BadRequestHeaders)?;
+            if let Some(ref allowed) = self.headers {
+                for h in hdrs.split(',').map(|h| h.trim()).filter(|h| !h.is_empty()) {
+                    let h = HeaderName::try_from(h)
+                        .map_err(|_| CorsError::BadRequestHeaders)?;
+                    if !allowed.contains(&h) {
+                        return Err(CorsError::HeadersNotAllowed);
+                    }
+                }
+            }
+            if !hdrs.trim().is_empty() {
+                res.header(header::ACCESS_CONTROL_ALLOW_HEADERS, hdrs.trim());
+            }
+        }
+
+        res.header(
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin(origin),
+        )
+        .header(header::ACCESS_CONTROL_ALLOW_METHODS, join(&self.methods));
+        if self.credentials {
+            res.header(header::ACCESS_CONTROL_ALLOW_CREDENTIALS, "true");
+        }
+        if let Some(max_age) = self.max_age {
+            res.header(header::ACCESS_CONTROL_MAX_AGE, max_age);
+        }
+        Ok(res.finish())
+    }
+
+    /// Add CORS headers to actual response
+    fn decorate(&self, origin: &HeaderValue, res: &mut WebResponse) {
+        let headers = res.headers_mut();
+        headers.insert(
+            header::ACCESS_CONTROL_ALLOW_ORIGIN,
+            self.allow_origin(origin),
+        );
+        if self.credentials {
+            headers.insert(
+                header::ACCESS_CONTROL_ALLOW_CREDENTIALS,
+                HeaderValue::from_static("true"),
+            );
+        }
+        if !self.expose.is_empty() {
+            if let Ok(val) = HeaderValue::try_from(join(&self.expose)) {
+                headers.insert(header::ACCESS_CONTROL_EXPOSE_HEADERS, val);
+            }
+        }
+    }
+
+    /// Response depends on request's origin unless any origin is allowed
+    fn vary(&self, res: &mut WebResponse) {
+        if !self.any_origin {
+            res.headers_mut()
+                .append(header::VARY, HeaderValue::from_static("Origin"));
+        }
+    }
+}
+
+fn join<T: fmt::Display>(items: &[T]) -> String {
+    items
+        .iter()
+        .map(|item| item.to_string())
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl<S> Transform<S> for Cors {
+    type Service = CorsMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        CorsMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct CorsMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, E> Service<WebRequest<E>> for CorsMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+    E: ErrorRenderer,
+    CorsError: Into<E::Container>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Either<
+        Ready<Self::Response, Self::Error>,
+        Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>,
+    >;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let origin = req.headers().get(&header::ORIGIN).cloned();
+
+        // preflight request
+        if origin.is_some()
+            && req.method() == Method::OPTIONS
+            && req
+                .headers()
+                .contains_key(&header::ACCESS_CONTROL_REQUEST_METHOD)
+        {
+            let mut res = match self.inner.preflight(req.head()) {
+                Ok(res) => req.into_response(res),
+                Err(e) => req.error_response(e),
+            };
+            self.inner.vary(&mut res);
+            return Either::Left(Ready::Ok(res));
+        }
+
+        let origin = origin.filter(|origin| {
+            origin
+                .to_str()
+                .map(|s| self.inner.is_origin_allowed(s, req.head()))
+                .unwrap_or(false)
+        });
+
+        let inner = self.inner.clone();
+        let fut = self.service.call(req);
+        Either::Right(Box::pin(async move {
+            let mut res = fut.await?;
+            if let Some(origin) = origin {
+                inner.decorate(&origin, &mut res);
+            }
+            inner.vary(&mut res);
+            Ok(res)
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::IntoService;
+    use crate::util::lazy;
+    use crate::web::test::{ok_service, TestRequest};
+    use crate::web::{DefaultError, Error};
+
+    #[crate::rt_test]
+    async fn test_no_origin() {
+        let mw = Cors::new()
+            .allowed_origin("https://www.example.com")
+            .new_transform(ok_service());
+
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let req = TestRequest::default().to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "Origin");
+    }
+
+    #[crate::rt_test]
+    async fn test_preflight() {
+        let mw = Cors::new()
+            .allowed_origin("https://www.example.com")
+            .allowed_methods(vec![Method::GET, Method::POST])
+            .allowed_headers(vec![header::CONTENT_TYPE, header::AUTHORIZATION])
+            .max_age(3600)
+            .new_transform(ok_service());
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "https://www.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "POST")
+            .header(
+                header::ACCESS_CONTROL_REQUEST_HEADERS,
+                "content-type, authorization",
+            )
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://www.example.com"
+        );
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_METHODS)
+                .unwrap(),
+            "GET, POST"
+        );
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_HEADERS)
+                .unwrap(),
+            "content-type, authorization"
+        );
+        assert_eq!(
+            resp.headers().get(header::ACCESS_CONTROL_MAX_AGE).unwrap(),
+            "3600"
+        );
+        assert_eq!(resp.headers().get(header::VARY).unwrap(), "Origin");
+
+        // method is not allowed
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "https://www.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "DELETE")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // header is not allowed
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "https://www.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "x-custom")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // origin is not allowed
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "https://www.unknown.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "GET")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::FORBIDDEN);
+
+        // malformed method
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "https://www.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "G E T")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[crate::rt_test]
+    async fn test_actual_request() {
+        let srv =
+            |req: WebRequest<DefaultError>| async move {
+                Ok::<_, Error>(req.into_response(
+                    HttpResponse::Ok().header(header::VARY, "Accept").finish(),
+                ))
+            };
+        let mw = Cors::new()
+            .allowed_origin("https://*.example.com")
+            .expose_headers(vec!["x-version", "x-request-id"])
+            .supports_credentials()
+            .new_transform(srv.into_service());
+
+        let req = TestRequest::default()
+            .header(header::ORIGIN, "https://api.example.com")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "https://api.example.com"
+        );
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS)
+                .unwrap(),
+            "true"
+        );
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_EXPOSE_HEADERS)
+                .unwrap(),
+            "x-version, x-request-id"
+        );
+        let vary: Vec<_> = resp.headers().get_all(header::VARY).collect();
+        assert_eq!(vary.len(), 2);
+        assert!(vary.contains(&&HeaderValue::from_static("Accept")));
+        assert!(vary.contains(&&HeaderValue::from_static("Origin")));
+
+        // wildcard requires non empty match
+        let req = TestRequest::default()
+            .header(header::ORIGIN, "https://.example.com")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert!(!resp
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+        let vary: Vec<_> = resp.headers().get_all(header::VARY).collect();
+        assert!(vary.contains(&&HeaderValue::from_static("Origin")));
+    }
+
+    #[crate::rt_test]
+    async fn test_any_origin() {
+        let mw = Cors::new().allowed_origin("*").new_transform(ok_service());
+
+        let req = TestRequest::default()
+            .header(header::ORIGIN, "https://www.example.com")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "*"
+        );
+        assert!(!resp.headers().contains_key(header::VARY));
+
+        let req = TestRequest::default()
+            .method(Method::OPTIONS)
+            .header(header::ORIGIN, "https://www.example.com")
+            .header(header::ACCESS_CONTROL_REQUEST_METHOD, "PUT")
+            .header(header::ACCESS_CONTROL_REQUEST_HEADERS, "x-custom")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(resp.status(), StatusCode::OK);
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_HEADERS)
+                .unwrap(),
+            "x-custom"
+        );
+        assert!(!resp.headers().contains_key(header::VARY));
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_any_origin_credentials() {
+        let _ = Cors::new().allowed_origin("*").supports_credentials();
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_credentials_any_origin() {
+        let _ = Cors::new().supports_credentials().allowed_origin("*");
+    }
+
+    #[crate::rt_test]
+    async fn test_origin_fn() {
+        let mw = Cors::new()
+            .allowed_origin_fn(|origin, _| origin.ends_with(".local"))
+            .new_transform(ok_service());
+
+        let req = TestRequest::default()
+            .header(header::ORIGIN, "http://app.local")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert_eq!(
+            resp.headers()
+                .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+                .unwrap(),
+            "http://app.local"
+        );
+
+        let req = TestRequest::default()
+            .header(header::ORIGIN, "http://app.remote")
+            .to_srv_request();
+        let resp = mw.call(req).await.unwrap();
+        assert!(!resp
+            .headers()
+            .contains_key(header::ACCESS_CONTROL_ALLOW_ORIGIN));
+    }
+}
@@ -0,0 +1,692 @@
+//! Cross-site request forgery protection middleware
+use std::task::{Context, Poll};
+use std::{convert::TryFrom, future::Future, ops::Deref, pin::Pin, rc::Rc};
+
+use coo_kie::{Cookie, CookieJar, Key, SameSite};
+
+use crate::http::header::{self, HeaderName, HeaderValue};
+use crate::http::{h1, HttpMessage, Method, Payload, Uri};
+use crate::service::{Service, Transform};
+use crate::util::{stream_recv, BytesMut, Ready};
+use crate::web::error::{CsrfError, ErrorRenderer};
+use crate::web::{FromRequest, HttpRequest, WebRequest, WebResponse};
+
+use super::Session;
+
+/// Session key of synchronizer token
+const SESSION_KEY: &str = "csrf-token";
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Mode {
+    DoubleSubmit,
+    Synchronizer,
+}
+
+/// `Middleware` for cross-site request forgery protection.
+///
+/// Middleware issues CSRF token for every client and validates it for
+/// requests with unsafe methods, i.e. any method except `GET`, `HEAD`,
+/// `OPTIONS` and `TRACE`. Token must be submitted in `X-CSRF-Token` header or
+/// in `csrf_token` field of urlencoded form. Token of the current request
+/// is available with `CsrfToken` extractor.
+///
+/// Two token storage modes are supported:
+///
+/// * Double-submit, token is stored in `csrf-token` cookie signed with the key,
+///   submitted token must match the cookie. Cookie is not `HttpOnly`, so
+///   scripts could read it and submit either token or whole cookie value.
+///   Signature prevents forged tokens, but token issued to an attacker still
+///   could be planted to the victim's browser from a sibling subdomain. If
+///   subdomains are not trusted, use `__Host-` cookie name prefix, e.g.
+///   `cookie_name("__Host-csrf-token")`, or synchronizer mode.
+/// * Synchronizer, token is stored in `Session`, `Sessions` middleware must be
+///   registered after `Csrf` middleware.
+///
+/// Additionally, `Origin` header, or `Referer` header if origin is missing,
+/// of unsafe request must match request's own origin or one of allowed origins.
+/// Requests without both headers are checked by token only.
+///
+/// Failed checks result in `CsrfError`, by default `403 Forbidden` response
+/// is generated.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, middleware::Key, App, HttpResponse};
+///
+/// async fn form(token: middleware::CsrfToken) -> HttpResponse {
+///     HttpResponse::Ok().body(format!(
+///         "<form method=\"post\"><input type=\"hidden\" name=\"csrf_token\" value=\"{}\">",
+///         token.as_str()
+///     ))
+/// }
+///
+/// fn main() {
+///     let store = middleware::MemorySessionStore::new();
+///
+///     let app = App::new()
+///         .wrap(middleware::Csrf::synchronizer())
+///         .wrap(middleware::Sessions::new(store, Key::generate()))
+///         .service(web::resource("/").to(form));
+/// }
+/// ```
+pub struct Csrf {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    mode: Mode,
+    key: Option<Key>,
+    header: HeaderName,
+    field: String,
+    origins: Vec<String>,
+    form_limit: usize,
+    name: String,
+    path: String,
+    domain: Option<String>,
+    secure: bool,
+    same_site: Option<SameSite>,
+}
+
+impl Csrf {
+    fn new(mode: Mode, key: Option<Key>) -> Self {
+        Csrf {
+            inner: Rc::new(Inner {
+                mode,
+                key,
+                header: HeaderName::from_static("x-csrf-token"),
+                field: "csrf_token".to_string(),
+                origins: Vec::new(),
+                form_limit: 16_384,
+                name: "csrf-token".to_string(),
+                path: "/".to_string(),
+                domain: None,
+                secure: true,
+                same_site: Some(SameSite::Strict),
+            }),
+        }
+    }
+
+    /// Construct `Csrf` middleware with double-submit cookie tokens and
+    /// key for signing of token cookie.
+    pub fn double_submit(key: Key) -> Self {
+        Csrf::new(Mode::DoubleSubmit, Some(key))
+    }
+
+    /// Construct `Csrf` middleware with synchronizer tokens stored in session.
+    pub fn synchronizer() -> Self {
+        Csrf::new(Mode::Synchronizer, None)
+    }
+
+    fn inner(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Set name of token header, default is `X-CSRF-Token`.
+    pub fn header_name(mut self, name: &str) -> Self {
+        match HeaderName::try_from(name) {
+            Ok(name) => self.inner().header = name,
+            Err(_) => log::error!("Invalid header name: {}", name),
+        }
+        self
+    }
+
+    /// Set name of token form field, default is `csrf_token`.
+    pub fn field_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.inner().field = name.into();
+        self
+    }
+
+    /// Add allowed origin, e.g. `https://www.example.com`.
+    ///
+    /// Request's own origin is always allowed.
+    ///
+    /// Panics if origin is not valid.
+    pub fn allowed_origin(mut self, origin: &str) -> Self {
+        let origin = parse_origin(origin).expect("Invalid origin");
+        self.inner().origins.push(origin);
+        self
+    }
+
+    /// Set max size of urlencoded form that is read to find submitted token,
+    /// default is 16Kb.
+    pub fn form_limit(mut self, limit: usize) -> Self {
+        self.inner().form_limit = limit;
+        self
+    }
+
+    /// Set token cookie name, default is `csrf-token`.
+    pub fn cookie_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.inner().name = name.into();
+        self
+    }
+
+    /// Set token cookie path, default is `/`.
+    pub fn cookie_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.inner().path = path.into();
+        self
+    }
+
+    /// Set token cookie domain.
+    pub fn cookie_domain<D: Into<String>>(mut self, domain: D) -> Self {
+        self.inner().domain = Some(domain.into());
+        self
+    }
+
+    /// Set `Secure` attribute of token cookie, default is `true`.
+    pub fn cookie_secure(mut self, value: bool) -> Self {
+        self.inner().secure = value;
+        self
+    }
+
+    /// Set `SameSite` attribute of token cookie, default is `Strict`.
+    pub fn cookie_same_site(mut self, value: Option<SameSite>) -> Self {
+        self.inner().same_site = value;
+        self
+    }
+}
+
+impl Inner {
+    /// Token issued to the client
+    fn stored_token<E>(
+        &self,
+        req: &WebRequest<E>,
+        session: Option<&Session>,
+    ) -> Option<String> {
+        let token = match self.mode {
+            Mode::DoubleSubmit => self.verify(req.cookie(&self.name)?.value()),
+            Mode::Synchronizer => {
+                session.and_then(|s| s.get::<String>(SESSION_KEY).ok()?)
+            }
+        };
+        token.filter(|token| is_valid_token(token))
+    }
+
+    /// Verify signed cookie value and extract token
+    fn verify(&self, value: &str) -> Option<String> {
+        let mut jar = CookieJar::new();
+        jar.add_original(Cookie::new(self.name.clone(), value.to_string()));
+        jar.signed(self.key.as_ref()?)
+            .get(&self.name)
+            .map(|c| c.value().to_string())
+    }
+
+    /// Token submitted with unsafe request
+    async fn submitted_token<E>(&self, req: &mut WebRequest<E>) -> Option<String> {
+        if let Some(token) = req.headers().get(&self.header) {
+            return token.to_str().ok().map(|t| t.to_string());
+        }
+
+        let is_form = req
+            .headers()
+            .get(&header::CONTENT_TYPE)
+            .and_then(|val| val.to_str().ok())
+            .map(|val| {
+                val.split(';').next().unwrap_or("").trim()
+                    == "application/x-www-form-urlencoded"
+            })
+            .unwrap_or(false);
+        if !is_form {
+            return None;
+        }
+
+        // read form and restore payload for the handler
+        let mut payload = req.take_payload();
+        let mut body = BytesMut::new();
+        while let Some(chunk) = stream_recv(&mut payload).await {
+            match chunk {
+                Ok(chunk) if body.len() + chunk.len() <= self.form_limit => {
+                    body.extend_from_slice(&chunk)
+                }
+                _ => return None,
+            }
+        }
+        let body = body.freeze();
+        let token = serde_urlencoded::from_bytes::<Vec<(String, String)>>(&body)
+            .ok()?
+            .into_iter()
+            .find(|(name, _)| *name == self.field)
+            .map(|(_, value)| value);
+
+        let mut payload = h1::Payload::empty();
+        payload.unread_data(body);
+        req.set_payload(payload.into());
+        token
+    }
+
+    /// Check that unsafe request is same-origin
+    fn check_origin<E>(&self, req: &WebRequest<E>) -> bool {
+        let source = req
+            .headers()
+            .get(&header::ORIGIN)
+            .or_else(|| req.headers().get(&header::REFERER));
+        let source = match source {
+            Some(val) => val.to_str().ok().and_then(parse_origin),
+            None => return true,
+        };
+
+        if let Some(source) = source {
+            let info = req.connection_info();
+            let own = parse_origin(&format!("{}://{}", info.scheme(), info.host()));
+            own.as_ref() == Some(&source) || self.origins.contains(&source)
+        } else {
+            false
+        }
+    }
+
+    fn set_cookie(&self, res: &mut WebResponse, token: String) {
+        let key = if let Some(ref key) = self.key {
+            key
+        } else {
+            return;
+        };
+        let mut cookie = Cookie::new(self.name.clone(), token);
+        cookie.set_path(self.path.clone());
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(false);
+        cookie.set_same_site(self.same_site);
+        if let Some(ref domain) = self.domain {
+            cookie.set_domain(domain.clone());
+        }
+
+        let mut jar = CookieJar::new();
+        jar.signed_mut(key).add(cookie);
+        for cookie in jar.delta() {
+            if let Ok(val) = HeaderValue::from_str(&cookie.encoded().to_string()) {
+                res.headers_mut().append(header::SET_COOKIE, val);
+            }
+        }
+    }
+}
+
+/// Serialized origin of the url, default port is omitted
+fn parse_origin(value: &str) -> Option<String> {
+    let uri = Uri::try_from(value).ok()?;
+    let scheme = uri.scheme_str()?.to_ascii_lowercase();
+    let host = uri.host()?.to_ascii_lowercase();
+    match (scheme.as_str(), uri.port_u16()) {
+        ("http", Some(80)) | ("https", Some(443)) | (_, None) => {
+            Some(format!("{}://{}", scheme, host))
+        }
+        (_, Some(port)) => Some(format!("{}://{}:{}", scheme, host, port)),
+    }
+}
+
+/// Generate random token
+///
+/// Token is generated by operating system's secure random number generator.
+fn generate_token() -> String {
+    let mut buf = [0u8; 32];
+    getrandom::getrandom(&mut buf)
+        .expect("Secure random number generator is not available");
+    base64::encode_config(buf, base64::URL_SAFE_NO_PAD)
+}
+
+fn is_valid_token(token: &str) -> bool {
+    token.len() == 43
+        && token
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'_')
+}
+
+/// Compare tokens in constant time
+fn token_eq(a: &str, b: &str) -> bool {
+    a.len() == b.len()
+        && a.bytes()
+            .zip(b.bytes())
+            .fold(0, |acc, (a, b)| acc | (a ^ b))
+            == 0
+}
+
+fn is_safe(method: &Method) -> bool {
+    matches!(
+        *method,
+        Method::GET | Method::HEAD | Method::OPTIONS | Method::TRACE
+    )
+}
+
+impl<S> Transform<S> for Csrf {
+    type Service = CsrfMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        CsrfMiddleware {
+            service: Rc::new(service),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct CsrfMiddleware<S> {
+    service: Rc<S>,
+    inner: Rc<Inner>,
+}
+
+impl<S, E> Service<WebRequest<E>> for CsrfMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse> + 'static,
+    E: ErrorRenderer,
+    CsrfError: Into<E::Container>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, mut req: WebRequest<E>) -> Self::Future {
+        let srv = self.service.clone();
+        let inner = self.inner.clone();
+
+        Box::pin(async move {
+            let session = if inner.mode == Mode::Synchronizer {
+                let session = req.extensions().get::<Session>().cloned();
+                if session.is_none() {
+                    log::error!("Sessions middleware is required for synchronizer tokens");
+                }
+                session
+            } else {
+                None
+            };
+            let stored = inner.stored_token(&req, session.as_ref());
+
+            if !is_safe(req.method()) {
+                if !inner.check_origin(&req) {
+                    log::trace!("Cross-origin request: {}", req.path());
+                    return Ok(req.error_response(CsrfError::Origin));
+                }
+                // whole cookie value could be submitted in double-submit mode
+                let submitted = inner.submitted_token(&mut req).await.and_then(|token| {
+                    if is_valid_token(&token) {
+                        Some(token)
+                    } else {
+                        inner.verify(&token)
+                    }
+                });
+                let err = match (stored.as_ref(), submitted) {
+                    (Some(stored), Some(submitted)) => {
+                        if token_eq(stored, &submitted) {
+                            None
+                        } else {
+                            Some(CsrfError::InvalidToken)
+                        }
+                    }
+                    _ => Some(CsrfError::MissingToken),
+                };
+                if let Some(err) = err {
+                    log::trace!("CSRF token check failed: {}", req.path());
+                    return Ok(req.error_response(err));
+                }
+            }
+
+            let (token, issued) = match stored {
+                Some(token) => (token, false),
+                None => (generate_token(), true),
+            };
+            if issued {
+                if let Some(ref session) = session {
+                    let _ = session.insert(SESSION_KEY, &token);
+                }
+            }
+            req.extensions_mut().insert(CsrfToken(token.clone()));
+
+            let mut res = srv.call(req).await?;
+
+            if issued && inner.mode == Mode::DoubleSubmit {
+                inner.set_cookie(&mut res, token);
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// CSRF token
+///
+/// Token is assigned by `Csrf` middleware and must be submitted with unsafe
+/// requests. If middleware is not registered, extractor fails with
+/// `CsrfError::MissingToken` error.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct CsrfToken(String);
+
+impl CsrfToken {
+    /// Get token as string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwrap into inner `String`.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for CsrfToken {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for CsrfToken {
+    type Error = CsrfError;
+    type Future = Ready<CsrfToken, CsrfError>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        req.extensions()
+            .get::<CsrfToken>()
+            .cloned()
+            .ok_or(CsrfError::MissingToken)
+            .into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use coo_kie::Key;
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::web::middleware::{MemorySessionStore, Sessions};
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    async fn token(token: CsrfToken) -> HttpResponse {
+        HttpResponse::Ok().body(token.into_inner())
+    }
+
+    async fn submit(form: web::types::Form<Vec<(String, String)>>) -> HttpResponse {
+        HttpResponse::Ok().body(form.into_inner()[0].1.clone())
+    }
+
+    #[test]
+    fn test_origin() {
+        assert_eq!(
+            parse_origin("HTTPS://Example.com:443/path?q=1").unwrap(),
+            "https://example.com"
+        );
+        assert_eq!(
+            parse_origin("http://example.com:8080").unwrap(),
+            "http://example.com:8080"
+        );
+        assert_eq!(parse_origin("null"), None);
+        assert_eq!(parse_origin("/path"), None);
+        assert!(token_eq("abc", "abc"));
+        assert!(!token_eq("abc", "abd"));
+        assert!(!token_eq("abc", "ab"));
+        assert!(is_valid_token(&generate_token()));
+        assert_ne!(generate_token(), generate_token());
+    }
+
+    #[crate::rt_test]
+    async fn test_double_submit() {
+        let srv = init_service(
+            App::new()
+                .wrap(
+                    Csrf::double_submit(Key::generate())
+                        .allowed_origin("https://www.example.com"),
+                )
+                .service(
+                    web::resource("/")
+                        .route(web::get().to(token))
+                        .route(web::post().to(submit)),
+                ),
+        )
+        .await;
+
+        // token is issued
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        let cookie = res.response().cookies().next().unwrap().into_owned();
+        assert_eq!(cookie.name(), "csrf-token");
+        assert_ne!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.same_site(), Some(SameSite::Strict));
+        let token = String::from_utf8(read_body(res).await.to_vec()).unwrap();
+        assert!(is_valid_token(&token));
+        // cookie value is signed token
+        assert_ne!(cookie.value(), token);
+        assert!(cookie.value().ends_with(&token));
+
+        // token is reused
+        let req = TestRequest::default().cookie(cookie.clone()).to_request();
+        let res = call_service(&srv, req).await;
+        assert!(res.response().cookies().next().is_none());
+        assert_eq!(read_body(res).await, token.as_bytes());
+
+        // header token
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .header("x-csrf-token", token.as_str())
+            .set_form(&[("name", "value")])
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "value");
+
+        // form token, payload is available to the handler
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .header(header::ORIGIN, "https://www.example.com")
+            .set_form(&[("csrf_token", token.as_str())])
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, token.as_bytes());
+
+        // whole cookie value
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .header("x-csrf-token", cookie.value())
+            .set_form(&[("name", "value")])
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // unsigned cookie
+        let req = TestRequest::post()
+            .cookie(Cookie::new("csrf-token", token.clone()))
+            .header("x-csrf-token", token.as_str())
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(read_body(res).await, "CSRF token is missing");
+
+        // missing token
+        let req = TestRequest::post().cookie(cookie.clone()).to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        // missing cookie
+        let req = TestRequest::post()
+            .header("x-csrf-token", token.as_str())
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+        // invalid token
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .header("x-csrf-token", generate_token())
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(read_body(res).await, "CSRF token is invalid");
+
+        // cross-origin
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .header(header::HOST, "example.com")
+            .header(header::REFERER, "https://evil.com/form")
+            .header("x-csrf-token", token.as_str())
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(read_body(res).await, "Cross-origin request");
+
+        // same-origin
+        let req = TestRequest::post()
+            .cookie(cookie)
+            .header(header::HOST, "example.com")
+            .header(header::ORIGIN, "http://example.com")
+            .header("x-csrf-token", token.as_str())
+            .set_form(&[("name", "value")])
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_synchronizer() {
+        let srv = init_service(
+            App::new()
+                .wrap(Csrf::synchronizer().header_name("x-token"))
+                .wrap(
+                    Sessions::new(MemorySessionStore::new(), Key::generate())
+                        .cookie_secure(false),
+                )
+                .service(
+                    web::resource("/")
+                        .route(web::get().to(token))
+                        .route(web::post().to(token)),
+                ),
+        )
+        .await;
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        let cookie = res.response().cookies().next().unwrap().into_owned();
+        assert_eq!(cookie.name(), "id");
+        let token = read_body(res).await;
+
+        let req = TestRequest::post()
+            .cookie(cookie.clone())
+            .header("x-token", &token[..])
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, token);
+
+        // token from other session
+        let req = TestRequest::post()
+            .header("x-token", &token[..])
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+        assert_eq!(read_body(res).await, "CSRF token is missing");
+    }
+
+    #[crate::rt_test]
+    async fn test_token_extractor() {
+        let srv = init_service(App::new().service(web::resource("/").to(token))).await;
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::FORBIDDEN);
+    }
+}
@@ -9,7 +9,9 @@ use crate::http::body::{Body, BodySize, MessageBody, ResponseBody};
 use crate::http::header::HeaderName;
 use crate::service::{Service, Transform};
 use crate::util::{Bytes, Either, HashSet};
-use crate::web::{HttpResponse, WebRequest, WebResponse};
+use crate::web::{HttpRequest, HttpResponse, WebRequest, WebResponse};
+
+use super::ReqId;
 
 /// `Middleware` for logging request and response info to the terminal.
 ///
@@ -67,6 +69,8 @@ use crate::web::{HttpResponse, WebRequest, WebResponse};
 ///
 /// `%{FOO}e`  os.environ['FOO']
 ///
+/// `%{request-id}`  Request id assigned by `RequestId` middleware
+///
 pub struct Logger {
     inner: Rc<Inner>,
 }
@@ -199,6 +203,7 @@ where
         if let Some(ref mut format) = this.format {
             for unit in &mut format.0 {
                 unit.render_response(res.response());
+                unit.render_request_id(res.request());
             }
         }
 
@@ -275,7 +280,7 @@ impl Format {
     /// Returns `None` if the format string syntax is incorrect.
     fn new(s: &str) -> Format {
         log::trace!("Access log format: {}", s);
-        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([ioe])|[atPrUsbTD]?)").unwrap();
+        let fmt = Regex::new(r"%(\{([A-Za-z0-9\-_]+)\}([ioe]?)|[atPrUsbTD]?)").unwrap();
 
         let mut idx = 0;
         let mut results = Vec::new();
@@ -296,7 +301,8 @@ impl Format {
                         HeaderName::try_from(key.as_str()).unwrap(),
                     ),
                     "e" => FormatText::EnvironHeader(key.as_str().to_owned()),
-                    _ => unreachable!(),
+                    "" if key.as_str() == "request-id" => FormatText::RequestId,
+                    _ => FormatText::Str(m.as_str().to_owned()),
                 })
             } else {
                 let m = cap.get(1).unwrap();
@@ -340,6 +346,7 @@ enum FormatText {
     RequestHeader(HeaderName),
     ResponseHeader(HeaderName),
     EnvironHeader(String),
+    RequestId,
 }
 
 impl FormatText {
@@ -370,6 +377,7 @@ impl FormatText {
                     "-".fmt(fmt)
                 }
             }
+            FormatText::RequestId => "-".fmt(fmt),
             _ => Ok(()),
         }
     }
@@ -395,6 +403,14 @@ impl FormatText {
         }
     }
 
+    fn render_request_id(&mut self, req: &HttpRequest) {
+        if let FormatText::RequestId = *self {
+            if let Some(id) = req.extensions().get::<ReqId>() {
+                *self = FormatText::Str(id.to_string());
+            }
+        }
+    }
+
     fn render_request<E>(&mut self, now: time::SystemTime, req: &WebRequest<E>) {
         match *self {
             FormatText::RequestLine => {
@@ -439,6 +455,11 @@ impl FormatText {
                 };
                 *self = s;
             }
+            FormatText::RequestId => {
+                if let Some(id) = req.extensions().get::<ReqId>() {
+                    *self = FormatText::Str(id.to_string());
+                }
+            }
             _ => (),
         }
     }
@@ -586,4 +607,41 @@ mod tests {
         let s = format!("{}", FormatDisplay(&render));
         assert!(s.contains(&httpdate::HttpDate::from(now).to_string()));
     }
+
+    #[crate::rt_test]
+    async fn test_request_id_format() {
+        let mut format = Format::new("%{request-id} %{unknown}");
+        let (req, mut pl) = TestRequest::default().to_http_parts();
+        let id = test::from_request::<ReqId>(&req, &mut pl).await.unwrap();
+        let req = WebRequest::<DefaultError>::new(req);
+
+        let now = time::SystemTime::now();
+        for unit in &mut format.0 {
+            unit.render_request(now, &req);
+        }
+
+        let render = |fmt: &mut fmt::Formatter<'_>| {
+            for unit in &format.0 {
+                unit.render(fmt, 1024, now)?;
+            }
+            Ok(())
+        };
+        assert_eq!(
+            format!("{}", FormatDisplay(&render)),
+            format!("{} %{{unknown}}", id)
+        );
+
+        let mut format = Format::new("%{request-id}");
+        let (req, _) = TestRequest::default().to_srv_request().into_parts();
+        for unit in &mut format.0 {
+            unit.render_request_id(&req);
+        }
+        let render = |fmt: &mut fmt::Formatter<'_>| {
+            for unit in &format.0 {
+                unit.render(fmt, 1024, now)?;
+            }
+            Ok(())
+        };
+        assert_eq!(format!("{}", FormatDisplay(&render)), "-");
+    }
 }
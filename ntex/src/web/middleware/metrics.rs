@@ -0,0 +1,487 @@
+//! Prometheus metrics middleware
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use std::{fmt::Write, future::Future, pin::Pin, time::Instant};
+
+use crate::http::{header, Method, StatusCode};
+use crate::service::{Service, Transform};
+use crate::util::{HashMap, Ready};
+use crate::web::dev::{WebServiceConfig, WebServiceFactory};
+use crate::web::{self, ErrorRenderer, HttpResponse, WebRequest, WebResponse};
+
+/// Default latency histogram buckets, in seconds
+const DEFAULT_BUCKETS: [f64; 11] = [
+    0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0,
+];
+
+/// Endpoint label value for requests that did not match any resource
+const UNMATCHED: &str = "<unmatched>";
+
+/// `Middleware` for collecting http request metrics.
+///
+/// Middleware records request counters and latency histograms per method
+/// and matched resource pattern (`/users/{id}` rather than `/users/1`),
+/// response status classes (`2xx`, `4xx`, etc) and number of requests
+/// that are currently in flight. Requests that do not match any resource
+/// are recorded with `<unmatched>` endpoint, non-standard methods are
+/// recorded as `OTHER` method.
+///
+/// Collected metrics are rendered in Prometheus text exposition format
+/// by [`MetricsExporter`] service. Registry is shared between all clones
+/// of the middleware, so it could be created once and cloned into every
+/// worker's application.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let metrics = middleware::Metrics::new().namespace("app");
+///
+///     let app = App::new()
+///         .wrap(metrics.clone())
+///         .service(metrics.exporter("/metrics"))
+///         .service(web::resource("/users/{id}").to(|| async { HttpResponse::Ok() }));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Metrics {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    namespace: String,
+    buckets: Vec<f64>,
+    in_flight: AtomicUsize,
+    series: Mutex<HashMap<String, HashMap<&'static str, Series>>>,
+}
+
+struct Series {
+    statuses: [u64; 10],
+    buckets: Vec<u64>,
+    count: u64,
+    sum: f64,
+}
+
+impl Default for Metrics {
+    fn default() -> Self {
+        Metrics {
+            inner: Arc::new(Inner {
+                namespace: String::new(),
+                buckets: DEFAULT_BUCKETS.to_vec(),
+                in_flight: AtomicUsize::new(0),
+                series: Mutex::new(HashMap::default()),
+            }),
+        }
+    }
+}
+
+impl Metrics {
+    /// Construct `Metrics` middleware with empty registry.
+    pub fn new() -> Metrics {
+        Metrics::default()
+    }
+
+    /// Set metrics namespace.
+    ///
+    /// Namespace is used as a prefix for metric names, i.e.
+    /// `<namespace>_http_requests_total`.
+    pub fn namespace<T: Into<String>>(mut self, namespace: T) -> Self {
+        self.inner_mut().namespace = namespace.into();
+        self
+    }
+
+    /// Set latency histogram buckets, in seconds.
+    ///
+    /// By default `0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1, 2.5, 5, 10`
+    /// buckets are used.
+    pub fn buckets<T: IntoIterator<Item = f64>>(mut self, buckets: T) -> Self {
+        let mut buckets: Vec<f64> = buckets.into_iter().filter(|b| b.is_finite()).collect();
+        buckets.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        buckets.dedup();
+        self.inner_mut().buckets = buckets;
+        self
+    }
+
+    /// Create service that renders collected metrics.
+    pub fn exporter<T: Into<String>>(&self, path: T) -> MetricsExporter {
+        MetricsExporter {
+            path: path.into(),
+            metrics: self.clone(),
+        }
+    }
+
+    /// Render collected metrics in Prometheus text exposition format.
+    pub fn render(&self) -> String {
+        self.inner.render()
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Arc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+}
+
+impl<S> Transform<S> for Metrics {
+    type Service = MetricsMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        MetricsMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct MetricsMiddleware<S> {
+    service: S,
+    inner: Arc<Inner>,
+}
+
+impl<S, E> Service<WebRequest<E>> for MetricsMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let method = method_label(req.method());
+        let guard = InFlight::new(self.inner.clone());
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let res = fut.await;
+            let elapsed = guard.start.elapsed().as_secs_f64();
+
+            match res {
+                Ok(res) => {
+                    let endpoint = res.request().match_pattern().unwrap_or(UNMATCHED);
+                    guard.inner.record(method, endpoint, res.status(), elapsed);
+                    Ok(res)
+                }
+                Err(e) => {
+                    let status = StatusCode::INTERNAL_SERVER_ERROR;
+                    guard.inner.record(method, UNMATCHED, status, elapsed);
+                    Err(e)
+                }
+            }
+        })
+    }
+}
+
+/// Label for request method, custom methods share single label
+fn method_label(method: &Method) -> &'static str {
+    match *method {
+        Method::GET => "GET",
+        Method::HEAD => "HEAD",
+        Method::POST => "POST",
+        Method::PUT => "PUT",
+        Method::DELETE => "DELETE",
+        Method::CONNECT => "CONNECT",
+        Method::OPTIONS => "OPTIONS",
+        Method::TRACE => "TRACE",
+        Method::PATCH => "PATCH",
+        _ => "OTHER",
+    }
+}
+
+/// Tracks number of in-flight requests, also for dropped futures
+struct InFlight {
+    inner: Arc<Inner>,
+    start: Instant,
+}
+
+impl InFlight {
+    fn new(inner: Arc<Inner>) -> Self {
+        inner.in_flight.fetch_add(1, Ordering::Relaxed);
+        InFlight {
+            inner,
+            start: Instant::now(),
+        }
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        self.inner.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+impl Inner {
+    fn record(
+        &self,
+        method: &'static str,
+        endpoint: &str,
+        status: StatusCode,
+        elapsed: f64,
+    ) {
+        let mut series = self.series.lock().unwrap();
+        let methods = if let Some(methods) = series.get_mut(endpoint) {
+            methods
+        } else {
+            series.entry(endpoint.to_string()).or_default()
+        };
+        let item = methods.entry(method).or_insert_with(|| Series {
+            statuses: [0; 10],
+            buckets: vec![0; self.buckets.len()],
+            count: 0,
+            sum: 0.0,
+        });
+
+        item.statuses[(status.as_u16() / 100) as usize % 10] += 1;
+        item.count += 1;
+        item.sum += elapsed;
+        if let Some(idx) = self.buckets.iter().position(|b| elapsed <= *b) {
+            item.buckets[idx] += 1;
+        }
+    }
+
+    fn render(&self) -> String {
+        let ns = if self.namespace.is_empty() {
+            String::new()
+        } else {
+            format!("{}_", self.namespace)
+        };
+        let series = self.series.lock().unwrap();
+
+        // stable output order
+        let mut items: Vec<_> = series
+            .iter()
+            .flat_map(|(endpoint, methods)| {
+                methods.iter().map(move |(method, s)| (endpoint, method, s))
+            })
+            .collect();
+        items.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+
+        let mut buf = String::new();
+        let _ = writeln!(
+            buf,
+            "# HELP {0}http_requests_total Total number of http requests.\n\
+             # TYPE {0}http_requests_total counter",
+            ns
+        );
+        for (endpoint, method, s) in &items {
+            for (class, count) in s.statuses.iter().enumerate() {
+                if *count != 0 {
+                    let _ = writeln!(
+                        buf,
+                        "{}http_requests_total{{method=\"{}\",endpoint=\"{}\",status=\"{}xx\"}} {}",
+                        ns,
+                        method,
+                        Escaped(endpoint),
+                        class,
+                        count
+                    );
+                }
+            }
+        }
+
+        let _ = writeln!(
+            buf,
+            "# HELP {0}http_request_duration_seconds Http request latency in seconds.\n\
+             # TYPE {0}http_request_duration_seconds histogram",
+            ns
+        );
+        for (endpoint, method, s) in &items {
+            let labels =
+                format!("method=\"{}\",endpoint=\"{}\"", method, Escaped(endpoint));
+            let mut total = 0;
+            for (bucket, count) in self.buckets.iter().zip(s.buckets.iter()) {
+                total += count;
+                let _ = writeln!(
+                    buf,
+                    "{}http_request_duration_seconds_bucket{{{},le=\"{}\"}} {}",
+                    ns, labels, bucket, total
+                );
+            }
+            let _ = writeln!(
+                buf,
+                "{0}http_request_duration_seconds_bucket{{{1},le=\"+Inf\"}} {2}\n\
+                 {0}http_request_duration_seconds_sum{{{1}}} {3}\n\
+                 {0}http_request_duration_seconds_count{{{1}}} {2}",
+                ns, labels, s.count, s.sum
+            );
+        }
+
+        let _ = writeln!(
+            buf,
+            "# HELP {0}http_requests_in_flight Number of http requests in flight.\n\
+             # TYPE {0}http_requests_in_flight gauge\n\
+             {0}http_requests_in_flight {1}",
+            ns,
+            self.in_flight.load(Ordering::Relaxed)
+        );
+        buf
+    }
+}
+
+/// Escape label value
+struct Escaped<'a>(&'a str);
+
+impl<'a> std::fmt::Display for Escaped<'a> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for ch in self.0.chars() {
+            match ch {
+                '\\' => f.write_str("\\\\")?,
+                '"' => f.write_str("\\\"")?,
+                '\n' => f.write_str("\\n")?,
+                _ => f.write_char(ch)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Service that renders collected metrics in Prometheus text exposition
+/// format.
+///
+/// Service responds to `GET` requests. Use [`Metrics::exporter()`] to
+/// construct it.
+pub struct MetricsExporter {
+    path: String,
+    metrics: Metrics,
+}
+
+impl<Err: ErrorRenderer> WebServiceFactory<Err> for MetricsExporter {
+    fn register(self, config: &mut WebServiceConfig<Err>) {
+        let metrics = self.metrics;
+        web::resource(self.path.as_str())
+            .route(web::get().to(move || {
+                Ready::<_, Err::Container>::Ok(
+                    HttpResponse::Ok()
+                        .content_type("text/plain; version=0.0.4; charset=utf-8")
+                        .header(header::CACHE_CONTROL, "no-cache")
+                        .body(metrics.render()),
+                )
+            }))
+            .register(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::IntoService;
+    use crate::util::lazy;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{App, DefaultError, Error};
+
+    #[crate::rt_test]
+    async fn test_metrics() {
+        let metrics = Metrics::new();
+        let srv = init_service(
+            App::new()
+                .wrap(metrics.clone())
+                .service(metrics.exporter("/metrics"))
+                .service(
+                    web::scope("/api").service(
+                        web::resource("/users/{id}")
+                            .to(|| async { HttpResponse::Ok().finish() }),
+                    ),
+                )
+                .service(
+                    web::resource("/error").to(|| async { HttpResponse::BadRequest() }),
+                ),
+        )
+        .await;
+
+        for uri in &["/api/users/1", "/api/users/2", "/error", "/unknown"] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let _ = call_service(&srv, req).await;
+        }
+        let req = TestRequest::with_uri("/api/users/3")
+            .method(Method::POST)
+            .to_request();
+        let _ = call_service(&srv, req).await;
+        for method in &["PURGE", "X-RANDOM-1", "X-RANDOM-2"] {
+            let req = TestRequest::with_uri("/api/users/4")
+                .method(Method::from_bytes(method.as_bytes()).unwrap())
+                .to_request();
+            let _ = call_service(&srv, req).await;
+        }
+
+        let req = TestRequest::with_uri("/metrics").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain; version=0.0.4; charset=utf-8"
+        );
+        let body = read_body(res).await;
+        let body = std::str::from_utf8(&body).unwrap();
+
+        assert!(body.contains("# TYPE http_requests_total counter\n"));
+        assert!(body.contains(
+            "http_requests_total{method=\"GET\",endpoint=\"/api/users/{id}\",status=\"2xx\"} 2\n"
+        ));
+        assert!(body.contains(
+            "http_requests_total{method=\"POST\",endpoint=\"/api/users/{id}\",status=\"2xx\"} 1\n"
+        ));
+        assert!(body.contains(
+            "http_requests_total{method=\"GET\",endpoint=\"/error\",status=\"4xx\"} 1\n"
+        ));
+        assert!(body.contains(
+            "http_requests_total{method=\"GET\",endpoint=\"<unmatched>\",status=\"4xx\"} 1\n"
+        ));
+        assert!(body.contains(
+            "http_requests_total{method=\"OTHER\",endpoint=\"/api/users/{id}\",status=\"2xx\"} 3\n"
+        ));
+        assert!(!body.contains("X-RANDOM"));
+        assert!(!body.contains("/api/users/1"));
+
+        assert!(body.contains("# TYPE http_request_duration_seconds histogram\n"));
+        assert!(body.contains(
+            "http_request_duration_seconds_bucket{method=\"GET\",endpoint=\"/api/users/{id}\",le=\"+Inf\"} 2\n"
+        ));
+        assert!(body.contains(
+            "http_request_duration_seconds_count{method=\"GET\",endpoint=\"/api/users/{id}\"} 2\n"
+        ));
+        assert!(body.contains(
+            "http_request_duration_seconds_bucket{method=\"GET\",endpoint=\"/error\",le=\"10\"} 1\n"
+        ));
+
+        // exporter request itself is in flight
+        assert!(body.contains("# TYPE http_requests_in_flight gauge\n"));
+        assert!(body.contains("\nhttp_requests_in_flight 1\n"));
+    }
+
+    #[crate::rt_test]
+    async fn test_options() {
+        let metrics = Metrics::new().namespace("app").buckets(vec![1.0, 0.1]);
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let mw = metrics.clone().new_transform(srv.into_service());
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let req = TestRequest::with_uri("/test").to_srv_request();
+        let _ = mw.call(req).await.unwrap();
+
+        let body = metrics.render();
+        assert!(body.contains(
+            "app_http_requests_total{method=\"GET\",endpoint=\"<unmatched>\",status=\"2xx\"} 1\n"
+        ));
+        assert!(body.contains(
+            "app_http_request_duration_seconds_bucket{method=\"GET\",endpoint=\"<unmatched>\",le=\"0.1\"} 1\n"
+        ));
+        assert!(body.contains(
+            "app_http_request_duration_seconds_bucket{method=\"GET\",endpoint=\"<unmatched>\",le=\"1\"} 1\n"
+        ));
+        assert!(body.contains("app_http_requests_in_flight 0\n"));
+
+        assert_eq!(Escaped("a\"b\\c\nd").to_string(), "a\\\"b\\\\c\\nd");
+    }
+}
@@ -3,10 +3,55 @@
 #[cfg(feature = "compress")]
 mod compress;
 #[cfg(feature = "compress")]
-pub use self::compress::Compress;
+pub use self::compress::{Compress, Precompressed};
 
 mod logger;
 pub use self::logger::Logger;
 
 mod defaultheaders;
 pub use self::defaultheaders::DefaultHeaders;
+
+mod cors;
+pub use self::cors::Cors;
+
+mod conditional;
+pub use self::conditional::Conditional;
+
+mod requestid;
+pub use self::requestid::{ReqId, RequestId};
+
+mod metrics;
+pub use self::metrics::{Metrics, MetricsExporter};
+
+mod catchpanic;
+pub use self::catchpanic::CatchPanic;
+
+mod auth;
+pub use self::auth::Authentication;
+
+mod normalize;
+pub use self::normalize::{NormalizePath, TrailingSlash};
+
+mod bodylimit;
+pub use self::bodylimit::BodyLimit;
+
+mod timeout;
+pub use self::timeout::Timeout;
+
+mod ratelimit;
+pub use self::ratelimit::{
+    Decision, MemoryRateLimitStore, Quota, RateLimit, RateLimitFuture, RateLimitStore,
+};
+
+#[cfg(feature = "cookie")]
+mod session;
+#[cfg(feature = "cookie")]
+pub use self::session::{
+    CookieContentSecurity, CookieSessionStore, Key, MemorySessionStore, Session,
+    SessionFuture, SessionState, SessionStatus, SessionStore, Sessions,
+};
+
+#[cfg(feature = "cookie")]
+mod csrf;
+#[cfg(feature = "cookie")]
+pub use self::csrf::{Csrf, CsrfToken};
@@ -0,0 +1,352 @@
+//! Request path normalization middleware
+use std::convert::TryFrom;
+use std::task::{Context, Poll};
+
+use crate::http::uri::{PathAndQuery, Uri};
+use crate::http::{header, StatusCode};
+use crate::service::{Service, Transform};
+use crate::util::{Either, Ready};
+use crate::web::{HttpResponse, WebRequest, WebResponse};
+
+/// Trailing slash policy of `NormalizePath` middleware
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TrailingSlash {
+    /// Always add trailing slash, `/path` becomes `/path/`
+    Always,
+    /// Never change trailing slash, `/path/` and `/path` are kept as is
+    Never,
+    /// Trim trailing slash, `/path/` becomes `/path`
+    Trim,
+}
+
+/// `Middleware` for request path normalization.
+///
+/// Middleware merges repeated slashes, resolves `.` and `..` segments and
+/// applies trailing slash policy. Normalization is done on the raw path,
+/// percent-encoded `%2F` is not treated as a separator and is matched by
+/// router as a part of segment, percent-encoded dot segments (`%2e`)
+/// are resolved. Query string is preserved.
+///
+/// By default request uri is rewritten in place and request is routed by
+/// normalized path, so middleware must be registered for application.
+/// Alternatively, middleware could respond with redirect to normalized path.
+/// Backslashes are percent-encoded in redirect location.
+///
+/// ```rust
+/// use ntex::http::StatusCode;
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(
+///             middleware::NormalizePath::new(middleware::TrailingSlash::Trim)
+///                 .redirect(StatusCode::PERMANENT_REDIRECT),
+///         )
+///         .service(web::resource("/test").to(|| async { HttpResponse::Ok() }));
+/// }
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct NormalizePath {
+    trailing: TrailingSlash,
+    redirect: Option<StatusCode>,
+}
+
+impl Default for NormalizePath {
+    fn default() -> Self {
+        NormalizePath::new(TrailingSlash::Never)
+    }
+}
+
+impl NormalizePath {
+    /// Construct `NormalizePath` middleware with trailing slash policy.
+    pub fn new(trailing: TrailingSlash) -> Self {
+        NormalizePath {
+            trailing,
+            redirect: None,
+        }
+    }
+
+    /// Respond with redirect to normalized path instead of rewriting
+    /// request uri.
+    ///
+    /// Use `301 Moved Permanently` or `308 Permanent Redirect` status,
+    /// the latter preserves request method and body.
+    ///
+    /// Panics if status is not a redirection status.
+    pub fn redirect(mut self, status: StatusCode) -> Self {
+        assert!(status.is_redirection(), "Redirection status is expected");
+        self.redirect = Some(status);
+        self
+    }
+}
+
+impl<S> Transform<S> for NormalizePath {
+    type Service = NormalizePathMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        NormalizePathMiddleware {
+            service,
+            trailing: self.trailing,
+            redirect: self.redirect,
+        }
+    }
+}
+
+pub struct NormalizePathMiddleware<S> {
+    service: S,
+    trailing: TrailingSlash,
+    redirect: Option<StatusCode>,
+}
+
+impl<S, E> Service<WebRequest<E>> for NormalizePathMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Either<S::Future, Ready<Self::Response, Self::Error>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, mut req: WebRequest<E>) -> Self::Future {
+        let path = match normalize(req.path(), self.trailing) {
+            Some(path) => path,
+            None => return Either::Left(self.service.call(req)),
+        };
+        let path = match req.uri().query() {
+            Some(query) => format!("{}?{}", path, query),
+            None => path,
+        };
+
+        if let Some(status) = self.redirect {
+            // browsers treat `\` as `/`, location must stay on the same host
+            let location = path.replace('\\', "%5C");
+            if location.starts_with("//") {
+                return Either::Left(self.service.call(req));
+            }
+            let res = HttpResponse::build(status)
+                .header(header::LOCATION, location)
+                .finish();
+            return Either::Right(Ready::Ok(req.into_response(res)));
+        }
+
+        let mut parts = req.uri().clone().into_parts();
+        parts.path_and_query = PathAndQuery::try_from(path).ok();
+        match Uri::from_parts(parts) {
+            Ok(uri) => {
+                // router matches against request's path
+                req.match_info_mut().set(uri.clone());
+                req.head_mut().uri = uri;
+            }
+            Err(_) => log::trace!("Cannot rewrite request uri: {}", req.uri()),
+        }
+        Either::Left(self.service.call(req))
+    }
+}
+
+/// Dot segment, returns number of dots
+fn dot_segment(seg: &str) -> Option<usize> {
+    if seg == "." || seg.eq_ignore_ascii_case("%2e") {
+        Some(1)
+    } else if seg == ".."
+        || seg.eq_ignore_ascii_case(".%2e")
+        || seg.eq_ignore_ascii_case("%2e.")
+        || seg.eq_ignore_ascii_case("%2e%2e")
+    {
+        Some(2)
+    } else {
+        None
+    }
+}
+
+/// Normalize path, returns `None` if path is already normalized
+fn normalize(path: &str, trailing: TrailingSlash) -> Option<String> {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut slash = false;
+
+    for seg in path.split('/') {
+        slash = true;
+        match dot_segment(seg) {
+            Some(1) => (),
+            Some(_) => {
+                segments.pop();
+            }
+            None if seg.is_empty() => (),
+            None => {
+                segments.push(seg);
+                slash = false;
+            }
+        }
+    }
+
+    let mut result = String::with_capacity(path.len() + 1);
+    for seg in &segments {
+        result.push('/');
+        result.push_str(seg);
+    }
+    let slash = match trailing {
+        TrailingSlash::Always => true,
+        TrailingSlash::Never => slash,
+        TrailingSlash::Trim => false,
+    };
+    if slash || result.is_empty() {
+        result.push('/');
+    }
+
+    if result == path {
+        None
+    } else {
+        Some(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::service::IntoService;
+    use crate::util::lazy;
+    use crate::web::test::{call_service, init_service, TestRequest};
+    use crate::web::{self, App, DefaultError, Error, HttpRequest};
+
+    #[test]
+    fn test_normalize() {
+        let cases = [
+            ("/", "/", "/", "/"),
+            ("", "/", "/", "/"),
+            ("/a", "/a", "/a/", "/a"),
+            ("/a/", "/a/", "/a/", "/a"),
+            ("//a//b///", "/a/b/", "/a/b/", "/a/b"),
+            ("/a/./b", "/a/b", "/a/b/", "/a/b"),
+            ("/a/b/..", "/a/", "/a/", "/a"),
+            ("/a/b/../c", "/a/c", "/a/c/", "/a/c"),
+            ("/../../a", "/a", "/a/", "/a"),
+            ("/a/.", "/a/", "/a/", "/a"),
+            ("/a/%2e%2E/b", "/b", "/b/", "/b"),
+            ("/a/.%2e/b", "/b", "/b/", "/b"),
+            ("/a/%2E/b", "/a/b", "/a/b/", "/a/b"),
+            ("/a/..b/.c", "/a/..b/.c", "/a/..b/.c/", "/a/..b/.c"),
+            ("/a%2F..%2fb", "/a%2F..%2fb", "/a%2F..%2fb/", "/a%2F..%2fb"),
+            ("/a/%2F/b", "/a/%2F/b", "/a/%2F/b/", "/a/%2F/b"),
+        ];
+
+        for (path, never, always, trim) in cases.iter() {
+            let check = |trailing, expected: &str| {
+                let res = normalize(path, trailing);
+                assert_eq!(
+                    res.as_deref().unwrap_or(path),
+                    expected,
+                    "{} {:?}",
+                    path,
+                    trailing
+                );
+                assert_eq!(res.is_none(), *path == expected);
+            };
+            check(TrailingSlash::Never, never);
+            check(TrailingSlash::Always, always);
+            check(TrailingSlash::Trim, trim);
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_rewrite() {
+        let srv = init_service(
+            App::new()
+                .wrap(NormalizePath::new(TrailingSlash::Trim))
+                .service(
+                    web::resource("/v1/something").to(|req: HttpRequest| async move {
+                        HttpResponse::Ok().body(req.uri().to_string())
+                    }),
+                )
+                .service(web::resource("/v1/{name}/info").to(
+                    |name: web::types::Path<String>| async move {
+                        HttpResponse::Ok().body(name.into_inner())
+                    },
+                )),
+        )
+        .await;
+
+        for uri in &[
+            "/v1/something",
+            "/v1/something/",
+            "//v1//something//",
+            "/v1/other/../something",
+            "/v1/./something/.",
+        ] {
+            let req = TestRequest::with_uri(uri).to_request();
+            let res = call_service(&srv, req).await;
+            assert_eq!(res.status(), StatusCode::OK, "{}", uri);
+            assert_eq!(crate::web::test::read_body(res).await, "/v1/something");
+        }
+
+        let req = TestRequest::with_uri("//v1/something/?a=1&b=2").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(
+            crate::web::test::read_body(res).await,
+            "/v1/something?a=1&b=2"
+        );
+
+        // encoded slash is a part of segment
+        let req = TestRequest::with_uri("/v1/a%2F..%2Fb/info/").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(crate::web::test::read_body(res).await, "a/../b");
+
+        let req = TestRequest::with_uri("/v1/a/%2e%2e/info").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[crate::rt_test]
+    async fn test_redirect() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let mw = NormalizePath::new(TrailingSlash::Always)
+            .redirect(StatusCode::MOVED_PERMANENTLY)
+            .new_transform(srv.into_service());
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let req = TestRequest::with_uri("/v1/").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("//v1/./a?q=1").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers().get(header::LOCATION).unwrap(), "/v1/a/?q=1");
+    }
+
+    #[crate::rt_test]
+    async fn test_redirect_backslash() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let mw = NormalizePath::new(TrailingSlash::Trim)
+            .redirect(StatusCode::MOVED_PERMANENTLY)
+            .new_transform(srv.into_service());
+
+        let req = TestRequest::with_uri("/\\evil.com/").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::MOVED_PERMANENTLY);
+        assert_eq!(res.headers().get(header::LOCATION).unwrap(), "/%5Cevil.com");
+
+        let req = TestRequest::with_uri("//\\evil.com/").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get(header::LOCATION).unwrap(), "/%5Cevil.com");
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_redirect_status() {
+        let _ = NormalizePath::default().redirect(StatusCode::OK);
+    }
+}
@@ -0,0 +1,102 @@
+use std::time::{Duration, Instant};
+use std::{cell::RefCell, rc::Rc};
+
+use super::{Quota, RateLimitFuture, RateLimitStore};
+use crate::time::now;
+use crate::util::HashMap;
+
+/// Interval of expired keys removal
+const PURGE_INTERVAL: Duration = Duration::from_secs(10);
+
+/// In-memory rate limit store
+///
+/// State is stored in the memory of the worker, every worker has its own
+/// store. Store uses low resolution time of the worker's timer, keys with
+/// fully replenished quota are removed periodically.
+#[derive(Clone, Default)]
+pub struct MemoryRateLimitStore {
+    inner: Rc<RefCell<Inner>>,
+}
+
+#[derive(Default)]
+struct Inner {
+    purge: Option<Instant>,
+    items: HashMap<String, Instant>,
+}
+
+impl MemoryRateLimitStore {
+    /// Construct new in-memory rate limit store.
+    pub fn new() -> Self {
+        MemoryRateLimitStore::default()
+    }
+
+    /// Number of stored keys, including expired ones
+    pub fn len(&self) -> usize {
+        self.inner.borrow().items.len()
+    }
+
+    /// Returns `true` if store does not contain any key
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl RateLimitStore for MemoryRateLimitStore {
+    fn check(&self, key: String, quota: &Quota) -> RateLimitFuture {
+        let now = now();
+        let mut inner = self.inner.borrow_mut();
+
+        // remove keys with fully replenished quota
+        match inner.purge {
+            Some(purge) if purge > now => (),
+            _ => {
+                inner.items.retain(|_, tat| *tat > now);
+                inner.purge = Some(now + PURGE_INTERVAL);
+            }
+        }
+
+        // tat is stored as an offset from current time
+        let tat = inner
+            .items
+            .get(&key)
+            .map(|tat| tat.saturating_duration_since(now));
+        let (tat, decision) = quota.check(tat, Duration::ZERO);
+        if decision.allowed {
+            inner.items.insert(key, now + tat);
+        }
+        Box::pin(async move { Ok(decision) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{sleep, Millis};
+
+    #[crate::rt_test]
+    async fn test_memory_store() {
+        let store = MemoryRateLimitStore::new();
+        assert!(store.is_empty());
+
+        let quota = Quota::with_interval(Duration::from_millis(50)).burst(2);
+        let d = store.check("key".to_string(), &quota).await.unwrap();
+        assert!(d.allowed);
+        assert_eq!(d.remaining, 1);
+        let d = store.check("key".to_string(), &quota).await.unwrap();
+        assert!(d.allowed);
+        assert_eq!(d.remaining, 0);
+        let d = store.check("key".to_string(), &quota).await.unwrap();
+        assert!(!d.allowed);
+        assert!(d.retry_after.is_some());
+        assert_eq!(store.len(), 1);
+
+        let d = store.check("key2".to_string(), &quota).await.unwrap();
+        assert!(d.allowed);
+        assert_eq!(store.len(), 2);
+
+        sleep(Millis(150)).await;
+        let d = store.check("key".to_string(), &quota).await.unwrap();
+        assert!(d.allowed);
+        assert_eq!(d.remaining, 1);
+    }
+}
@@ -0,0 +1,583 @@
+//! Rate limiting middleware
+use std::task::{Context, Poll};
+use std::{cmp, convert::TryFrom, future::Future, net, pin::Pin, rc::Rc, time::Duration};
+
+use crate::http::error::HttpError;
+use crate::http::header::{self, HeaderName, HeaderValue};
+use crate::http::RequestHead;
+use crate::service::{Service, Transform};
+use crate::web::error::{ErrorRenderer, RateLimitError};
+use crate::web::{WebRequest, WebResponse};
+
+mod memory;
+
+pub use self::memory::MemoryRateLimitStore;
+
+const RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+
+/// Boxed future returned by rate limit store
+pub type RateLimitFuture = Pin<Box<dyn Future<Output = Result<Decision, RateLimitError>>>>;
+
+/// Rate limit storage backend
+///
+/// Store keeps theoretical arrival time of the next request for every key
+/// and makes decision for incoming request. [`Quota::check()`] implements
+/// GCRA algorithm and could be used by store implementations.
+pub trait RateLimitStore: 'static {
+    /// Check quota for the key and account one request if it is allowed.
+    fn check(&self, key: String, quota: &Quota) -> RateLimitFuture;
+}
+
+/// Max time span covered by a quota, larger values are clamped
+const MAX_TOLERANCE: Duration = Duration::from_secs(u32::MAX as u64);
+
+/// Rate limit quota
+///
+/// Quota defines steady request rate and burst size. Bucket of `burst`
+/// size is refilled with one request per `interval`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Quota {
+    interval: Duration,
+    burst: u32,
+}
+
+impl Quota {
+    /// Quota that allows `n` requests per second, burst size is `n`.
+    pub fn per_second(n: u32) -> Quota {
+        Quota::per_period(Duration::from_secs(1), n)
+    }
+
+    /// Quota that allows `n` requests per minute, burst size is `n`.
+    pub fn per_minute(n: u32) -> Quota {
+        Quota::per_period(Duration::from_secs(60), n)
+    }
+
+    /// Quota that allows `n` requests per hour, burst size is `n`.
+    pub fn per_hour(n: u32) -> Quota {
+        Quota::per_period(Duration::from_secs(3600), n)
+    }
+
+    /// Quota that allows one request per `interval`, burst size is 1.
+    ///
+    /// Panics if interval is zero.
+    pub fn with_interval(interval: Duration) -> Quota {
+        assert!(!interval.is_zero(), "Quota interval must be non-zero");
+        Quota { interval, burst: 1 }
+    }
+
+    fn per_period(period: Duration, n: u32) -> Quota {
+        assert!(n != 0, "Quota must allow at least one request");
+        // interval is rounded to zero for large `n`
+        let interval = cmp::max(period / n, Duration::from_nanos(1));
+        Quota::with_interval(interval).burst(n)
+    }
+
+    /// Set burst size.
+    ///
+    /// Panics if burst size is zero.
+    pub fn burst(mut self, burst: u32) -> Quota {
+        assert!(burst != 0, "Burst size must be non-zero");
+        self.burst = burst;
+        self
+    }
+
+    /// Replenish interval of one request
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Max number of requests in a burst
+    pub fn burst_size(&self) -> u32 {
+        self.burst
+    }
+
+    /// Check request with GCRA algorithm.
+    ///
+    /// `tat` is theoretical arrival time stored for the key and `now` is
+    /// current time, both are offsets from the same point of time. Returns
+    /// new theoretical arrival time that must be stored for the key and
+    /// decision for the request.
+    pub fn check(&self, tat: Option<Duration>, now: Duration) -> (Duration, Decision) {
+        let interval = cmp::min(self.interval, MAX_TOLERANCE);
+        let tolerance = interval
+            .checked_mul(self.burst)
+            .map(|t| cmp::min(t, MAX_TOLERANCE))
+            .unwrap_or(MAX_TOLERANCE);
+        let tat = tat.map(|tat| tat.max(now)).unwrap_or(now);
+        let new_tat = tat.saturating_add(interval);
+        let allow_at = now.saturating_add(tolerance);
+
+        if new_tat > allow_at {
+            let decision = Decision {
+                allowed: false,
+                limit: self.burst,
+                remaining: 0,
+                reset: tat - now,
+                retry_after: Some(new_tat - allow_at),
+            };
+            (tat, decision)
+        } else {
+            let remaining = (allow_at - new_tat).as_nanos() / interval.as_nanos();
+            let decision = Decision {
+                allowed: true,
+                limit: self.burst,
+                remaining: cmp::min(remaining, u128::from(self.burst)) as u32,
+                reset: new_tat - now,
+                retry_after: None,
+            };
+            (new_tat, decision)
+        }
+    }
+}
+
+/// Rate limit decision for a request
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Decision {
+    /// Request is allowed
+    pub allowed: bool,
+    /// Max number of requests in a burst
+    pub limit: u32,
+    /// Number of requests left in current burst
+    pub remaining: u32,
+    /// Time until quota is fully replenished
+    pub reset: Duration,
+    /// Time after which request is going to be allowed
+    pub retry_after: Option<Duration>,
+}
+
+enum Key {
+    Peer,
+    Remote,
+    Header(HeaderName),
+    Custom(Box<dyn Fn(&RequestHead) -> Option<String>>),
+}
+
+/// `Middleware` for request rate limiting.
+///
+/// Middleware computes key for each request and checks it against the
+/// quota in the rate limit store. By default peer address of the
+/// connection is used as a key, requests without key are not limited.
+/// If server is deployed behind trusted reverse proxy, use
+/// `RateLimit::key_remote()`. Rejected requests get `429 Too Many Requests` response with
+/// `Retry-After` header. `RateLimit-Limit`, `RateLimit-Remaining` and
+/// `RateLimit-Reset` headers are added to all responses.
+///
+/// [`MemoryRateLimitStore`] keeps state in memory of the worker, so quota
+/// is enforced per worker. Use [`RateLimitStore`] implementation with
+/// shared backend to enforce quota for the whole server.
+///
+/// ```rust
+/// use ntex::web::{self, App, HttpResponse};
+/// use ntex::web::middleware::{MemoryRateLimitStore, Quota, RateLimit};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(
+///             RateLimit::new(MemoryRateLimitStore::new(), Quota::per_second(10).burst(20))
+///                 .key_header("x-api-key")
+///         )
+///         .service(web::resource("/").to(|| async { HttpResponse::Ok() }));
+/// }
+/// ```
+pub struct RateLimit<T> {
+    inner: Rc<Inner<T>>,
+}
+
+struct Inner<T> {
+    store: T,
+    quota: Quota,
+    key: Key,
+    headers: bool,
+}
+
+impl<T: RateLimitStore> RateLimit<T> {
+    /// Construct `RateLimit` middleware with rate limit store and quota.
+    pub fn new(store: T, quota: Quota) -> Self {
+        RateLimit {
+            inner: Rc::new(Inner {
+                store,
+                quota,
+                key: Key::Peer,
+                headers: true,
+            }),
+        }
+    }
+
+    fn inner(&mut self) -> &mut Inner<T> {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Use peer address of the connection as a key, peer port is ignored.
+    ///
+    /// This is default.
+    pub fn key_peer(mut self) -> Self {
+        self.inner().key = Key::Peer;
+        self
+    }
+
+    /// Use remote address from `ConnectionInfo` as a key.
+    ///
+    /// Remote address is resolved through `Forwarded` and `X-Forwarded-For`
+    /// headers that are sent by the client. Use it only behind trusted proxy
+    /// that overwrites these headers, otherwise client gets unlimited quota
+    /// by changing header value.
+    pub fn key_remote(mut self) -> Self {
+        self.inner().key = Key::Remote;
+        self
+    }
+
+    /// Use value of request header as a key.
+    pub fn key_header<K>(mut self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+    {
+        match HeaderName::try_from(name) {
+            Ok(name) => self.inner().key = Key::Header(name),
+            Err(_) => panic!("Cannot create header name"),
+        }
+        self
+    }
+
+    /// Use custom function for key extraction.
+    ///
+    /// Request is not limited if function returns `None`.
+    pub fn key<F>(mut self, f: F) -> Self
+    where
+        F: Fn(&RequestHead) -> Option<String> + 'static,
+    {
+        self.inner().key = Key::Custom(Box::new(f));
+        self
+    }
+
+    /// Add `RateLimit-*` headers to allowed responses, default is `true`.
+    ///
+    /// Rejected responses always contain rate limit headers.
+    pub fn headers(mut self, value: bool) -> Self {
+        self.inner().headers = value;
+        self
+    }
+}
+
+impl<T> Inner<T> {
+    fn key<E>(&self, req: &WebRequest<E>) -> Option<String> {
+        match self.key {
+            Key::Peer => req.peer_addr().map(|addr| addr.ip().to_string()),
+            Key::Remote => req.connection_info().remote().map(|remote| {
+                // ignore peer port
+                remote
+                    .parse::<net::SocketAddr>()
+                    .map(|addr| addr.ip().to_string())
+                    .unwrap_or_else(|_| remote.to_string())
+            }),
+            Key::Header(ref name) => req
+                .headers()
+                .get(name)
+                .and_then(|val| val.to_str().ok())
+                .map(|val| val.to_string()),
+            Key::Custom(ref f) => f(req.head()),
+        }
+    }
+}
+
+fn set_headers(res: &mut WebResponse, decision: &Decision) {
+    let headers = res.headers_mut();
+    headers.insert(RATELIMIT_LIMIT, HeaderValue::from(decision.limit));
+    headers.insert(RATELIMIT_REMAINING, HeaderValue::from(decision.remaining));
+    headers.insert(RATELIMIT_RESET, HeaderValue::from(seconds(decision.reset)));
+    if let Some(retry_after) = decision.retry_after {
+        headers.insert(
+            header::RETRY_AFTER,
+            HeaderValue::from(seconds(retry_after).max(1)),
+        );
+    }
+}
+
+/// Round duration up to whole seconds
+fn seconds(dur: Duration) -> u64 {
+    if dur.subsec_nanos() == 0 {
+        dur.as_secs()
+    } else {
+        dur.as_secs() + 1
+    }
+}
+
+impl<S, T> Transform<S> for RateLimit<T> {
+    type Service = RateLimitMiddleware<S, T>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        RateLimitMiddleware {
+            service: Rc::new(service),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct RateLimitMiddleware<S, T> {
+    service: Rc<S>,
+    inner: Rc<Inner<T>>,
+}
+
+impl<S, T, E> Service<WebRequest<E>> for RateLimitMiddleware<S, T>
+where
+    S: Service<WebRequest<E>, Response = WebResponse> + 'static,
+    T: RateLimitStore,
+    E: ErrorRenderer,
+    RateLimitError: Into<E::Container>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let srv = self.service.clone();
+        let inner = self.inner.clone();
+        let key = inner.key(&req);
+
+        Box::pin(async move {
+            let key = if let Some(key) = key {
+                key
+            } else {
+                return srv.call(req).await;
+            };
+
+            let decision = match inner.store.check(key, &inner.quota).await {
+                Ok(decision) => decision,
+                Err(e) => return Ok(req.error_response(e)),
+            };
+            if !decision.allowed {
+                let mut res = req.error_response(RateLimitError::Limited);
+                set_headers(&mut res, &decision);
+                return Ok(res);
+            }
+
+            let mut res = srv.call(req).await?;
+            if inner.headers {
+                set_headers(&mut res, &decision);
+            }
+            Ok(res)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::IntoService;
+    use crate::util::lazy;
+    use crate::web::test::{self, TestRequest};
+    use crate::web::{DefaultError, Error, HttpResponse};
+
+    #[test]
+    fn test_quota() {
+        let quota = Quota::per_second(2);
+        assert_eq!(quota.interval(), Duration::from_millis(500));
+        assert_eq!(quota.burst_size(), 2);
+
+        let now = Duration::from_secs(10);
+        let (tat, d) = quota.check(None, now);
+        assert!(d.allowed);
+        assert_eq!(d.remaining, 1);
+        assert_eq!(d.reset, Duration::from_millis(500));
+
+        let (tat, d) = quota.check(Some(tat), now);
+        assert!(d.allowed);
+        assert_eq!(d.remaining, 0);
+        assert_eq!(d.reset, Duration::from_secs(1));
+
+        let (tat2, d) = quota.check(Some(tat), now);
+        assert!(!d.allowed);
+        assert_eq!(tat, tat2);
+        assert_eq!(d.remaining, 0);
+        assert_eq!(d.retry_after, Some(Duration::from_millis(500)));
+
+        // one request is replenished
+        let (_, d) = quota.check(Some(tat), now + Duration::from_millis(500));
+        assert!(d.allowed);
+        assert_eq!(d.remaining, 0);
+
+        // stale tat
+        let (_, d) = quota.check(Some(tat), now + Duration::from_secs(10));
+        assert!(d.allowed);
+        assert_eq!(d.remaining, 1);
+
+        // large values are clamped
+        let quota = Quota::per_second(u32::MAX);
+        assert_eq!(quota.interval(), Duration::from_nanos(1));
+        let (_, d) = quota.check(None, now);
+        assert!(d.allowed);
+
+        let quota = Quota::with_interval(Duration::MAX).burst(u32::MAX);
+        let (tat, d) = quota.check(None, now);
+        assert!(d.allowed);
+        assert_eq!(tat, now + MAX_TOLERANCE);
+        let (_, d) = quota.check(Some(tat), now);
+        assert!(!d.allowed);
+
+        let quota = Quota::with_interval(Duration::from_secs(1)).burst(3);
+        assert_eq!(quota.burst_size(), 3);
+        assert_eq!(Quota::per_minute(60), Quota::per_second(1).burst(60));
+        assert_eq!(Quota::per_hour(1).interval(), Duration::from_secs(3600));
+        assert_eq!(seconds(Duration::from_millis(1500)), 2);
+        assert_eq!(seconds(Duration::from_secs(1)), 1);
+    }
+
+    #[crate::rt_test]
+    async fn test_rate_limit() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let mw = RateLimit::new(MemoryRateLimitStore::new(), Quota::per_minute(2))
+            .key_remote()
+            .new_transform(srv.into_service());
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let req =
+            || TestRequest::with_header("x-forwarded-for", "192.0.2.1").to_srv_request();
+
+        let res = mw.call(req()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("ratelimit-limit").unwrap(), "2");
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "1");
+        assert_eq!(res.headers().get("ratelimit-reset").unwrap(), "30");
+        assert!(!res.headers().contains_key(header::RETRY_AFTER));
+
+        let res = mw.call(req()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+
+        let res = mw.call(req()).await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "30");
+
+        // different key
+        let req =
+            TestRequest::with_header("x-forwarded-for", "192.0.2.2:1000").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "1");
+
+        // port is ignored
+        let req =
+            TestRequest::with_header("x-forwarded-for", "192.0.2.2:2000").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("ratelimit-remaining").unwrap(), "0");
+    }
+
+    #[crate::rt_test]
+    async fn test_key() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let mw = RateLimit::new(MemoryRateLimitStore::new(), Quota::per_hour(1))
+            .key_header("x-api-key")
+            .headers(false)
+            .new_transform(srv.into_service());
+
+        // requests without key are not limited
+        for _ in 0..3 {
+            let res = mw
+                .call(TestRequest::default().to_srv_request())
+                .await
+                .unwrap();
+            assert_eq!(res.status(), StatusCode::OK);
+            assert!(!res.headers().contains_key("ratelimit-limit"));
+        }
+
+        let req = TestRequest::with_header("x-api-key", "key1").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key("ratelimit-limit"));
+
+        let req = TestRequest::with_header("x-api-key", "key1").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(res.headers().get("ratelimit-limit").unwrap(), "1");
+        assert_eq!(res.headers().get(header::RETRY_AFTER).unwrap(), "3600");
+
+        let mw = RateLimit::new(MemoryRateLimitStore::new(), Quota::per_hour(1))
+            .key(|head| Some(head.uri.path().to_string()))
+            .new_transform(srv.into_service());
+        let res = mw.call(TestRequest::with_uri("/a").to_srv_request()).await;
+        assert_eq!(res.unwrap().status(), StatusCode::OK);
+        let res = mw.call(TestRequest::with_uri("/b").to_srv_request()).await;
+        assert_eq!(res.unwrap().status(), StatusCode::OK);
+        let res = mw.call(TestRequest::with_uri("/a").to_srv_request()).await;
+        assert_eq!(res.unwrap().status(), StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[crate::rt_test]
+    async fn test_peer() {
+        let srv = test::server(|| {
+            crate::web::App::new()
+                .wrap(RateLimit::new(
+                    MemoryRateLimitStore::new(),
+                    Quota::per_minute(2),
+                ))
+                .service(crate::web::resource("/").to(|| async { HttpResponse::Ok() }))
+        });
+
+        // forwarded headers are ignored
+        for (idx, addr) in ["192.0.2.1", "192.0.2.2", "192.0.2.3"].iter().enumerate() {
+            let res = srv
+                .get("/")
+                .header("x-forwarded-for", *addr)
+                .send()
+                .await
+                .unwrap();
+            if idx < 2 {
+                assert_eq!(res.status(), StatusCode::OK);
+            } else {
+                assert_eq!(res.status(), StatusCode::TOO_MANY_REQUESTS);
+            }
+        }
+
+        // requests without peer address are not limited
+        let srv = |req: WebRequest<DefaultError>| async move {
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+        };
+        let mw = RateLimit::new(MemoryRateLimitStore::new(), Quota::per_hour(1))
+            .key_peer()
+            .new_transform(srv.into_service());
+        for _ in 0..3 {
+            let res = mw.call(TestRequest::default().to_srv_request()).await;
+            assert_eq!(res.unwrap().status(), StatusCode::OK);
+        }
+    }
+
+    struct FailingStore;
+
+    impl RateLimitStore for FailingStore {
+        fn check(&self, _: String, _: &Quota) -> RateLimitFuture {
+            Box::pin(async { Err(RateLimitError::Store("unavailable".into())) })
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_store_error() {
+        let srv = test::init_service(
+            crate::web::App::new()
+                .wrap(RateLimit::new(FailingStore, Quota::per_second(1)).key_remote())
+                .service(crate::web::resource("/").to(|| async { HttpResponse::Ok() })),
+        )
+        .await;
+        let req = TestRequest::with_header("x-forwarded-for", "192.0.2.1").to_request();
+        let res = test::call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,330 @@
+//! Middleware for request id assignment and propagation
+use std::task::{Context, Poll};
+use std::{convert::TryFrom, fmt, future::Future, ops::Deref, pin::Pin, rc::Rc};
+
+use nanorand::{Rng, WyRand};
+
+use crate::http::error::HttpError;
+use crate::http::header::{HeaderName, HeaderValue};
+use crate::http::Payload;
+use crate::service::{Service, Transform};
+use crate::util::Ready;
+use crate::web::{ErrorRenderer, FromRequest, HttpRequest, WebRequest, WebResponse};
+
+/// Max length of the request id accepted from incoming request
+const MAX_ID_LENGTH: usize = 128;
+
+/// `Middleware` for request id assignment.
+///
+/// Middleware reads request id from incoming request header, or generates
+/// new one if request does not contain valid id. Request id is stored in
+/// request extensions, could be extracted with [`ReqId`] extractor and is
+/// sent back to the client in response header. `Logger` middleware prints
+/// request id with `%{request-id}` format variable.
+///
+/// By default `X-Request-Id` header is used and new ids are random
+/// UUID v4 strings.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, App, HttpResponse};
+///
+/// async fn index(id: middleware::ReqId) -> HttpResponse {
+///     HttpResponse::Ok().body(format!("request: {}", id))
+/// }
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(middleware::Logger::new("%{request-id} %r %s"))
+///         .wrap(middleware::RequestId::new().header("x-correlation-id"))
+///         .service(web::resource("/").to(index));
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RequestId {
+    inner: Rc<Inner>,
+}
+
+struct Inner {
+    header: HeaderName,
+    use_incoming: bool,
+    generator: Box<dyn Fn() -> String>,
+}
+
+impl Default for RequestId {
+    fn default() -> Self {
+        RequestId {
+            inner: Rc::new(Inner {
+                header: HeaderName::from_static("x-request-id"),
+                use_incoming: true,
+                generator: Box::new(generate),
+            }),
+        }
+    }
+}
+
+impl RequestId {
+    /// Construct `RequestId` middleware.
+    pub fn new() -> RequestId {
+        RequestId::default()
+    }
+
+    /// Set request id header name.
+    ///
+    /// Header is used for incoming and outgoing request id.
+    /// Default is `X-Request-Id`.
+    pub fn header<K>(mut self, name: K) -> Self
+    where
+        HeaderName: TryFrom<K>,
+        <HeaderName as TryFrom<K>>::Error: Into<HttpError>,
+    {
+        match HeaderName::try_from(name) {
+            Ok(name) => self.inner_mut().header = name,
+            Err(_) => panic!("Cannot create header name"),
+        }
+        self
+    }
+
+    /// Use request id from incoming request header.
+    ///
+    /// If disabled, new request id is generated for every request.
+    /// Default is true.
+    pub fn use_incoming(mut self, value: bool) -> Self {
+        self.inner_mut().use_incoming = value;
+        self
+    }
+
+    /// Set request id generator.
+    pub fn generator<F>(mut self, f: F) -> Self
+    where
+        F: Fn() -> String + 'static,
+    {
+        self.inner_mut().generator = Box::new(f);
+        self
+    }
+
+    fn inner_mut(&mut self) -> &mut Inner {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+}
+
+impl<S> Transform<S> for RequestId {
+    type Service = RequestIdMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        RequestIdMiddleware {
+            service,
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: S,
+    inner: Rc<Inner>,
+}
+
+impl<S, E> Service<WebRequest<E>> for RequestIdMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse>,
+    S::Future: 'static,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let incoming = if self.inner.use_incoming {
+            req.headers()
+                .get(&self.inner.header)
+                .and_then(|val| val.to_str().ok())
+                .filter(|val| !val.is_empty() && val.len() <= MAX_ID_LENGTH)
+                .map(|val| val.to_string())
+        } else {
+            None
+        };
+        let id = incoming.unwrap_or_else(|| (self.inner.generator)());
+        let value = HeaderValue::from_str(&id).ok();
+        req.extensions_mut().insert(ReqId(id));
+
+        let header = self.inner.header.clone();
+        let fut = self.service.call(req);
+
+        Box::pin(async move {
+            let mut res = fut.await?;
+
+            if let Some(value) = value {
+                if !res.headers().contains_key(&header) {
+                    res.headers_mut().insert(header, value);
+                }
+            }
+            Ok(res)
+        })
+    }
+}
+
+/// Request id
+///
+/// Request id is assigned by `RequestId` middleware. If middleware is not
+/// registered, extractor generates new random id for the request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ReqId(String);
+
+impl ReqId {
+    /// Get request id of the request, if assigned.
+    pub fn get(req: &HttpRequest) -> Option<ReqId> {
+        req.extensions().get::<ReqId>().cloned()
+    }
+
+    /// Get request id as string slice.
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Unwrap into inner `String`.
+    pub fn into_inner(self) -> String {
+        self.0
+    }
+}
+
+impl Deref for ReqId {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for ReqId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl<Err: ErrorRenderer> FromRequest<Err> for ReqId {
+    type Error = Err::Container;
+    type Future = Ready<ReqId, Err::Container>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let id = if let Some(id) = ReqId::get(req) {
+            id
+        } else {
+            let id = ReqId(generate());
+            req.extensions_mut().insert(id.clone());
+            id
+        };
+        Ready::Ok(id)
+    }
+}
+
+/// Generate random UUID v4 string
+fn generate() -> String {
+    let mut rng = WyRand::new();
+    let hi = (rng.generate::<u64>() & 0xffff_ffff_ffff_0fff) | 0x4000;
+    let lo = (rng.generate::<u64>() & 0x3fff_ffff_ffff_ffff) | 0x8000_0000_0000_0000;
+    format!(
+        "{:08x}-{:04x}-{:04x}-{:04x}-{:012x}",
+        hi >> 32,
+        (hi >> 16) & 0xffff,
+        hi & 0xffff,
+        lo >> 48,
+        lo & 0xffff_ffff_ffff
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::IntoService;
+    use crate::util::lazy;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, DefaultError, Error, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_request_id() {
+        let srv = |req: WebRequest<DefaultError>| async move {
+            let id = req.extensions().get::<ReqId>().cloned().unwrap();
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().body(id.into_inner())))
+        };
+        let mw = RequestId::new().new_transform(srv.into_service());
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let req = TestRequest::with_header("x-request-id", "abc").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "abc");
+        assert_eq!(read_body(res).await, "abc");
+
+        let req = TestRequest::with_header("x-request-id", "").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        let id = res.headers().get("x-request-id").unwrap().clone();
+        assert_eq!(id.len(), 36);
+        assert_eq!(&id.as_bytes()[14..15], b"4");
+        assert_eq!(read_body(res).await, id.as_bytes());
+
+        let req =
+            TestRequest::with_header("x-request-id", "x".repeat(256)).to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("x-request-id").unwrap().len(), 36);
+
+        let mw = RequestId::new()
+            .header("x-correlation-id")
+            .use_incoming(false)
+            .generator(|| "generated".to_string())
+            .new_transform(srv.into_service());
+        let req = TestRequest::with_header("x-correlation-id", "abc").to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.headers().get("x-correlation-id").unwrap(), "generated");
+        assert!(!res.headers().contains_key("x-request-id"));
+    }
+
+    #[crate::rt_test]
+    async fn test_extractor() {
+        let srv =
+            init_service(
+                App::new()
+                    .wrap(RequestId::new())
+                    .service(web::resource("/").to(|id: ReqId| async move {
+                        HttpResponse::Ok().body(id.to_string())
+                    }))
+                    .service(web::resource("/keep").to(|| async {
+                        HttpResponse::Ok()
+                            .header("x-request-id", "handler")
+                            .finish()
+                    })),
+            )
+            .await;
+
+        let req = TestRequest::with_header("x-request-id", "1234").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, "1234");
+
+        let req = TestRequest::with_uri("/keep")
+            .header("x-request-id", "1234")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.headers().get("x-request-id").unwrap(), "handler");
+
+        let srv = init_service(App::new().service(web::resource("/").to(
+            |id1: ReqId, id2: ReqId| async move {
+                assert_eq!(id1, id2);
+                HttpResponse::Ok().body(id1.into_inner())
+            },
+        )))
+        .await;
+        let res = call_service(&srv, TestRequest::default().to_request()).await;
+        assert_eq!(read_body(res).await.len(), 36);
+    }
+}
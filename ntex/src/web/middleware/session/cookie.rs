@@ -0,0 +1,142 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+
+use super::{SessionFuture, SessionState, SessionStore};
+use crate::web::error::SessionError;
+
+/// Cookie session store
+///
+/// Session state is serialized and stored in the session cookie itself,
+/// cookie content is protected by signed or private cookie jar of
+/// `Sessions` middleware. Browsers limit cookie size to 4Kb, so
+/// session state must stay small.
+///
+/// Expiration time is stored together with session state, expired
+/// session cookie is ignored even if browser keeps sending it.
+#[derive(Debug, Clone)]
+pub struct CookieSessionStore {
+    limit: usize,
+}
+
+impl CookieSessionStore {
+    /// Construct new cookie session store.
+    pub fn new() -> Self {
+        CookieSessionStore::default()
+    }
+
+    /// Set max size of serialized session state, default is 4000 bytes.
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    fn serialize(
+        &self,
+        state: SessionState,
+        ttl: Duration,
+    ) -> Result<String, SessionError> {
+        let expires = now().saturating_add(ttl.as_secs());
+        let val = serde_json::to_string(&CookieState { expires, state })?;
+        if val.len() > self.limit {
+            Err(SessionError::Overflow { limit: self.limit })
+        } else {
+            Ok(val)
+        }
+    }
+}
+
+/// Session state with expiration time, seconds since unix epoch
+#[derive(Serialize, Deserialize)]
+struct CookieState {
+    #[serde(rename = "exp")]
+    expires: u64,
+    state: SessionState,
+}
+
+fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl Default for CookieSessionStore {
+    fn default() -> Self {
+        CookieSessionStore { limit: 4000 }
+    }
+}
+
+impl SessionStore for CookieSessionStore {
+    fn load(&self, key: String) -> SessionFuture<Option<SessionState>> {
+        let state = serde_json::from_str::<CookieState>(&key)
+            .ok()
+            .filter(|st| st.expires > now())
+            .map(|st| st.state);
+        Box::pin(async move { Ok(state) })
+    }
+
+    fn save(&self, state: SessionState, ttl: Duration) -> SessionFuture<String> {
+        let res = self.serialize(state, ttl);
+        Box::pin(async move { res })
+    }
+
+    fn update(
+        &self,
+        _: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> SessionFuture<String> {
+        let res = self.serialize(state, ttl);
+        Box::pin(async move { res })
+    }
+
+    fn delete(&self, _: String) -> SessionFuture<()> {
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[crate::rt_test]
+    async fn test_cookie_store() {
+        let store = CookieSessionStore::new().limit(64);
+        let ttl = Duration::from_secs(60);
+
+        let mut state = SessionState::default();
+        state.insert("key".to_string(), "\"value\"".to_string());
+        let key = store.save(state.clone(), ttl).await.unwrap();
+        assert_eq!(store.load(key.clone()).await.unwrap(), Some(state.clone()));
+        assert_eq!(store.load("garbage".to_string()).await.unwrap(), None);
+
+        state.insert("key2".to_string(), "\"long value\"".to_string());
+        let res = store.update(key.clone(), state, ttl).await;
+        assert!(matches!(res, Err(SessionError::Overflow { limit: 64 })));
+        assert!(store.delete(key).await.is_ok());
+    }
+
+    #[crate::rt_test]
+    async fn test_cookie_store_expiry() {
+        let store = CookieSessionStore::new();
+
+        let mut state = SessionState::default();
+        state.insert("key".to_string(), "\"value\"".to_string());
+        let key = store
+            .save(state.clone(), Duration::from_secs(0))
+            .await
+            .unwrap();
+        assert_eq!(store.load(key.clone()).await.unwrap(), None);
+
+        let key = store
+            .update(key, state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.load(key).await.unwrap(), Some(state.clone()));
+
+        // state without expiration time
+        let key = serde_json::to_string(&state).unwrap();
+        assert_eq!(store.load(key).await.unwrap(), None);
+    }
+}
@@ -0,0 +1,164 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use super::{SessionFuture, SessionState, SessionStore};
+use crate::time::now;
+use crate::util::HashMap;
+
+/// Interval between removals of expired sessions
+const PURGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// In-memory session store
+///
+/// Sessions are stored in the process memory and are lost on restart.
+/// Store could be cloned and shared between workers, expired sessions
+/// are removed lazily.
+#[derive(Clone, Default)]
+pub struct MemorySessionStore {
+    inner: Arc<Mutex<Sessions>>,
+}
+
+#[derive(Default)]
+struct Sessions {
+    map: HashMap<String, (Instant, SessionState)>,
+    purged: Option<Instant>,
+}
+
+impl MemorySessionStore {
+    /// Construct new in-memory session store.
+    pub fn new() -> Self {
+        MemorySessionStore::default()
+    }
+
+    /// Number of stored sessions, including expired ones
+    pub fn len(&self) -> usize {
+        self.inner.lock().unwrap().map.len()
+    }
+
+    /// Returns `true` if store does not contain any session
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn insert(&self, key: String, state: SessionState, ttl: Duration) -> String {
+        let now = now();
+        let mut sessions = self.inner.lock().unwrap();
+
+        // remove expired sessions, at most once per purge interval
+        if sessions
+            .purged
+            .map(|purged| now.duration_since(purged) >= PURGE_INTERVAL)
+            .unwrap_or(true)
+        {
+            sessions.map.retain(|_, (expires, _)| *expires > now);
+            sessions.purged = Some(now);
+        }
+        sessions.map.insert(key.clone(), (now + ttl, state));
+        key
+    }
+}
+
+/// Generate random session key
+///
+/// Key is generated by operating system's secure random number generator.
+fn session_key() -> String {
+    let mut buf = [0u8; 32];
+    getrandom::getrandom(&mut buf)
+        .expect("Secure random number generator is not available");
+    base64::encode_config(buf, base64::URL_SAFE_NO_PAD)
+}
+
+impl SessionStore for MemorySessionStore {
+    fn load(&self, key: String) -> SessionFuture<Option<SessionState>> {
+        let now = now();
+        let mut sessions = self.inner.lock().unwrap();
+        let state = match sessions.map.get(&key) {
+            Some((expires, state)) if *expires > now => Some(state.clone()),
+            Some(_) => {
+                sessions.map.remove(&key);
+                None
+            }
+            None => None,
+        };
+        Box::pin(async move { Ok(state) })
+    }
+
+    fn save(&self, state: SessionState, ttl: Duration) -> SessionFuture<String> {
+        let key = self.insert(session_key(), state, ttl);
+        Box::pin(async move { Ok(key) })
+    }
+
+    fn update(
+        &self,
+        key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> SessionFuture<String> {
+        let key = self.insert(key, state, ttl);
+        Box::pin(async move { Ok(key) })
+    }
+
+    fn delete(&self, key: String) -> SessionFuture<()> {
+        self.inner.lock().unwrap().map.remove(&key);
+        Box::pin(async { Ok(()) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::time::{sleep, Millis};
+
+    #[crate::rt_test]
+    async fn test_memory_store() {
+        let store = MemorySessionStore::new();
+        assert!(store.is_empty());
+
+        let mut state = SessionState::default();
+        state.insert("key".to_string(), "1".to_string());
+        let key = store
+            .save(state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(store.len(), 1);
+        assert_eq!(store.load(key.clone()).await.unwrap(), Some(state.clone()));
+        assert_eq!(store.load("unknown".to_string()).await.unwrap(), None);
+
+        state.insert("key".to_string(), "2".to_string());
+        let key2 = store
+            .update(key.clone(), state.clone(), Duration::from_secs(60))
+            .await
+            .unwrap();
+        assert_eq!(key, key2);
+        assert_eq!(store.load(key.clone()).await.unwrap(), Some(state.clone()));
+
+        store.delete(key.clone()).await.unwrap();
+        assert!(store.is_empty());
+        assert_eq!(store.load(key).await.unwrap(), None);
+
+        // expired session
+        let key = store
+            .save(state.clone(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        sleep(Millis(50)).await;
+        assert_eq!(store.load(key).await.unwrap(), None);
+        assert!(store.is_empty());
+
+        // expired sessions are removed once per purge interval
+        store
+            .save(state.clone(), Duration::from_millis(10))
+            .await
+            .unwrap();
+        sleep(Millis(50)).await;
+        store.save(state, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(store.len(), 2);
+    }
+
+    #[test]
+    fn test_session_key() {
+        let key = session_key();
+        assert_eq!(key.len(), 43);
+        assert_ne!(key, session_key());
+    }
+}
@@ -0,0 +1,732 @@
+//! Session middleware
+use std::cell::{Ref, RefCell};
+use std::task::{Context, Poll};
+use std::{convert::TryFrom, fmt, future::Future, pin::Pin, rc::Rc, time::Duration};
+
+use coo_kie::{Cookie, CookieJar, SameSite};
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::http::header::{self, HeaderValue};
+use crate::http::{HttpMessage, Payload};
+use crate::service::{Service, Transform};
+use crate::util::{HashMap, Ready};
+use crate::web::error::{ErrorRenderer, SessionError};
+use crate::web::{FromRequest, HttpRequest, WebRequest, WebResponse};
+
+mod cookie;
+mod memory;
+
+pub use self::cookie::CookieSessionStore;
+pub use self::memory::MemorySessionStore;
+
+/// Key for signing and encryption of cookies
+pub use coo_kie::Key;
+
+/// Session state, values are stored in serialized form
+pub type SessionState = HashMap<String, String>;
+
+/// Boxed future returned by session store
+pub type SessionFuture<T> = Pin<Box<dyn Future<Output = Result<T, SessionError>>>>;
+
+/// Session storage backend
+///
+/// Store maps session key to session state. Session key is stored in the
+/// session cookie, cookie is signed or encrypted by `Sessions` middleware.
+pub trait SessionStore: 'static {
+    /// Load session state for session key.
+    ///
+    /// Returns `None` if session does not exist or expired.
+    fn load(&self, key: String) -> SessionFuture<Option<SessionState>>;
+
+    /// Store new session and return session key
+    fn save(&self, state: SessionState, ttl: Duration) -> SessionFuture<String>;
+
+    /// Update existing session and return session key
+    fn update(
+        &self,
+        key: String,
+        state: SessionState,
+        ttl: Duration,
+    ) -> SessionFuture<String>;
+
+    /// Delete session
+    fn delete(&self, key: String) -> SessionFuture<()>;
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Status of the session
+pub enum SessionStatus {
+    /// Session state has not been modified
+    Unchanged,
+    /// Session state has been modified
+    Changed,
+    /// Session has been purged, session cookie is removed
+    Purged,
+    /// Session key has been renewed
+    Renewed,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+/// Session cookie protection
+pub enum CookieContentSecurity {
+    /// Cookie value is encrypted and authenticated (private jar)
+    Private,
+    /// Cookie value is authenticated but visible to the client (signed jar)
+    Signed,
+}
+
+/// Session extractor
+///
+/// Session is populated by `Sessions` middleware and is stored in request
+/// extensions, so it is accessible from other middlewares via
+/// `req.extensions().get::<Session>()`.
+///
+/// ```rust
+/// use ntex::web::{self, middleware::Session, Error};
+///
+/// async fn index(session: Session) -> Result<String, Error> {
+///     let counter = session.get::<u32>("counter")?.unwrap_or(0) + 1;
+///     session.insert("counter", counter)?;
+///     Ok(format!("Counter: {}", counter))
+/// }
+/// ```
+#[derive(Clone)]
+pub struct Session(Rc<RefCell<SessionInner>>);
+
+struct SessionInner {
+    state: SessionState,
+    status: SessionStatus,
+}
+
+impl Session {
+    fn new(state: SessionState) -> Self {
+        Session(Rc::new(RefCell::new(SessionInner {
+            state,
+            status: SessionStatus::Unchanged,
+        })))
+    }
+
+    /// Get value from the session
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, SessionError> {
+        if let Some(val) = self.0.borrow().state.get(key) {
+            Ok(Some(serde_json::from_str(val)?))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Set value to the session
+    pub fn insert<K, T>(&self, key: K, value: T) -> Result<(), SessionError>
+    where
+        K: Into<String>,
+        T: Serialize,
+    {
+        let val = serde_json::to_string(&value)?;
+        let mut inner = self.0.borrow_mut();
+        inner.changed();
+        inner.state.insert(key.into(), val);
+        Ok(())
+    }
+
+    /// Remove value from the session
+    ///
+    /// Returns serialized value, if it exists
+    pub fn remove(&self, key: &str) -> Option<String> {
+        let mut inner = self.0.borrow_mut();
+        let val = inner.state.remove(key);
+        if val.is_some() {
+            inner.changed();
+        }
+        val
+    }
+
+    /// Remove all values from the session
+    pub fn clear(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.changed();
+        inner.state.clear();
+    }
+
+    /// Remove session from the store and remove session cookie
+    pub fn purge(&self) {
+        let mut inner = self.0.borrow_mut();
+        inner.status = SessionStatus::Purged;
+        inner.state.clear();
+    }
+
+    /// Keep session state but issue new session key
+    pub fn renew(&self) {
+        let mut inner = self.0.borrow_mut();
+        if inner.status != SessionStatus::Purged {
+            inner.status = SessionStatus::Renewed;
+        }
+    }
+
+    /// Get session status
+    pub fn status(&self) -> SessionStatus {
+        self.0.borrow().status
+    }
+
+    /// Get session entries
+    pub fn entries(&self) -> Ref<'_, SessionState> {
+        Ref::map(self.0.borrow(), |inner| &inner.state)
+    }
+}
+
+impl SessionInner {
+    fn changed(&mut self) {
+        if self.status == SessionStatus::Unchanged {
+            self.status = SessionStatus::Changed;
+        }
+    }
+}
+
+impl fmt::Debug for Session {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.0.borrow();
+        f.debug_struct("Session")
+            .field("status", &inner.status)
+            .field("state", &inner.state)
+            .finish()
+    }
+}
+
+/// Extractor returns session populated by `Sessions` middleware.
+///
+/// If middleware is not registered, new empty session is created,
+/// changes to such session are not persisted.
+impl<Err: ErrorRenderer> FromRequest<Err> for Session {
+    type Error = Err::Container;
+    type Future = Ready<Session, Err::Container>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let session = req.extensions().get::<Session>().cloned();
+        if let Some(session) = session {
+            Ready::Ok(session)
+        } else {
+            let session = Session::new(SessionState::default());
+            req.extensions_mut().insert(session.clone());
+            Ready::Ok(session)
+        }
+    }
+}
+
+/// `Middleware` for session management.
+///
+/// Middleware loads session state from the session store and stores
+/// `Session` in request extensions. After response is generated, modified
+/// session state is persisted and session cookie is updated. Session cookie
+/// is encrypted by default, see `Sessions::signed()`.
+///
+/// ```rust
+/// use ntex::web::{self, middleware, middleware::Key, App, HttpResponse};
+///
+/// fn main() {
+///     let store = middleware::MemorySessionStore::new();
+///
+///     let app = App::new()
+///         .wrap(
+///             middleware::Sessions::new(store, Key::generate())
+///                 .cookie_name("sid")
+///                 .cookie_secure(false)
+///         )
+///         .service(
+///             web::resource("/test")
+///                 .route(web::get().to(|| async { HttpResponse::Ok() }))
+///         );
+/// }
+/// ```
+pub struct Sessions<T> {
+    inner: Rc<Inner<T>>,
+}
+
+struct Inner<T> {
+    store: T,
+    key: Key,
+    security: CookieContentSecurity,
+    name: String,
+    path: String,
+    domain: Option<String>,
+    secure: bool,
+    http_only: bool,
+    same_site: Option<SameSite>,
+    max_age: Option<Duration>,
+}
+
+impl<T: SessionStore> Sessions<T> {
+    /// Construct `Sessions` middleware with session store and
+    /// key for signing/encryption of session cookie.
+    pub fn new(store: T, key: Key) -> Self {
+        Sessions {
+            inner: Rc::new(Inner {
+                store,
+                key,
+                security: CookieContentSecurity::Private,
+                name: "id".to_string(),
+                path: "/".to_string(),
+                domain: None,
+                secure: true,
+                http_only: true,
+                same_site: Some(SameSite::Lax),
+                max_age: None,
+            }),
+        }
+    }
+
+    fn inner(&mut self) -> &mut Inner<T> {
+        Rc::get_mut(&mut self.inner).expect("Multiple copies exist")
+    }
+
+    /// Sign session cookie instead of encrypting it.
+    pub fn signed(mut self) -> Self {
+        self.inner().security = CookieContentSecurity::Signed;
+        self
+    }
+
+    /// Set session cookie protection.
+    pub fn content_security(mut self, security: CookieContentSecurity) -> Self {
+        self.inner().security = security;
+        self
+    }
+
+    /// Set session cookie name, default is `id`.
+    pub fn cookie_name<N: Into<String>>(mut self, name: N) -> Self {
+        self.inner().name = name.into();
+        self
+    }
+
+    /// Set session cookie path, default is `/`.
+    pub fn cookie_path<P: Into<String>>(mut self, path: P) -> Self {
+        self.inner().path = path.into();
+        self
+    }
+
+    /// Set session cookie domain.
+    pub fn cookie_domain<D: Into<String>>(mut self, domain: D) -> Self {
+        self.inner().domain = Some(domain.into());
+        self
+    }
+
+    /// Set `Secure` attribute of session cookie, default is `true`.
+    pub fn cookie_secure(mut self, value: bool) -> Self {
+        self.inner().secure = value;
+        self
+    }
+
+    /// Set `HttpOnly` attribute of session cookie, default is `true`.
+    pub fn cookie_http_only(mut self, value: bool) -> Self {
+        self.inner().http_only = value;
+        self
+    }
+
+    /// Set `SameSite` attribute of session cookie, default is `Lax`.
+    pub fn cookie_same_site(mut self, value: Option<SameSite>) -> Self {
+        self.inner().same_site = value;
+        self
+    }
+
+    /// Set session lifetime.
+    ///
+    /// Lifetime is used as `Max-Age` of session cookie and as session ttl
+    /// in session store. By default session cookie is removed when browser
+    /// is closed, and session ttl is one day.
+    pub fn max_age(mut self, value: Duration) -> Self {
+        self.inner().max_age = Some(value);
+        self
+    }
+}
+
+impl<T> Inner<T> {
+    fn ttl(&self) -> Duration {
+        self.max_age.unwrap_or(Duration::from_secs(86400))
+    }
+
+    /// Read and verify session key from session cookie
+    fn session_key<E>(&self, req: &WebRequest<E>) -> Option<String> {
+        let mut jar = CookieJar::new();
+        jar.add_original(req.cookie(&self.name)?);
+
+        let cookie = match self.security {
+            CookieContentSecurity::Private => jar.private(&self.key).get(&self.name),
+            CookieContentSecurity::Signed => jar.signed(&self.key).get(&self.name),
+        };
+        cookie.map(|c| c.value().to_string())
+    }
+
+    fn cookie(&self, value: String) -> Cookie<'static> {
+        let mut cookie = Cookie::new(self.name.clone(), value);
+        cookie.set_path(self.path.clone());
+        cookie.set_secure(self.secure);
+        cookie.set_http_only(self.http_only);
+        cookie.set_same_site(self.same_site);
+        if let Some(ref domain) = self.domain {
+            cookie.set_domain(domain.clone());
+        }
+        if let Some(max_age) = self.max_age {
+            if let Ok(max_age) = coo_kie::time::Duration::try_from(max_age) {
+                cookie.set_max_age(max_age);
+            }
+        }
+        cookie
+    }
+
+    fn set_cookie(&self, res: &mut WebResponse, value: String) {
+        let mut jar = CookieJar::new();
+        match self.security {
+            CookieContentSecurity::Private => {
+                jar.private_mut(&self.key).add(self.cookie(value))
+            }
+            CookieContentSecurity::Signed => {
+                jar.signed_mut(&self.key).add(self.cookie(value))
+            }
+        }
+        for cookie in jar.delta() {
+            add_cookie(res, cookie);
+        }
+    }
+
+    fn remove_cookie(&self, res: &mut WebResponse) {
+        let mut cookie = self.cookie(String::new());
+        cookie.make_removal();
+        add_cookie(res, &cookie);
+    }
+}
+
+fn add_cookie(res: &mut WebResponse, cookie: &Cookie<'_>) {
+    if let Ok(val) = HeaderValue::from_str(&cookie.encoded().to_string()) {
+        res.headers_mut().append(header::SET_COOKIE, val);
+    }
+}
+
+impl<S, T> Transform<S> for Sessions<T> {
+    type Service = SessionsMiddleware<S, T>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        SessionsMiddleware {
+            service: Rc::new(service),
+            inner: self.inner.clone(),
+        }
+    }
+}
+
+pub struct SessionsMiddleware<S, T> {
+    service: Rc<S>,
+    inner: Rc<Inner<T>>,
+}
+
+impl<S, T, E> Service<WebRequest<E>> for SessionsMiddleware<S, T>
+where
+    S: Service<WebRequest<E>, Response = WebResponse> + 'static,
+    T: SessionStore,
+    E: ErrorRenderer,
+    SessionError: Into<E::Container>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        let srv = self.service.clone();
+        let inner = self.inner.clone();
+
+        Box::pin(async move {
+            // load session state
+            let mut key = inner.session_key(&req);
+            let state = if let Some(k) = key.clone() {
+                match inner.store.load(k).await {
+                    Ok(Some(state)) => state,
+                    Ok(None) => {
+                        key = None;
+                        SessionState::default()
+                    }
+                    Err(e) => return Ok(req.error_response(e)),
+                }
+            } else {
+                SessionState::default()
+            };
+            let session = Session::new(state);
+            req.extensions_mut().insert(session.clone());
+
+            let mut res = srv.call(req).await?;
+
+            // persist session state
+            let (status, state) = {
+                let mut inner = session.0.borrow_mut();
+                (inner.status, std::mem::take(&mut inner.state))
+            };
+            let ttl = inner.ttl();
+            let result = match status {
+                SessionStatus::Unchanged => Ok(None),
+                SessionStatus::Changed => match key {
+                    Some(key) => inner.store.update(key, state, ttl).await.map(Some),
+                    None => inner.store.save(state, ttl).await.map(Some),
+                },
+                SessionStatus::Renewed => {
+                    if let Some(key) = key {
+                        if let Err(e) = inner.store.delete(key).await {
+                            return Ok(res.error_response::<E, _>(e));
+                        }
+                    }
+                    inner.store.save(state, ttl).await.map(Some)
+                }
+                SessionStatus::Purged => {
+                    if let Some(key) = key {
+                        if let Err(e) = inner.store.delete(key).await {
+                            return Ok(res.error_response::<E, _>(e));
+                        }
+                        inner.remove_cookie(&mut res);
+                    }
+                    Ok(None)
+                }
+            };
+
+            match result {
+                Ok(Some(key)) => {
+                    inner.set_cookie(&mut res, key);
+                    Ok(res)
+                }
+                Ok(None) => Ok(res),
+                Err(e) => Ok(res.error_response::<E, _>(e)),
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::IntoService;
+    use crate::util::lazy;
+    use crate::web::test::{ok_service, TestRequest};
+    use crate::web::{DefaultError, Error, HttpResponse};
+
+    fn counter_service(
+    ) -> impl Service<WebRequest<DefaultError>, Response = WebResponse, Error = Error> {
+        (|req: WebRequest<DefaultError>| async move {
+            let session = req.extensions().get::<Session>().cloned().unwrap();
+            let counter = session.get::<u32>("counter")?.unwrap_or(0) + 1;
+            session.insert("counter", counter)?;
+            if req.path() == "/renew" {
+                session.renew();
+            } else if req.path() == "/purge" {
+                session.purge();
+            }
+            Ok::<_, Error>(req.into_response(HttpResponse::Ok().body(counter.to_string())))
+        })
+        .into_service()
+    }
+
+    fn session_cookie(res: &WebResponse) -> Cookie<'static> {
+        let val = res.headers().get(header::SET_COOKIE).unwrap();
+        Cookie::parse_encoded(val.to_str().unwrap().to_string()).unwrap()
+    }
+
+    #[crate::rt_test]
+    async fn test_session() {
+        let session = Session::new(SessionState::default());
+        assert_eq!(session.status(), SessionStatus::Unchanged);
+        assert_eq!(session.get::<u32>("key").unwrap(), None);
+
+        session.insert("key", 10u32).unwrap();
+        assert_eq!(session.status(), SessionStatus::Changed);
+        assert_eq!(session.get::<u32>("key").unwrap(), Some(10));
+        assert!(session.get::<String>("key").is_err());
+        assert_eq!(session.entries().len(), 1);
+
+        assert_eq!(session.remove("key"), Some("10".to_string()));
+        assert_eq!(session.remove("key"), None);
+
+        session.insert("key", "value").unwrap();
+        session.renew();
+        assert_eq!(session.status(), SessionStatus::Renewed);
+        session.clear();
+        assert_eq!(session.status(), SessionStatus::Renewed);
+        assert!(session.entries().is_empty());
+
+        session.purge();
+        session.renew();
+        assert_eq!(session.status(), SessionStatus::Purged);
+        assert!(format!("{:?}", session).contains("Purged"));
+    }
+
+    #[crate::rt_test]
+    async fn test_extractor() {
+        let req = TestRequest::default().to_http_request();
+        let session =
+            <Session as FromRequest<DefaultError>>::from_request(&req, &mut Payload::None)
+                .await
+                .unwrap();
+        session.insert("key", 1).unwrap();
+
+        let session =
+            <Session as FromRequest<DefaultError>>::from_request(&req, &mut Payload::None)
+                .await
+                .unwrap();
+        assert_eq!(session.get::<i32>("key").unwrap(), Some(1));
+    }
+
+    #[crate::rt_test]
+    async fn test_unchanged() {
+        let mw = Sessions::new(MemorySessionStore::new(), Key::generate())
+            .new_transform(ok_service());
+
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let req = TestRequest::default().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::SET_COOKIE));
+    }
+
+    #[crate::rt_test]
+    async fn test_memory_store() {
+        let store = MemorySessionStore::new();
+        let mw = Sessions::new(store.clone(), Key::generate())
+            .cookie_name("sid")
+            .max_age(Duration::from_secs(3600))
+            .new_transform(counter_service());
+
+        let req = TestRequest::default().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        let cookie = session_cookie(&res);
+        assert_eq!(cookie.name(), "sid");
+        assert_eq!(cookie.path(), Some("/"));
+        assert_eq!(cookie.secure(), Some(true));
+        assert_eq!(cookie.http_only(), Some(true));
+        assert_eq!(cookie.same_site(), Some(SameSite::Lax));
+        assert_eq!(
+            cookie.max_age(),
+            Some(coo_kie::time::Duration::seconds(3600))
+        );
+        assert_eq!(store.len(), 1);
+
+        let req = TestRequest::default()
+            .cookie(cookie.clone())
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(crate::web::test::read_body(res).await, "2");
+        assert_eq!(store.len(), 1);
+
+        // tampered cookie starts new session
+        let mut tampered = cookie.clone();
+        tampered.set_value(format!("{}x", cookie.value()));
+        let req = TestRequest::default().cookie(tampered).to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(crate::web::test::read_body(res).await, "1");
+        assert_eq!(store.len(), 2);
+
+        // renew session key
+        let req = TestRequest::with_uri("/renew")
+            .cookie(cookie.clone())
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        let renewed = session_cookie(&res);
+        assert_ne!(renewed.value(), cookie.value());
+        assert_eq!(crate::web::test::read_body(res).await, "3");
+        assert_eq!(store.len(), 2);
+
+        // old key is removed
+        let req = TestRequest::default().cookie(cookie).to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(crate::web::test::read_body(res).await, "1");
+
+        // purge session
+        let req = TestRequest::with_uri("/purge")
+            .cookie(renewed)
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        let cookie = session_cookie(&res);
+        assert_eq!(cookie.value(), "");
+        assert_eq!(cookie.max_age(), Some(coo_kie::time::Duration::ZERO));
+        assert_eq!(store.len(), 2);
+    }
+
+    #[crate::rt_test]
+    async fn test_cookie_store() {
+        let key = Key::generate();
+        let mw = Sessions::new(CookieSessionStore::default(), key.clone())
+            .signed()
+            .cookie_secure(false)
+            .cookie_domain("www.rust-lang.org")
+            .cookie_same_site(None)
+            .new_transform(counter_service());
+
+        let req = TestRequest::default().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        let cookie = session_cookie(&res);
+        assert_eq!(cookie.name(), "id");
+        assert_eq!(cookie.secure(), None);
+        assert_eq!(cookie.same_site(), None);
+        assert_eq!(cookie.domain(), Some("www.rust-lang.org"));
+        // signed cookie value is visible
+        assert!(cookie.value().ends_with(r#""state":{"counter":"1"}}"#));
+
+        let req = TestRequest::default()
+            .cookie(cookie.clone())
+            .to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert!(session_cookie(&res)
+            .value()
+            .ends_with(r#""state":{"counter":"2"}}"#));
+
+        // private cookie with the same key can not be read
+        let mw = Sessions::new(CookieSessionStore::default(), key)
+            .new_transform(counter_service());
+        let req = TestRequest::default().cookie(cookie).to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        let cookie = session_cookie(&res);
+        assert!(!cookie.value().contains("counter"));
+        assert_eq!(crate::web::test::read_body(res).await, "1");
+    }
+
+    #[crate::rt_test]
+    async fn test_store_error() {
+        struct FailingStore;
+
+        impl SessionStore for FailingStore {
+            fn load(&self, _: String) -> SessionFuture<Option<SessionState>> {
+                Box::pin(async { Err(SessionError::Store("load".into())) })
+            }
+            fn save(&self, _: SessionState, _: Duration) -> SessionFuture<String> {
+                Box::pin(async { Err(SessionError::Store("save".into())) })
+            }
+            fn update(
+                &self,
+                _: String,
+                _: SessionState,
+                _: Duration,
+            ) -> SessionFuture<String> {
+                Box::pin(async { Err(SessionError::Store("update".into())) })
+            }
+            fn delete(&self, _: String) -> SessionFuture<()> {
+                Box::pin(async { Ok(()) })
+            }
+        }
+
+        let key = Key::generate();
+        let mw = Sessions::new(FailingStore, key.clone()).new_transform(counter_service());
+        let req = TestRequest::default().to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+
+        let mut jar = CookieJar::new();
+        jar.private_mut(&key).add(Cookie::new("id", "key"));
+        let cookie = jar.get("id").unwrap().clone();
+        let req = TestRequest::default().cookie(cookie).to_srv_request();
+        let res = mw.call(req).await.unwrap();
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    }
+}
@@ -0,0 +1,227 @@
+//! Request processing timeout middleware
+use std::task::{Context, Poll};
+use std::{future::Future, pin::Pin};
+
+use crate::service::{Service, Transform};
+use crate::time::{timeout_checked, Millis};
+use crate::web::error::{ErrorRenderer, ServiceTimeoutError};
+use crate::web::{WebRequest, WebResponse};
+
+/// `Middleware` for request processing timeout.
+///
+/// Middleware bounds execution time of the wrapped service, it could be
+/// registered for application, scope or resource. If service does not
+/// complete in time, service future is dropped and `ServiceTimeoutError`
+/// is rendered by application's `ErrorRenderer`, so outer middlewares
+/// receive regular response. By default `504 Gateway Timeout` response
+/// is generated.
+///
+/// Timeout does not include time spent on sending response body.
+///
+/// ```rust
+/// use ntex::time::Seconds;
+/// use ntex::web::{self, middleware::Timeout, App, HttpResponse};
+///
+/// fn main() {
+///     let app = App::new()
+///         .wrap(Timeout::new(Seconds(30)))
+///         .service(
+///             web::resource("/report")
+///                 .wrap(Timeout::new(Seconds(120)).service_unavailable())
+///                 .to(|| async { HttpResponse::Ok() }),
+///         );
+/// }
+/// ```
+#[derive(Debug, Copy, Clone)]
+pub struct Timeout {
+    timeout: Millis,
+    error: ServiceTimeoutError,
+}
+
+impl Timeout {
+    /// Construct `Timeout` middleware.
+    ///
+    /// Zero timeout disables timeout.
+    pub fn new<T: Into<Millis>>(timeout: T) -> Self {
+        Timeout {
+            timeout: timeout.into(),
+            error: ServiceTimeoutError::GatewayTimeout,
+        }
+    }
+
+    /// Respond with `503 Service Unavailable` instead of
+    /// `504 Gateway Timeout`.
+    pub fn service_unavailable(mut self) -> Self {
+        self.error = ServiceTimeoutError::ServiceUnavailable;
+        self
+    }
+}
+
+impl<S> Transform<S> for Timeout {
+    type Service = TimeoutMiddleware<S>;
+
+    fn new_transform(&self, service: S) -> Self::Service {
+        TimeoutMiddleware {
+            service,
+            timeout: self.timeout,
+            error: self.error,
+        }
+    }
+}
+
+pub struct TimeoutMiddleware<S> {
+    service: S,
+    timeout: Millis,
+    error: ServiceTimeoutError,
+}
+
+impl<S, E> Service<WebRequest<E>> for TimeoutMiddleware<S>
+where
+    S: Service<WebRequest<E>, Response = WebResponse, Error = E::Container>,
+    S::Future: 'static,
+    E: ErrorRenderer,
+    ServiceTimeoutError: Into<E::Container>,
+{
+    type Response = WebResponse;
+    type Error = S::Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    #[inline]
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(cx)
+    }
+
+    #[inline]
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        self.service.poll_shutdown(cx, is_error)
+    }
+
+    fn call(&self, req: WebRequest<E>) -> Self::Future {
+        if self.timeout.is_zero() {
+            return Box::pin(self.service.call(req));
+        }
+
+        // request is consumed by the service, copy is used for error rendering
+        let copy = req.duplicate();
+        let fut = timeout_checked(self.timeout, self.service.call(req));
+        let error = self.error;
+
+        Box::pin(async move {
+            match fut.await {
+                Ok(res) => res,
+                Err(_) => {
+                    log::trace!("Request processing timed out");
+                    Ok(WebResponse::from_err::<E, _>(error, copy))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{cell::Cell, rc::Rc};
+
+    use super::*;
+    use crate::http::StatusCode;
+    use crate::service::IntoService;
+    use crate::time::{sleep, Seconds};
+    use crate::util::lazy;
+    use crate::web::middleware::DefaultHeaders;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, DefaultError, Error, HttpResponse};
+
+    struct Dropped(Rc<Cell<bool>>);
+
+    impl Drop for Dropped {
+        fn drop(&mut self) {
+            self.0.set(true);
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_timeout() {
+        let dropped = Rc::new(Cell::new(false));
+        let dropped2 = dropped.clone();
+        let srv = move |req: WebRequest<DefaultError>| {
+            let guard = Dropped(dropped2.clone());
+            async move {
+                let delay = req.path().trim_start_matches('/').parse().unwrap();
+                sleep(Millis(delay)).await;
+                drop(guard);
+                Ok::<_, Error>(req.into_response(HttpResponse::Ok().finish()))
+            }
+        };
+        let mw = Timeout::new(Millis(50)).new_transform(srv.clone().into_service());
+        assert!(lazy(|cx| mw.poll_ready(cx).is_ready()).await);
+        assert!(lazy(|cx| mw.poll_shutdown(cx, true).is_ready()).await);
+
+        let res = mw.call(TestRequest::with_uri("/1").to_srv_request()).await;
+        assert_eq!(res.unwrap().status(), StatusCode::OK);
+
+        dropped.set(false);
+        let res = mw
+            .call(TestRequest::with_uri("/500").to_srv_request())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+        assert_eq!(res.request().path(), "/500");
+        assert!(dropped.get());
+
+        let mw = Timeout::new(Millis(50))
+            .service_unavailable()
+            .new_transform(srv.clone().into_service());
+        let res = mw
+            .call(TestRequest::with_uri("/500").to_srv_request())
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        // zero timeout disables timeout
+        let mw = Timeout::new(Seconds::ZERO).new_transform(srv.into_service());
+        let res = mw
+            .call(TestRequest::with_uri("/100").to_srv_request())
+            .await;
+        assert_eq!(res.unwrap().status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_scope_resource() {
+        let srv = init_service(
+            App::new()
+                .wrap(Timeout::new(Seconds(10)))
+                .wrap(DefaultHeaders::new().header("x-test", "1"))
+                .service(web::scope("/scope").wrap(Timeout::new(Millis(50))).service(
+                    web::resource("/slow").to(|| async {
+                        sleep(Millis(500)).await;
+                        HttpResponse::Ok()
+                    }),
+                ))
+                .service(
+                    web::resource("/resource")
+                        .wrap(Timeout::new(Millis(50)).service_unavailable())
+                        .to(|| async {
+                            sleep(Millis(500)).await;
+                            HttpResponse::Ok()
+                        }),
+                )
+                .service(web::resource("/fast").to(|| async { HttpResponse::Ok() })),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/fast").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/scope/slow").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::GATEWAY_TIMEOUT);
+        // outer middleware receives response
+        assert_eq!(res.headers().get("x-test").unwrap(), "1");
+        assert_eq!(read_body(res).await, "Request processing timed out");
+
+        let req = TestRequest::with_uri("/resource").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+}
@@ -67,7 +67,9 @@ mod app_service;
 mod config;
 pub mod error;
 mod error_default;
+mod error_problem;
 mod extract;
+pub mod files;
 pub mod guard;
 mod handler;
 mod httprequest;
@@ -85,6 +87,7 @@ mod service;
 pub mod test;
 pub mod types;
 mod util;
+mod vhost;
 pub mod ws;
 
 // re-export proc macro
@@ -118,6 +121,7 @@ pub use self::scope::Scope;
 pub use self::server::HttpServer;
 pub use self::service::WebServiceFactory;
 pub use self::util::*;
+pub use self::vhost::VirtualHosts;
 
 pub mod dev {
     //! The `ntex::web` prelude for library developers
@@ -189,6 +189,17 @@ impl<Err> WebRequest<Err> {
         self.req.match_info_mut()
     }
 
+    #[inline]
+    pub(super) fn push_match_pattern(&mut self, pattern: &str) {
+        self.req.push_match_pattern(pattern)
+    }
+
+    #[inline]
+    /// Copy of the request without payload and match information
+    pub(crate) fn duplicate(&self) -> HttpRequest {
+        self.req.duplicate()
+    }
+
     #[inline]
     /// Get a reference to a `ResourceMap` of current application.
     pub fn resource_map(&self) -> &ResourceMap {
@@ -2,7 +2,7 @@ use std::{
     cell::RefCell, fmt, future::Future, pin::Pin, rc::Rc, task::Context, task::Poll,
 };
 
-use crate::http::Response;
+use crate::http::StatusCode;
 use crate::router::{IntoPattern, ResourceDef};
 use crate::service::boxed::{self, BoxService, BoxServiceFactory};
 use crate::service::{pipeline_factory, PipelineFactory};
@@ -569,10 +569,9 @@ impl<Err: ErrorRenderer> Service<WebRequest<Err>> for ResourceRouter<Err> {
         if let Some(ref default) = self.default {
             Either::Right(default.call(req))
         } else {
-            Either::Left(Ready::Ok(WebResponse::new(
-                Response::MethodNotAllowed().finish(),
-                req.into_parts().0,
-            )))
+            let req = req.into_parts().0;
+            let res = Err::unmatched(StatusCode::METHOD_NOT_ALLOWED, &req);
+            Either::Left(Ready::Ok(WebResponse::new(res, req)))
         }
     }
 }
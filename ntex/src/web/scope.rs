@@ -2,7 +2,7 @@ use std::{
     cell::RefCell, fmt, future::Future, pin::Pin, rc::Rc, task::Context, task::Poll,
 };
 
-use crate::http::Response;
+use crate::http::StatusCode;
 use crate::router::{IntoPattern, ResourceDef, Router};
 use crate::service::boxed::{self, BoxService, BoxServiceFactory};
 use crate::service::{pipeline_factory, PipelineFactory};
@@ -591,7 +591,9 @@ impl<Err: ErrorRenderer> ServiceFactory<WebRequest<Err>> for ScopeRouterFactory<
             }
             for (path, factory, guards) in &mut services.iter() {
                 let service = factory.new_service(()).await?;
-                router.rdef(path.clone(), service).2 = guards.borrow_mut().take();
+                router
+                    .rdef(path.clone(), (service, path.pattern().to_string()))
+                    .2 = guards.borrow_mut().take();
             }
 
             let default = if let Some(fut) = default_fut {
@@ -611,7 +613,7 @@ impl<Err: ErrorRenderer> ServiceFactory<WebRequest<Err>> for ScopeRouterFactory<
 
 struct ScopeRouter<Err: ErrorRenderer> {
     state: Option<Rc<Extensions>>,
-    router: Router<HttpService<Err>, Vec<Box<dyn Guard>>>,
+    router: Router<(HttpService<Err>, String), Vec<Box<dyn Guard>>>,
     default: Option<HttpService<Err>>,
 }
 
@@ -637,7 +639,8 @@ impl<Err: ErrorRenderer> Service<WebRequest<Err>> for ScopeRouter<Err> {
             true
         });
 
-        if let Some((srv, _info)) = res {
+        if let Some(((srv, pattern), _info)) = res {
+            req.push_match_pattern(pattern);
             if let Some(ref state) = self.state {
                 req.set_state_container(state.clone());
             }
@@ -646,10 +649,8 @@ impl<Err: ErrorRenderer> Service<WebRequest<Err>> for ScopeRouter<Err> {
             Either::Left(default.call(req))
         } else {
             let req = req.into_parts().0;
-            Either::Right(Ready::Ok(WebResponse::new(
-                Response::NotFound().finish(),
-                req,
-            )))
+            let res = Err::unmatched(StatusCode::NOT_FOUND, &req);
+            Either::Right(Ready::Ok(WebResponse::new(res, req)))
         }
     }
 }
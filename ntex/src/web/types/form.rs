@@ -105,12 +105,14 @@ where
 
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
-        let limit = req
+        let (limit, max_ratio) = req
             .app_state::<FormConfig>()
-            .map(|c| c.limit)
-            .unwrap_or(16384);
+            .map(|c| (c.limit, c.max_ratio))
+            .unwrap_or((16384, 0));
 
-        let fut = UrlEncoded::new(req, payload).limit(limit);
+        let fut = UrlEncoded::new(req, payload)
+            .limit(limit)
+            .max_ratio(max_ratio);
         Box::pin(async move {
             match fut.await {
                 Err(e) => Err(e),
@@ -182,6 +184,7 @@ where
 #[derive(Clone, Debug)]
 pub struct FormConfig {
     limit: usize,
+    max_ratio: usize,
 }
 
 impl FormConfig {
@@ -190,11 +193,24 @@ impl FormConfig {
         self.limit = limit;
         self
     }
+
+    /// Set max ratio between decoded and encoded payload sizes.
+    ///
+    /// Compressed payload is rejected if decoded payload exceeds 64Kb
+    /// and is more than `ratio` times larger than encoded payload.
+    /// By default ratio is not checked.
+    pub fn max_ratio(mut self, ratio: usize) -> Self {
+        self.max_ratio = ratio;
+        self
+    }
 }
 
 impl Default for FormConfig {
     fn default() -> Self {
-        FormConfig { limit: 16384 }
+        FormConfig {
+            limit: 16384,
+            max_ratio: 0,
+        }
     }
 }
 
@@ -277,6 +293,16 @@ impl<U> UrlEncoded<U> {
         self.limit = limit;
         self
     }
+
+    /// Set max ratio between decoded and encoded payload sizes
+    #[cfg_attr(not(feature = "compress"), allow(unused_mut, unused_variables))]
+    fn max_ratio(mut self, ratio: usize) -> Self {
+        #[cfg(feature = "compress")]
+        {
+            self.stream = self.stream.take().map(|stream| stream.max_ratio(ratio));
+        }
+        self
+    }
 }
 
 impl<U> Future for UrlEncoded<U>
@@ -304,6 +330,9 @@ where
 
         // future
         let encoding = self.encoding;
+        #[cfg(feature = "compress")]
+        let mut stream = self.stream.take().unwrap().limit(limit);
+        #[cfg(not(feature = "compress"))]
         let mut stream = self.stream.take().unwrap();
 
         self.fut = Some(Box::pin(async move {
@@ -407,6 +436,42 @@ mod tests {
         assert!(eq(res.err().unwrap(), UrlencodedError::UnknownLength));
     }
 
+    #[cfg(feature = "compress")]
+    #[crate::rt_test]
+    async fn test_form_max_ratio() {
+        use crate::http::header::CONTENT_ENCODING;
+        use std::io::Write;
+
+        let mut enc =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        enc.write_all(b"hello=").unwrap();
+        enc.write_all(&[b'x'; 1_048_576]).unwrap();
+        enc.write_all(b"&counter=1").unwrap();
+        let data = Bytes::from(enc.finish().unwrap());
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_ENCODING, "gzip")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .set_payload(data.clone())
+            .state(FormConfig::default().limit(2_097_152))
+            .to_http_parts();
+        let Form(s) = from_request::<Form<Info>>(&req, &mut pl).await.unwrap();
+        assert_eq!(s.hello.len(), 1_048_576);
+
+        let (req, mut pl) = TestRequest::with_header(CONTENT_ENCODING, "gzip")
+            .header(CONTENT_TYPE, "application/x-www-form-urlencoded")
+            .set_payload(data)
+            .state(FormConfig::default().limit(2_097_152).max_ratio(200))
+            .to_http_parts();
+        let err = from_request::<Form<Info>>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            UrlencodedError::Payload(crate::http::error::PayloadError::DecodedOverflow)
+        ));
+    }
+
     #[crate::rt_test]
     async fn test_urlencoded_error() {
         let (req, mut pl) =
@@ -0,0 +1,162 @@
+//! Typed header extractor
+use std::{fmt, ops};
+
+use crate::http::header::TypedHeader;
+use crate::web::error::{ErrorRenderer, HeaderError};
+use crate::web::{FromRequest, HttpRequest};
+use crate::{http::Payload, util::Ready};
+
+/// Extract typed header from the request.
+///
+/// If header is missing or could not be parsed, `HeaderError` is returned
+/// and `400 Bad Request` response is generated by default error renderer.
+/// Use `Option<Header<T>>` for optional headers.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::http::header::{CacheControl, CacheFlags};
+/// use ntex::web::{self, types::Header};
+///
+/// async fn index(cc: Header<CacheControl>) -> String {
+///     if cc.has_flag(CacheFlags::NO_CACHE) {
+///         "fresh".to_string()
+///     } else {
+///         "cached".to_string()
+///     }
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+pub struct Header<T>(pub T);
+
+impl<T> Header<T> {
+    /// Deconstruct to a inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Header<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Header<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Header<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Header<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T, Err> FromRequest<Err> for Header<T>
+where
+    T: TypedHeader,
+    Err: ErrorRenderer,
+{
+    type Error = HeaderError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        let name = T::name();
+        let mut values = req.headers().get_all(&name).peekable();
+        if values.peek().is_none() {
+            log::debug!("Header {} is missing. Request path: {:?}", name, req.path());
+            return Ready::Err(HeaderError::Missing(name));
+        }
+
+        match T::decode(values) {
+            Ok(value) => Ready::Ok(Header(value)),
+            Err(_) => {
+                log::debug!("Header {} is invalid. Request path: {:?}", name, req.path());
+                Ready::Err(HeaderError::Invalid(name))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+    use crate::http::header::{self, Age, CacheControl, CacheFlags, IfNoneMatch};
+    use crate::http::StatusCode;
+    use crate::web::test::{call_service, from_request, init_service, TestRequest};
+    use crate::web::{self, App, HttpResponse};
+
+    #[crate::rt_test]
+    async fn test_request_extract() {
+        let req = TestRequest::default()
+            .header(header::CACHE_CONTROL, "no-cache")
+            .header(header::CACHE_CONTROL, "max-age=60")
+            .to_srv_request();
+        let (req, mut pl) = req.into_parts();
+
+        let cc = from_request::<Header<CacheControl>>(&req, &mut pl)
+            .await
+            .unwrap();
+        assert!(cc.has_flag(CacheFlags::NO_CACHE));
+        assert_eq!(cc.get_max_age(), Some(Duration::from_secs(60)));
+        assert_eq!(
+            format!("{}, {:?}", cc, cc),
+            format!("no-cache, max-age=60, {:?}", cc.into_inner())
+        );
+
+        let res = from_request::<Header<Age>>(&req, &mut pl).await;
+        assert!(matches!(res, Err(HeaderError::Missing(_))));
+        let res = from_request::<Option<Header<Age>>>(&req, &mut pl).await;
+        assert!(res.unwrap().is_none());
+
+        let req = TestRequest::default()
+            .header(header::AGE, "abc")
+            .to_http_request();
+        let res = from_request::<Header<Age>>(&req, &mut Payload::None).await;
+        assert!(matches!(res, Err(HeaderError::Invalid(_))));
+    }
+
+    #[crate::rt_test]
+    async fn test_bad_request() {
+        let srv = init_service(App::new().service(
+            web::resource("/").to(|etag: Header<IfNoneMatch>| async move {
+                HttpResponse::Ok().body(etag.to_string())
+            }),
+        ))
+        .await;
+
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, "\"a\"")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::default().to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::default()
+            .header(header::IF_NONE_MATCH, "a")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
@@ -169,12 +169,14 @@ where
     #[inline]
     fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
         let req2 = req.clone();
-        let (limit, ctype) = req
+        let (limit, max_ratio, ctype) = req
             .app_state::<JsonConfig>()
-            .map(|c| (c.limit, c.content_type.clone()))
-            .unwrap_or((32768, None));
+            .map(|c| (c.limit, c.max_ratio, c.content_type.clone()))
+            .unwrap_or((32768, 0, None));
 
-        let fut = JsonBody::new(req, payload, ctype).limit(limit);
+        let fut = JsonBody::new(req, payload, ctype)
+            .limit(limit)
+            .max_ratio(max_ratio);
         Box::pin(async move {
             match fut.await {
                 Err(e) => {
@@ -225,6 +227,7 @@ where
 #[derive(Clone)]
 pub struct JsonConfig {
     limit: usize,
+    max_ratio: usize,
     content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
 }
 
@@ -235,6 +238,16 @@ impl JsonConfig {
         self
     }
 
+    /// Set max ratio between decoded and encoded payload sizes.
+    ///
+    /// Compressed payload is rejected if decoded payload exceeds 64Kb
+    /// and is more than `ratio` times larger than encoded payload.
+    /// By default ratio is not checked.
+    pub fn max_ratio(mut self, ratio: usize) -> Self {
+        self.max_ratio = ratio;
+        self
+    }
+
     /// Set predicate for allowed content types
     pub fn content_type<F>(mut self, predicate: F) -> Self
     where
@@ -249,6 +262,7 @@ impl Default for JsonConfig {
     fn default() -> Self {
         JsonConfig {
             limit: 32768,
+            max_ratio: 0,
             content_type: None,
         }
     }
@@ -326,6 +340,16 @@ where
         self.limit = limit;
         self
     }
+
+    /// Set max ratio between decoded and encoded payload sizes
+    #[cfg_attr(not(feature = "compress"), allow(unused_mut, unused_variables))]
+    fn max_ratio(mut self, ratio: usize) -> Self {
+        #[cfg(feature = "compress")]
+        {
+            self.stream = self.stream.take().map(|stream| stream.max_ratio(ratio));
+        }
+        self
+    }
 }
 
 impl<U> Future for JsonBody<U>
@@ -349,6 +373,9 @@ where
                 return Poll::Ready(Err(JsonPayloadError::Overflow));
             }
         }
+        #[cfg(feature = "compress")]
+        let mut stream = self.stream.take().unwrap().limit(limit);
+        #[cfg(not(feature = "compress"))]
         let mut stream = self.stream.take().unwrap();
 
         self.fut = Some(Box::pin(async move {
@@ -517,6 +544,41 @@ mod tests {
         );
     }
 
+    #[cfg(feature = "compress")]
+    #[crate::rt_test]
+    async fn test_json_max_ratio() {
+        use std::io::Write;
+
+        let mut enc =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        enc.write_all(b"{\"name\": \"").unwrap();
+        enc.write_all(&[b'x'; 1_048_576]).unwrap();
+        enc.write_all(b"\"}").unwrap();
+        let data = Bytes::from(enc.finish().unwrap());
+
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_ENCODING, "gzip")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload(data.clone())
+            .state(JsonConfig::default().limit(2_097_152))
+            .to_http_parts();
+        let s = from_request::<Json<MyObject>>(&req, &mut pl).await.unwrap();
+        assert_eq!(s.name.len(), 1_048_576);
+
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_ENCODING, "gzip")
+            .header(header::CONTENT_TYPE, "application/json")
+            .set_payload(data)
+            .state(JsonConfig::default().limit(2_097_152).max_ratio(200))
+            .to_http_parts();
+        let err = from_request::<Json<MyObject>>(&req, &mut pl)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            JsonPayloadError::Payload(crate::http::error::PayloadError::DecodedOverflow)
+        ));
+    }
+
     #[crate::rt_test]
     async fn test_with_json_and_bad_content_type() {
         let (req, mut pl) = TestRequest::with_header(
@@ -1,18 +1,35 @@
 //! Extractor types
 
+mod auth;
 pub(in crate::web) mod form;
+mod header;
 pub(in crate::web) mod json;
+mod multipart;
+mod ndjson;
+mod negotiated;
 mod path;
 pub(in crate::web) mod payload;
 mod query;
+mod ranged;
+mod sse;
 pub(in crate::web) mod state;
+mod valid;
 
+pub use self::auth::{AuthConfig, AuthScheme, BasicAuth, BearerAuth, Challenge};
 pub use self::form::{Form, FormConfig};
+pub use self::header::Header;
 pub use self::json::{Json, JsonConfig};
+pub use self::multipart::{Field, Multipart, MultipartConfig};
+pub use self::ndjson::{JsonStream, NdJson, NdJsonConfig};
+pub use self::negotiated::Negotiated;
 pub use self::path::Path;
 pub use self::payload::{Payload, PayloadConfig};
 pub use self::query::Query;
+pub use self::ranged::{RangeSource, Ranged};
+pub use self::sse::Sse;
 pub use self::state::State;
+pub use self::valid::{Valid, Validate, ValidateLength};
+pub use ntex_macros::Validate;
 
 #[deprecated]
 #[doc(hidden)]
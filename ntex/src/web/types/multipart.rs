@@ -0,0 +1,894 @@
+//! Multipart payload extractor
+use std::{
+    cell::RefCell, convert::TryFrom, fmt, pin::Pin, rc::Rc, task::Context, task::Poll,
+};
+
+use mime::Mime;
+
+#[cfg(feature = "compress")]
+use crate::http::encoding::Decoder;
+use crate::http::header::{self, HeaderMap, HeaderName, HeaderValue};
+use crate::http::Payload;
+use crate::util::{poll_fn, Bytes, BytesMut, Ready, Stream};
+use crate::web::error::{ErrorRenderer, MultipartError};
+use crate::web::{FromRequest, HttpRequest};
+
+/// Max number of headers per field
+const MAX_HEADERS: usize = 32;
+/// Max size of the field headers block
+const MAX_HEADERS_SIZE: usize = 8192;
+
+/// Multipart payload extractor (`multipart/form-data`)
+///
+/// Payload is parsed incrementally, `Multipart` is a stream of
+/// [`Field`](struct.Field.html) items and each field is a stream of
+/// `Bytes` chunks. Fields must be consumed in order, polling `Multipart`
+/// for the next field skips unread data of the current field.
+///
+/// [**MultipartConfig**](struct.MultipartConfig.html) allows to configure
+/// extraction process.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, error, types::Multipart, App};
+///
+/// async fn index(mut form: Multipart) -> Result<String, error::MultipartError> {
+///     let mut result = String::new();
+///
+///     while let Some(field) = form.recv().await {
+///         let mut field = field?;
+///         let mut size = 0;
+///         while let Some(chunk) = field.recv().await {
+///             size += chunk?.len();
+///         }
+///         result.push_str(&format!(
+///             "{:?} {:?} {}\n", field.name(), field.filename(), size
+///         ));
+///     }
+///     Ok(result)
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/upload").route(web::post().to(index))
+///     );
+/// }
+/// ```
+pub struct Multipart {
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl Multipart {
+    /// Create multipart stream from request headers and payload.
+    pub fn new(
+        headers: &HeaderMap,
+        payload: Payload,
+        cfg: &MultipartConfig,
+    ) -> Result<Multipart, MultipartError> {
+        let boundary = Self::boundary(headers)?;
+
+        if let Some(len) = headers
+            .get(&header::CONTENT_LENGTH)
+            .and_then(|l| l.to_str().ok())
+            .and_then(|s| s.parse::<usize>().ok())
+        {
+            if len > cfg.limit {
+                return Err(MultipartError::Overflow { limit: cfg.limit });
+            }
+        }
+
+        #[cfg(feature = "compress")]
+        let payload = Decoder::from_headers(payload, headers).limit(cfg.limit);
+
+        Ok(Multipart {
+            inner: Rc::new(RefCell::new(Inner {
+                payload,
+                boundary: format!("--{}", boundary).into_bytes(),
+                buf: BytesMut::new(),
+                eof: false,
+                state: State::Preamble,
+                idx: 0,
+                size: 0,
+                total: 0,
+                limit: cfg.limit,
+                field_limit: cfg.field_limit,
+            })),
+        })
+    }
+
+    /// Extract boundary parameter from the content-type header.
+    fn boundary(headers: &HeaderMap) -> Result<String, MultipartError> {
+        let ct = headers
+            .get(&header::CONTENT_TYPE)
+            .ok_or(MultipartError::NoContentType)?
+            .to_str()
+            .map_err(|_| MultipartError::ParseContentType)?
+            .parse::<Mime>()
+            .map_err(|_| MultipartError::ParseContentType)?;
+
+        if ct.type_() != mime::MULTIPART {
+            return Err(MultipartError::ParseContentType);
+        }
+        match ct.get_param(mime::BOUNDARY) {
+            Some(b) if !b.as_str().is_empty() && b.as_str().len() <= 70 => {
+                Ok(b.as_str().to_owned())
+            }
+            _ => Err(MultipartError::Boundary),
+        }
+    }
+
+    #[inline]
+    /// Attempt to pull out the next field of the multipart stream.
+    pub async fn recv(&mut self) -> Option<Result<Field, MultipartError>> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Attempt to pull out the next field of the multipart stream,
+    /// registering the current task for wakeup if the field is not
+    /// yet available, and returning None if the stream is exhausted.
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Field, MultipartError>>> {
+        let mut inner = self.inner.borrow_mut();
+        match inner.poll_field(cx) {
+            Poll::Ready(Some(Ok((headers, ct, cd)))) => Poll::Ready(Some(Ok(Field {
+                headers,
+                ct,
+                name: cd.as_ref().and_then(|cd| cd.param("name")),
+                filename: cd.as_ref().and_then(|cd| cd.filename()),
+                idx: inner.idx,
+                inner: self.inner.clone(),
+            }))),
+            Poll::Ready(Some(Err(e))) => {
+                inner.state = State::Eof;
+                Poll::Ready(Some(Err(e)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+impl Stream for Multipart {
+    type Item = Result<Field, MultipartError>;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx)
+    }
+}
+
+impl fmt::Debug for Multipart {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Multipart")
+            .field("state", &self.inner.borrow().state)
+            .finish()
+    }
+}
+
+/// Get request's multipart stream
+///
+/// Content type must be `multipart/*` with `boundary` parameter.
+impl<Err: ErrorRenderer> FromRequest<Err> for Multipart {
+    type Error = MultipartError;
+    type Future = Ready<Multipart, MultipartError>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let tmp;
+        let cfg = if let Some(cfg) = req.app_state::<MultipartConfig>() {
+            cfg
+        } else {
+            tmp = MultipartConfig::default();
+            &tmp
+        };
+
+        Multipart::new(req.headers(), payload.take(), cfg).into()
+    }
+}
+
+/// Single field of the multipart stream
+///
+/// Field is a stream of `Bytes` chunks. Field stream is exhausted when
+/// multipart stream moves to the next field.
+pub struct Field {
+    idx: usize,
+    ct: Mime,
+    headers: HeaderMap,
+    name: Option<String>,
+    filename: Option<String>,
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl Field {
+    #[inline]
+    /// Returns field's headers.
+    pub fn headers(&self) -> &HeaderMap {
+        &self.headers
+    }
+
+    #[inline]
+    /// Returns field's content type.
+    ///
+    /// If field does not define content type, `text/plain` is returned.
+    pub fn content_type(&self) -> &Mime {
+        &self.ct
+    }
+
+    #[inline]
+    /// Field name from `Content-Disposition` header.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    #[inline]
+    /// File name from `Content-Disposition` header.
+    pub fn filename(&self) -> Option<&str> {
+        self.filename.as_deref()
+    }
+
+    #[inline]
+    /// Attempt to pull out the next chunk of the field.
+    pub async fn recv(&mut self) -> Option<Result<Bytes, MultipartError>> {
+        poll_fn(|cx| self.poll_recv(cx)).await
+    }
+
+    /// Attempt to pull out the next chunk of the field, registering
+    /// the current task for wakeup if the chunk is not yet available,
+    /// and returning None if the field is exhausted.
+    pub fn poll_recv(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, MultipartError>>> {
+        let mut inner = self.inner.borrow_mut();
+        if inner.idx != self.idx {
+            return Poll::Ready(None);
+        }
+        match inner.poll_chunk(cx) {
+            Poll::Ready(Some(Err(e))) => {
+                inner.state = State::Eof;
+                Poll::Ready(Some(Err(e)))
+            }
+            res => res,
+        }
+    }
+}
+
+impl Stream for Field {
+    type Item = Result<Bytes, MultipartError>;
+
+    #[inline]
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        self.poll_recv(cx)
+    }
+}
+
+impl fmt::Debug for Field {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Field")
+            .field("name", &self.name)
+            .field("filename", &self.filename)
+            .field("content_type", &self.ct)
+            .field("headers", &self.headers)
+            .finish()
+    }
+}
+
+/// Multipart extractor configuration
+///
+/// ```rust
+/// use ntex::web::{self, types::Multipart, App};
+///
+/// async fn index(form: Multipart) -> &'static str {
+///     "Ok"
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/upload")
+///             // max request size is 1Mb, max field size is 64kb
+///             .app_state(
+///                 web::types::MultipartConfig::default()
+///                     .limit(1_048_576)
+///                     .field_limit(65_536)
+///             )
+///             .route(web::post().to(index))
+///     );
+/// }
+/// ```
+#[derive(Clone, Debug)]
+pub struct MultipartConfig {
+    limit: usize,
+    field_limit: usize,
+}
+
+impl MultipartConfig {
+    /// Change max size of the whole payload. By default max size is 8Mb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Change max size of a single field. By default max size is 4Mb
+    pub fn field_limit(mut self, limit: usize) -> Self {
+        self.field_limit = limit;
+        self
+    }
+}
+
+impl Default for MultipartConfig {
+    fn default() -> Self {
+        MultipartConfig {
+            limit: 8_388_608,
+            field_limit: 4_194_304,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum State {
+    /// Skip data before first boundary
+    Preamble,
+    /// Boundary is found, check for the final boundary
+    Boundary,
+    /// Read field headers
+    Headers,
+    /// Read field body
+    Body,
+    /// Final boundary is found
+    Eof,
+}
+
+struct Inner {
+    #[cfg(feature = "compress")]
+    payload: Decoder<Payload>,
+    #[cfg(not(feature = "compress"))]
+    payload: Payload,
+    boundary: Vec<u8>,
+    buf: BytesMut,
+    eof: bool,
+    state: State,
+    /// current field index
+    idx: usize,
+    /// current field size
+    size: usize,
+    /// total payload size
+    total: usize,
+    limit: usize,
+    field_limit: usize,
+}
+
+impl Inner {
+    /// Read next chunk from the payload into buffer
+    fn poll_fill(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), MultipartError>> {
+        if self.eof {
+            return Poll::Ready(Err(MultipartError::Incomplete));
+        }
+
+        match Pin::new(&mut self.payload).poll_next(cx) {
+            Poll::Ready(Some(Ok(chunk))) => {
+                self.total += chunk.len();
+                if self.total > self.limit {
+                    Poll::Ready(Err(MultipartError::Overflow { limit: self.limit }))
+                } else {
+                    self.buf.extend_from_slice(&chunk);
+                    Poll::Ready(Ok(()))
+                }
+            }
+            Poll::Ready(Some(Err(e))) => Poll::Ready(Err(e.into())),
+            Poll::Ready(None) => {
+                self.eof = true;
+                Poll::Ready(Ok(()))
+            }
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn poll_field(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<(HeaderMap, Mime, Option<ContentDisposition>), MultipartError>>>
+    {
+        loop {
+            let state = self.state;
+            let res = match state {
+                State::Eof => return Poll::Ready(None),
+                State::Body => {
+                    // skip unread data of the current field
+                    match self.poll_chunk(cx) {
+                        Poll::Ready(Some(Ok(_))) | Poll::Ready(None) => continue,
+                        Poll::Ready(Some(Err(e))) => return Poll::Ready(Some(Err(e))),
+                        Poll::Pending => return Poll::Pending,
+                    }
+                }
+                State::Preamble => self.read_preamble().map(|_| None),
+                State::Boundary => self.read_boundary().map(|_| None),
+                State::Headers => self.read_headers(),
+            };
+
+            match res {
+                Ok(Some(item)) => return Poll::Ready(Some(Ok(item))),
+                Ok(None) => (),
+                Err(e) => return Poll::Ready(Some(Err(e))),
+            }
+
+            // need more data
+            if self.state == state {
+                match self.poll_fill(cx) {
+                    Poll::Ready(Ok(())) => (),
+                    Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e))),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+    }
+
+    /// Skip everything before the first boundary
+    fn read_preamble(&mut self) -> Result<(), MultipartError> {
+        let blen = self.boundary.len();
+        let mut pos = 0;
+        while let Some(idx) = find(&self.buf[pos..], &self.boundary) {
+            let idx = pos + idx;
+            if idx == 0 || (idx >= 2 && &self.buf[idx - 2..idx] == b"\r\n") {
+                let _ = self.buf.split_to(idx + blen);
+                self.state = State::Boundary;
+                return Ok(());
+            }
+            pos = idx + 1;
+        }
+        // keep possible partial boundary
+        if self.buf.len() > blen + 1 {
+            let _ = self.buf.split_to(self.buf.len() - blen - 1);
+        }
+        if self.eof {
+            Err(MultipartError::Incomplete)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Check boundary tail, it is either final boundary or line end
+    fn read_boundary(&mut self) -> Result<(), MultipartError> {
+        if self.buf.len() >= 2 && &self.buf[..2] == b"--" {
+            // final boundary, ignore epilogue
+            self.buf.clear();
+            self.state = State::Eof;
+            return Ok(());
+        }
+
+        if let Some(idx) = find(&self.buf, b"\r\n") {
+            // transport padding is allowed after boundary
+            if self.buf[..idx].iter().all(|c| *c == b' ' || *c == b'\t') {
+                let _ = self.buf.split_to(idx + 2);
+                self.state = State::Headers;
+                Ok(())
+            } else {
+                Err(MultipartError::Boundary)
+            }
+        } else if self.buf.len() > 128 {
+            Err(MultipartError::Boundary)
+        } else if self.eof {
+            Err(MultipartError::Incomplete)
+        } else {
+            Ok(())
+        }
+    }
+
+    /// Read field's headers
+    fn read_headers(
+        &mut self,
+    ) -> Result<Option<(HeaderMap, Mime, Option<ContentDisposition>)>, MultipartError> {
+        let end = if self.buf.starts_with(b"\r\n") {
+            Some(2)
+        } else {
+            find(&self.buf, b"\r\n\r\n").map(|idx| idx + 4)
+        };
+
+        let end = match end {
+            Some(end) => end,
+            None => {
+                return if self.buf.len() > MAX_HEADERS_SIZE {
+                    Err(MultipartError::Headers)
+                } else if self.eof {
+                    Err(MultipartError::Incomplete)
+                } else {
+                    Ok(None)
+                };
+            }
+        };
+
+        let data = self.buf.split_to(end);
+        let mut hdrs = [httparse::EMPTY_HEADER; MAX_HEADERS];
+        let parsed = match httparse::parse_headers(&data, &mut hdrs) {
+            Ok(httparse::Status::Complete((_, parsed))) => parsed,
+            _ => return Err(MultipartError::Headers),
+        };
+
+        let mut headers = HeaderMap::with_capacity(parsed.len());
+        for h in parsed {
+            let name = HeaderName::try_from(h.name).map_err(|_| MultipartError::Headers)?;
+            let value =
+                HeaderValue::try_from(h.value).map_err(|_| MultipartError::Headers)?;
+            headers.append(name, value);
+        }
+
+        let ct = if let Some(ct) = headers.get(&header::CONTENT_TYPE) {
+            ct.to_str()
+                .ok()
+                .and_then(|ct| ct.parse::<Mime>().ok())
+                .ok_or(MultipartError::ParseContentType)?
+        } else {
+            mime::TEXT_PLAIN
+        };
+        let cd = headers
+            .get(&header::CONTENT_DISPOSITION)
+            .and_then(|cd| cd.to_str().ok())
+            .map(ContentDisposition::parse);
+
+        self.idx += 1;
+        self.size = 0;
+        self.state = State::Body;
+        Ok(Some((headers, ct, cd)))
+    }
+
+    /// Read next chunk of the current field
+    fn poll_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, MultipartError>>> {
+        loop {
+            if self.state != State::Body {
+                return Poll::Ready(None);
+            }
+
+            // field data ends with CRLF followed by boundary
+            let blen = self.boundary.len() + 2;
+            let chunk = if let Some(idx) = find_delimiter(&self.buf, &self.boundary) {
+                let chunk = self.buf.split_to(idx).freeze();
+                let _ = self.buf.split_to(blen);
+                self.state = State::Boundary;
+                chunk
+            } else if self.buf.len() >= blen {
+                // keep possible partial delimiter
+                self.buf.split_to(self.buf.len() - blen + 1).freeze()
+            } else {
+                Bytes::new()
+            };
+
+            if !chunk.is_empty() {
+                self.size += chunk.len();
+                return if self.size > self.field_limit {
+                    Poll::Ready(Some(Err(MultipartError::FieldOverflow {
+                        limit: self.field_limit,
+                    })))
+                } else {
+                    Poll::Ready(Some(Ok(chunk)))
+                };
+            }
+
+            if self.state == State::Body {
+                match self.poll_fill(cx) {
+                    Poll::Ready(Ok(())) => (),
+                    Poll::Ready(Err(e)) => return Poll::Ready(Some(Err(e))),
+                    Poll::Pending => return Poll::Pending,
+                }
+            }
+        }
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Find CRLF followed by boundary
+fn find_delimiter(buf: &[u8], boundary: &[u8]) -> Option<usize> {
+    let mut pos = 0;
+    while let Some(idx) = find(&buf[pos..], boundary) {
+        let idx = pos + idx;
+        if idx >= 2 && &buf[idx - 2..idx] == b"\r\n" {
+            return Some(idx - 2);
+        }
+        pos = idx + 1;
+    }
+    None
+}
+
+/// Parsed `Content-Disposition` header of the field
+struct ContentDisposition {
+    params: Vec<(String, String)>,
+}
+
+impl ContentDisposition {
+    fn parse(s: &str) -> Self {
+        let mut params = Vec::new();
+        let mut chars = s.chars().peekable();
+
+        // skip disposition type
+        for c in chars.by_ref() {
+            if c == ';' {
+                break;
+            }
+        }
+
+        loop {
+            let name: String = chars
+                .by_ref()
+                .skip_while(|c| c.is_whitespace())
+                .take_while(|c| *c != '=')
+                .collect();
+            if name.is_empty() {
+                break;
+            }
+
+            let mut value = String::new();
+            if chars.peek() == Some(&'"') {
+                let _ = chars.next();
+                while let Some(c) = chars.next() {
+                    match c {
+                        '\\' => {
+                            if let Some(c) = chars.next() {
+                                value.push(c)
+                            }
+                        }
+                        '"' => break,
+                        _ => value.push(c),
+                    }
+                }
+                for c in chars.by_ref() {
+                    if c == ';' {
+                        break;
+                    }
+                }
+            } else {
+                value = chars.by_ref().take_while(|c| *c != ';').collect();
+            }
+            params.push((name.trim().to_ascii_lowercase(), value.trim().to_owned()));
+        }
+
+        ContentDisposition { params }
+    }
+
+    fn param(&self, name: &str) -> Option<String> {
+        self.params
+            .iter()
+            .find(|(n, _)| n == name)
+            .map(|(_, v)| v.clone())
+    }
+
+    fn filename(&self) -> Option<String> {
+        // extended notation, RFC 5987
+        if let Some(val) = self.param("filename*") {
+            let mut parts = val.splitn(3, '\'');
+            if let (Some(charset), Some(_), Some(val)) =
+                (parts.next(), parts.next(), parts.next())
+            {
+                if charset.eq_ignore_ascii_case("utf-8") {
+                    if let Ok(val) = percent_encoding::percent_decode_str(val).decode_utf8()
+                    {
+                        return Some(val.into_owned());
+                    }
+                }
+            }
+        }
+        self.param("filename")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::h1;
+    use crate::web::test::{from_request, TestRequest};
+    use crate::web::DefaultError;
+
+    const BODY: &[u8] = b"testasdadsad\r\n\
+        --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+        Content-Disposition: form-data; name=\"file\"; filename=\"fn.txt\"\r\n\
+        Content-Type: text/plain; charset=utf-8\r\nContent-Length: 4\r\n\r\n\
+        test\r\n\
+        --abbc761f78ff4d7cb7573b5a23f96ef0\r\n\
+        Content-Disposition: form-data; name=\"data\"\r\n\r\n\
+        data\r\n\
+        --abbc761f78ff4d7cb7573b5a23f96ef0--\r\n";
+
+    fn request() -> TestRequest {
+        TestRequest::with_header(
+            header::CONTENT_TYPE,
+            "multipart/mixed; boundary=\"abbc761f78ff4d7cb7573b5a23f96ef0\"",
+        )
+    }
+
+    async fn read_field(field: &mut Field) -> Result<Vec<u8>, MultipartError> {
+        let mut data = Vec::new();
+        while let Some(chunk) = field.recv().await {
+            data.extend_from_slice(&chunk?);
+        }
+        Ok(data)
+    }
+
+    #[crate::rt_test]
+    async fn test_boundary() {
+        let req = TestRequest::default().to_http_request();
+        match Multipart::boundary(req.headers()) {
+            Err(MultipartError::NoContentType) => (),
+            _ => unreachable!("should not happen"),
+        }
+
+        let req = TestRequest::with_header(header::CONTENT_TYPE, "test").to_http_request();
+        match Multipart::boundary(req.headers()) {
+            Err(MultipartError::ParseContentType) => (),
+            _ => unreachable!("should not happen"),
+        }
+
+        let req = TestRequest::with_header(header::CONTENT_TYPE, "multipart/mixed")
+            .to_http_request();
+        match Multipart::boundary(req.headers()) {
+            Err(MultipartError::Boundary) => (),
+            _ => unreachable!("should not happen"),
+        }
+
+        let req = request().to_http_request();
+        assert_eq!(
+            Multipart::boundary(req.headers()).unwrap(),
+            "abbc761f78ff4d7cb7573b5a23f96ef0"
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_multipart() {
+        let (req, mut pl) = request().set_payload(BODY).to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert_eq!(field.name(), Some("file"));
+        assert_eq!(field.filename(), Some("fn.txt"));
+        assert_eq!(field.content_type().type_(), mime::TEXT);
+        assert_eq!(field.content_type().subtype(), mime::PLAIN);
+        assert_eq!(field.headers().get(header::CONTENT_LENGTH).unwrap(), "4");
+        assert!(format!("{:?}", field).contains("fn.txt"));
+        assert_eq!(read_field(&mut field).await.unwrap(), b"test");
+
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert_eq!(field.name(), Some("data"));
+        assert_eq!(field.filename(), None);
+        assert_eq!(field.content_type(), &mime::TEXT_PLAIN);
+        assert_eq!(read_field(&mut field).await.unwrap(), b"data");
+
+        assert!(form.recv().await.is_none());
+        assert!(format!("{:?}", form).contains("Eof"));
+    }
+
+    #[crate::rt_test]
+    async fn test_stream() {
+        let (mut sender, payload) = h1::Payload::create(false);
+        let req = request().to_http_request();
+        let mut form =
+            Multipart::new(req.headers(), payload.into(), &MultipartConfig::default())
+                .unwrap();
+
+        // feed payload byte by byte
+        for b in BODY {
+            sender.feed_data(Bytes::copy_from_slice(&[*b]));
+        }
+        sender.feed_eof();
+
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert_eq!(field.filename(), Some("fn.txt"));
+        assert_eq!(read_field(&mut field).await.unwrap(), b"test");
+
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert_eq!(read_field(&mut field).await.unwrap(), b"data");
+        assert!(form.recv().await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_skip_field() {
+        let (req, mut pl) = request().set_payload(BODY).to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+
+        let mut first = form.recv().await.unwrap().unwrap();
+        let mut field = form.recv().await.unwrap().unwrap();
+        assert!(first.recv().await.is_none());
+        assert_eq!(field.name(), Some("data"));
+        assert_eq!(read_field(&mut field).await.unwrap(), b"data");
+        assert!(form.recv().await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_limits() {
+        let (req, mut pl) = request()
+            .header(header::CONTENT_LENGTH, "1000000")
+            .set_payload(BODY)
+            .state(MultipartConfig::default().limit(1024))
+            .to_http_parts();
+        match from_request::<Multipart>(&req, &mut pl).await {
+            Err(MultipartError::Overflow { limit: 1024 }) => (),
+            _ => unreachable!("should not happen"),
+        }
+
+        let (req, mut pl) = request()
+            .set_payload(BODY)
+            .state(MultipartConfig::default().limit(64))
+            .to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+        match form.recv().await.unwrap() {
+            Err(MultipartError::Overflow { limit: 64 }) => (),
+            _ => unreachable!("should not happen"),
+        }
+        assert!(form.recv().await.is_none());
+
+        let (req, mut pl) = request()
+            .set_payload(BODY)
+            .state(MultipartConfig::default().field_limit(2))
+            .to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+        let mut field = form.recv().await.unwrap().unwrap();
+        match read_field(&mut field).await {
+            Err(MultipartError::FieldOverflow { limit: 2 }) => (),
+            _ => unreachable!("should not happen"),
+        }
+        assert!(form.recv().await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_incomplete() {
+        let (req, mut pl) = request()
+            .set_payload(&BODY[..BODY.len() - 10])
+            .to_http_parts();
+        let mut form = from_request::<Multipart>(&req, &mut pl).await.unwrap();
+        let _ = form.recv().await.unwrap().unwrap();
+        let mut field = form.recv().await.unwrap().unwrap();
+        match read_field(&mut field).await {
+            Err(MultipartError::Incomplete) => (),
+            _ => unreachable!("should not happen"),
+        }
+    }
+
+    #[crate::rt_test]
+    async fn test_error_response() {
+        use crate::http::StatusCode;
+        use crate::web::WebResponseError;
+
+        let req = TestRequest::default().to_http_request();
+        let resp = WebResponseError::<DefaultError>::error_response(
+            &MultipartError::Overflow { limit: 0 },
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp = WebResponseError::<DefaultError>::error_response(
+            &MultipartError::FieldOverflow { limit: 0 },
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let resp = WebResponseError::<DefaultError>::error_response(
+            &MultipartError::Boundary,
+            &req,
+        );
+        assert_eq!(resp.status(), StatusCode::BAD_REQUEST);
+    }
+
+    #[test]
+    fn test_content_disposition() {
+        let cd = ContentDisposition::parse(
+            "form-data; name=\"f\\\"n\"; filename=plain.txt; \
+             filename*=UTF-8''%e2%82%ac%20rates.txt",
+        );
+        assert_eq!(cd.param("name").unwrap(), "f\"n");
+        assert_eq!(cd.filename().unwrap(), "€ rates.txt");
+
+        let cd = ContentDisposition::parse("form-data; name=field; filename=\"a;b.txt\"");
+        assert_eq!(cd.param("name").unwrap(), "field");
+        assert_eq!(cd.filename().unwrap(), "a;b.txt");
+    }
+}
@@ -0,0 +1,633 @@
+//! Streaming json responder/extractor
+use std::task::{Context, Poll};
+use std::{error::Error, marker::PhantomData, pin::Pin, sync::Arc};
+
+use serde::{de::DeserializeOwned, Serialize};
+
+use crate::http::body::{Body, BodySize, MessageBody};
+#[cfg(feature = "compress")]
+use crate::http::encoding::Decoder;
+use crate::http::header::{self, HeaderValue};
+use crate::http::{HttpMessage, Payload, Response, StatusCode};
+use crate::util::{BufMut, Bytes, BytesMut, Ready, Stream};
+use crate::web::error::{ErrorRenderer, JsonPayloadError};
+use crate::web::responder::{self, Responder};
+use crate::web::{FromRequest, HttpRequest, HttpResponse};
+
+const MAX_CHUNK_SIZE: usize = 16_384;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Format {
+    Lines,
+    Array,
+}
+
+/// Streaming json responder
+///
+/// Responder serializes items of the stream one by one, either as
+/// newline-delimited json (`application/x-ndjson`) or as elements of
+/// json array (`application/json`). Whole value is never buffered,
+/// stream is polled only when response payload is able to accept more
+/// data. Serialization error terminates response.
+///
+/// ```rust
+/// use futures_util::stream;
+/// use ntex::web;
+///
+/// #[derive(serde::Serialize)]
+/// struct Record {
+///     id: u64,
+/// }
+///
+/// async fn export() -> impl web::Responder {
+///     let records = stream::iter((0..1000).map(|id| Record { id }));
+///     web::types::JsonStream::ndjson(records)
+/// }
+/// ```
+pub struct JsonStream<S> {
+    stream: S,
+    format: Format,
+}
+
+impl<S, T> JsonStream<S>
+where
+    S: Stream<Item = T> + Unpin + 'static,
+    T: Serialize + 'static,
+{
+    /// Create responder that sends items as newline-delimited json.
+    pub fn ndjson(stream: S) -> Self {
+        JsonStream {
+            stream,
+            format: Format::Lines,
+        }
+    }
+
+    /// Create responder that sends items as json array.
+    pub fn array(stream: S) -> Self {
+        JsonStream {
+            stream,
+            format: Format::Array,
+        }
+    }
+
+    /// Build response
+    pub fn into_response(self) -> HttpResponse {
+        let content_type = match self.format {
+            Format::Lines => "application/x-ndjson",
+            Format::Array => "application/json",
+        };
+
+        Response::build(StatusCode::OK)
+            .header(header::CONTENT_TYPE, HeaderValue::from_static(content_type))
+            .body(Body::from_message(JsonStreamBody {
+                buf: BytesMut::new(),
+                stream: self.stream,
+                format: self.format,
+                first: true,
+                eof: false,
+            }))
+    }
+}
+
+impl<S, T, Err> Responder<Err> for JsonStream<S>
+where
+    S: Stream<Item = T> + Unpin + 'static,
+    T: Serialize + 'static,
+    Err: ErrorRenderer,
+{
+    type Error = Err::Container;
+    type Future = responder::Ready<HttpResponse>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        self.into_response().into()
+    }
+}
+
+struct JsonStreamBody<S> {
+    buf: BytesMut,
+    stream: S,
+    format: Format,
+    first: bool,
+    eof: bool,
+}
+
+impl<S> JsonStreamBody<S> {
+    fn encode<T: Serialize>(&mut self, item: &T) -> Result<(), serde_json::Error> {
+        match self.format {
+            Format::Lines => {
+                serde_json::to_writer((&mut self.buf).writer(), item)?;
+                self.buf.put_u8(b'\n');
+            }
+            Format::Array => {
+                self.buf.put_u8(if self.first { b'[' } else { b',' });
+                serde_json::to_writer((&mut self.buf).writer(), item)?;
+            }
+        }
+        self.first = false;
+        Ok(())
+    }
+
+    fn finish(&mut self) {
+        self.eof = true;
+        if self.format == Format::Array {
+            self.buf
+                .extend_from_slice(if self.first { b"[]" } else { b"]" });
+        }
+    }
+}
+
+impl<S, T> MessageBody for JsonStreamBody<S>
+where
+    S: Stream<Item = T> + Unpin + 'static,
+    T: Serialize + 'static,
+{
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        // collect ready items
+        while !self.eof && self.buf.len() < MAX_CHUNK_SIZE {
+            match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(item)) => {
+                    if let Err(err) = self.encode(&item) {
+                        self.eof = true;
+                        self.buf.clear();
+                        return Poll::Ready(Some(Err(Box::new(err))));
+                    }
+                }
+                Poll::Ready(None) => self.finish(),
+                Poll::Pending => break,
+            }
+        }
+
+        if !self.buf.is_empty() {
+            Poll::Ready(Some(Ok(self.buf.split().freeze())))
+        } else if self.eof {
+            Poll::Ready(None)
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+/// Newline-delimited json extractor
+///
+/// Extractor deserializes request's payload as a stream of items, one item
+/// per line. Payload is read incrementally, size of each item is limited
+/// by [**NdJsonConfig**](struct.NdJsonConfig.html), default limit is 32Kb.
+/// Total payload size is not limited by default. Empty lines are skipped.
+/// Stream terminates after first error.
+///
+/// Content type of the request must be `application/x-ndjson`,
+/// `application/ndjson` or `application/jsonl`.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, error::JsonPayloadError, types::NdJson};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Record {
+///     id: u64,
+/// }
+///
+/// async fn import(mut records: NdJson<Record>) -> Result<String, JsonPayloadError> {
+///     let mut count = 0;
+///     while let Some(record) = records.recv().await {
+///         let _record = record?;
+///         count += 1;
+///     }
+///     Ok(format!("Imported {} records", count))
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/import").route(web::post().to(import)));
+/// }
+/// ```
+pub struct NdJson<T> {
+    #[cfg(feature = "compress")]
+    stream: Decoder<Payload>,
+    #[cfg(not(feature = "compress"))]
+    stream: Payload,
+    buf: BytesMut,
+    limit: usize,
+    max_size: usize,
+    size: usize,
+    scanned: usize,
+    eof: bool,
+    done: bool,
+    _t: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> NdJson<T> {
+    /// Attempt to pull out the next item of the stream.
+    pub async fn recv(&mut self) -> Option<Result<T, JsonPayloadError>> {
+        crate::util::stream_recv(self).await
+    }
+
+    fn next_line(&mut self) -> Option<Result<Bytes, JsonPayloadError>> {
+        // bytes before `scanned` offset do not contain newline
+        while let Some(pos) = self.buf[self.scanned..].iter().position(|b| *b == b'\n') {
+            let pos = self.scanned + pos;
+            if pos > self.limit {
+                return Some(Err(JsonPayloadError::Overflow));
+            }
+            let line = self.buf.split_to(pos + 1).freeze();
+            self.scanned = 0;
+            if !line.iter().all(u8::is_ascii_whitespace) {
+                return Some(Ok(line));
+            }
+        }
+        self.scanned = self.buf.len();
+
+        if self.buf.len() > self.limit {
+            Some(Err(JsonPayloadError::Overflow))
+        } else if self.eof && !self.buf.iter().all(u8::is_ascii_whitespace) {
+            self.scanned = 0;
+            Some(Ok(self.buf.split().freeze()))
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: DeserializeOwned> Stream for NdJson<T> {
+    type Item = Result<T, JsonPayloadError>;
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Self::Item>> {
+        let this = self.as_mut().get_mut();
+
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+
+            let item = match this.next_line() {
+                Some(Ok(line)) => serde_json::from_slice(&line).map_err(Into::into),
+                Some(Err(err)) => Err(err),
+                None if this.eof => {
+                    this.done = true;
+                    continue;
+                }
+                None => match Pin::new(&mut this.stream).poll_next(cx) {
+                    Poll::Ready(Some(Ok(chunk))) => {
+                        this.size += chunk.len();
+                        if this.size > this.max_size {
+                            Err(JsonPayloadError::Overflow)
+                        } else {
+                            this.buf.extend_from_slice(&chunk);
+                            continue;
+                        }
+                    }
+                    Poll::Ready(Some(Err(err))) => Err(err.into()),
+                    Poll::Ready(None) => {
+                        this.eof = true;
+                        continue;
+                    }
+                    Poll::Pending => return Poll::Pending,
+                },
+            };
+
+            if item.is_err() {
+                this.done = true;
+                this.buf.clear();
+            }
+            return Poll::Ready(Some(item));
+        }
+    }
+}
+
+impl<T, Err: ErrorRenderer> FromRequest<Err> for NdJson<T>
+where
+    T: DeserializeOwned + 'static,
+{
+    type Error = JsonPayloadError;
+    type Future = Ready<Self, Self::Error>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let cfg = req.app_state::<NdJsonConfig>();
+        let (limit, max_size, ctype) = cfg
+            .map(|c| (c.limit, c.max_size, c.content_type.clone()))
+            .unwrap_or((32768, usize::MAX, None));
+
+        // check content-type
+        let ndjson = if let Ok(Some(mime)) = req.mime_type() {
+            mime.type_() == mime::APPLICATION
+                && matches!(mime.subtype().as_str(), "x-ndjson" | "ndjson" | "jsonl")
+                || ctype
+                    .as_ref()
+                    .map(|predicate| predicate(mime))
+                    .unwrap_or(false)
+        } else {
+            false
+        };
+        if !ndjson {
+            log::debug!(
+                "Content type of NdJson payload is not supported. Request path: {}",
+                req.path()
+            );
+            return Ready::Err(JsonPayloadError::ContentType);
+        }
+
+        #[cfg(feature = "compress")]
+        let stream = Decoder::from_headers(payload.take(), req.headers())
+            .limit(max_size)
+            .max_ratio(cfg.map(|c| c.max_ratio).unwrap_or(0));
+        #[cfg(not(feature = "compress"))]
+        let stream = payload.take();
+
+        Ready::Ok(NdJson {
+            stream,
+            limit,
+            max_size,
+            size: 0,
+            scanned: 0,
+            buf: BytesMut::new(),
+            eof: false,
+            done: false,
+            _t: PhantomData,
+        })
+    }
+}
+
+/// NdJson extractor configuration
+///
+/// ```rust
+/// use ntex::web::{self, App, types::{NdJson, NdJsonConfig}};
+///
+/// #[derive(serde::Deserialize)]
+/// struct Record {
+///     id: u64,
+/// }
+///
+/// async fn import(records: NdJson<Record>) -> &'static str {
+///     "done"
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(
+///         web::resource("/import")
+///             // max size of each record is 1kb
+///             .app_state(NdJsonConfig::default().limit(1024))
+///             .route(web::post().to(import))
+///     );
+/// }
+/// ```
+#[derive(Clone)]
+pub struct NdJsonConfig {
+    limit: usize,
+    max_size: usize,
+    max_ratio: usize,
+    content_type: Option<Arc<dyn Fn(mime::Mime) -> bool + Send + Sync>>,
+}
+
+impl NdJsonConfig {
+    /// Change max size of each item. By default max size is 32Kb
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = limit;
+        self
+    }
+
+    /// Change max size of whole payload. By default size is not limited
+    pub fn max_size(mut self, size: usize) -> Self {
+        self.max_size = size;
+        self
+    }
+
+    /// Set max ratio between decoded and encoded payload sizes.
+    ///
+    /// Compressed payload is rejected if decoded payload exceeds 64Kb
+    /// and is more than `ratio` times larger than encoded payload.
+    /// By default ratio is not checked.
+    pub fn max_ratio(mut self, ratio: usize) -> Self {
+        self.max_ratio = ratio;
+        self
+    }
+
+    /// Set predicate for allowed content types
+    pub fn content_type<F>(mut self, predicate: F) -> Self
+    where
+        F: Fn(mime::Mime) -> bool + Send + Sync + 'static,
+    {
+        self.content_type = Some(Arc::new(predicate));
+        self
+    }
+}
+
+impl Default for NdJsonConfig {
+    fn default() -> Self {
+        NdJsonConfig {
+            limit: 32768,
+            max_size: usize::MAX,
+            max_ratio: 0,
+            content_type: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use futures_util::stream;
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::channel::mpsc;
+    use crate::util::stream_recv;
+    use crate::web::test::{
+        call_service, from_request, init_service, read_body, TestRequest,
+    };
+    use crate::web::{self, App, DefaultError};
+
+    #[derive(Serialize, Deserialize, Debug, PartialEq)]
+    struct Item {
+        id: u32,
+    }
+
+    #[crate::rt_test]
+    async fn test_json_stream() {
+        let items = stream::iter((1..4).map(|id| Item { id }));
+        let req = TestRequest::default().to_http_request();
+        let mut res =
+            Responder::<DefaultError>::respond_to(JsonStream::ndjson(items), &req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/x-ndjson"
+        );
+        let mut body = res.take_body();
+        assert_eq!(body.size(), BodySize::Stream);
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, "{\"id\":1}\n{\"id\":2}\n{\"id\":3}\n");
+        assert!(stream_recv(&mut body).await.is_none());
+
+        let (tx, rx) = mpsc::channel::<Item>();
+        let mut res = JsonStream::array(rx).into_response();
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/json"
+        );
+        let mut body = res.take_body();
+        tx.send(Item { id: 1 }).unwrap();
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, "[{\"id\":1}");
+        tx.send(Item { id: 2 }).unwrap();
+        drop(tx);
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, ",{\"id\":2}]");
+        assert!(stream_recv(&mut body).await.is_none());
+
+        let items = stream::iter(Vec::<Item>::new());
+        let mut body = JsonStream::array(items).into_response().take_body();
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, "[]");
+        assert!(stream_recv(&mut body).await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_json_stream_error() {
+        // json object keys must be strings
+        let mut map = std::collections::HashMap::new();
+        map.insert(vec![1], 1);
+        let items = stream::iter(vec![map]);
+        let mut body = JsonStream::ndjson(items).into_response().take_body();
+        assert!(stream_recv(&mut body).await.unwrap().is_err());
+        assert!(stream_recv(&mut body).await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_ndjson() {
+        let (req, mut pl) =
+            TestRequest::with_header(header::CONTENT_TYPE, "application/x-ndjson")
+                .set_payload(Bytes::from_static(
+                    b"{\"id\":1}\n\n{\"id\":2}\r\n{\"id\":3}",
+                ))
+                .to_http_parts();
+        let mut items = from_request::<NdJson<Item>>(&req, &mut pl).await.unwrap();
+        assert_eq!(items.recv().await.unwrap().unwrap(), Item { id: 1 });
+        assert_eq!(items.recv().await.unwrap().unwrap(), Item { id: 2 });
+        assert_eq!(items.recv().await.unwrap().unwrap(), Item { id: 3 });
+        assert!(items.recv().await.is_none());
+
+        let (req, mut pl) =
+            TestRequest::with_header(header::CONTENT_TYPE, "application/jsonl")
+                .set_payload(Bytes::from_static(b"{\"id\":1}\n{\"id\":\n{\"id\":3}\n"))
+                .to_http_parts();
+        let mut items = from_request::<NdJson<Item>>(&req, &mut pl).await.unwrap();
+        assert!(items.recv().await.unwrap().is_ok());
+        assert!(matches!(
+            items.recv().await.unwrap(),
+            Err(JsonPayloadError::Deserialize(_))
+        ));
+        assert!(items.recv().await.is_none());
+
+        let (req, mut pl) =
+            TestRequest::with_header(header::CONTENT_TYPE, "application/json")
+                .to_http_parts();
+        let res = from_request::<NdJson<Item>>(&req, &mut pl).await;
+        assert!(matches!(res, Err(JsonPayloadError::ContentType)));
+
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_TYPE, "text/plain")
+            .state(NdJsonConfig::default().content_type(|mime| mime == mime::TEXT_PLAIN))
+            .to_http_parts();
+        assert!(from_request::<NdJson<Item>>(&req, &mut pl).await.is_ok());
+    }
+
+    #[crate::rt_test]
+    async fn test_ndjson_limit() {
+        let (req, mut pl) =
+            TestRequest::with_header(header::CONTENT_TYPE, "application/x-ndjson")
+                .set_payload(Bytes::from_static(b"{\"id\":1}\n{\"id\":1000000}\n"))
+                .state(NdJsonConfig::default().limit(10))
+                .to_http_parts();
+        let mut items = from_request::<NdJson<Item>>(&req, &mut pl).await.unwrap();
+        assert!(items.recv().await.unwrap().is_ok());
+        assert!(matches!(
+            items.recv().await.unwrap(),
+            Err(JsonPayloadError::Overflow)
+        ));
+        assert!(items.recv().await.is_none());
+
+        // item without newline
+        let (req, mut pl) =
+            TestRequest::with_header(header::CONTENT_TYPE, "application/x-ndjson")
+                .set_payload(Bytes::from_static(b"{\"id\":1000000}"))
+                .state(NdJsonConfig::default().limit(10))
+                .to_http_parts();
+        let mut items = from_request::<NdJson<Item>>(&req, &mut pl).await.unwrap();
+        assert!(matches!(
+            items.recv().await.unwrap(),
+            Err(JsonPayloadError::Overflow)
+        ));
+
+        // total payload size
+        let (req, mut pl) =
+            TestRequest::with_header(header::CONTENT_TYPE, "application/x-ndjson")
+                .set_payload(Bytes::from_static(b"{\"id\":1}\n{\"id\":2}\n"))
+                .state(NdJsonConfig::default().max_size(10))
+                .to_http_parts();
+        let mut items = from_request::<NdJson<Item>>(&req, &mut pl).await.unwrap();
+        assert!(matches!(
+            items.recv().await.unwrap(),
+            Err(JsonPayloadError::Overflow)
+        ));
+    }
+
+    #[crate::rt_test]
+    async fn test_ndjson_chunks() {
+        let (tx, rx) = mpsc::channel();
+        let (req, _) =
+            TestRequest::with_header(header::CONTENT_TYPE, "application/x-ndjson")
+                .to_http_parts();
+        let mut pl = Payload::from_stream(rx);
+        let mut items = from_request::<NdJson<Item>>(&req, &mut pl).await.unwrap();
+
+        tx.send(Ok::<_, crate::http::error::PayloadError>(
+            Bytes::from_static(b"{\"id\""),
+        ))
+        .unwrap();
+        tx.send(Ok(Bytes::from_static(b":1}"))).unwrap();
+        tx.send(Ok(Bytes::from_static(b"\n{\"id\":2}\n{"))).unwrap();
+        assert_eq!(items.recv().await.unwrap().unwrap(), Item { id: 1 });
+        assert_eq!(items.recv().await.unwrap().unwrap(), Item { id: 2 });
+        tx.send(Ok(Bytes::from_static(b"\"id\":3}"))).unwrap();
+        drop(tx);
+        assert_eq!(items.recv().await.unwrap().unwrap(), Item { id: 3 });
+        assert!(items.recv().await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_service() {
+        let srv = init_service(App::new().service(web::resource("/").to(
+            |mut items: NdJson<Item>| async move {
+                let mut ids = Vec::new();
+                while let Some(item) = items.recv().await {
+                    ids.push(item?.id);
+                }
+                Ok::<_, JsonPayloadError>(JsonStream::array(stream::iter(ids)))
+            },
+        )))
+        .await;
+
+        let req = TestRequest::with_header(header::CONTENT_TYPE, "application/x-ndjson")
+            .set_payload(Bytes::from_static(b"{\"id\":1}\n{\"id\":2}\n"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, Bytes::from_static(b"[1,2]"));
+
+        let req = TestRequest::with_header(header::CONTENT_TYPE, "application/x-ndjson")
+            .set_payload(Bytes::from_static(b"{\"id\":1}\n{\"id\"\n"))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+    }
+}
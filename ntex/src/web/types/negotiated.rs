@@ -0,0 +1,257 @@
+//! Content negotiation responder
+use std::{error::Error, fmt};
+
+use mime::Mime;
+use serde::Serialize;
+
+use crate::http::header::{self, HeaderValue, TypedHeader};
+use crate::http::{Response, StatusCode};
+use crate::util::Bytes;
+use crate::web::error::{ErrorContainer, ErrorRenderer, NegotiationError};
+use crate::web::responder::{Ready, Responder};
+use crate::web::HttpRequest;
+
+type Serializer<T> = Box<dyn Fn(&T) -> Result<Bytes, Box<dyn Error>>>;
+
+/// Responder that serializes value according to request's `Accept` header.
+///
+/// By default value could be serialized to json and to urlencoded form,
+/// json is used if both are equally acceptable or if request does not
+/// contain `Accept` header. Custom serializers are added with
+/// `Negotiated::format()`.
+///
+/// If none of available formats is acceptable, `NegotiationError` is
+/// returned and `406 Not Acceptable` response is generated by default
+/// error renderer.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, types::Negotiated};
+///
+/// #[derive(serde::Serialize)]
+/// struct Info {
+///     username: String,
+/// }
+///
+/// async fn index() -> Negotiated<Info> {
+///     let info = Info { username: "test".to_string() };
+///     Negotiated::new(info).format(mime::TEXT_PLAIN, |info: &Info| {
+///         Ok::<_, std::io::Error>(info.username.clone().into())
+///     })
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/index.html").route(web::get().to(index)));
+/// }
+/// ```
+pub struct Negotiated<T> {
+    value: T,
+    formats: Vec<(Mime, Serializer<T>)>,
+}
+
+impl<T: Serialize + 'static> Negotiated<T> {
+    /// Create responder with json and urlencoded form serializers.
+    pub fn new(value: T) -> Self {
+        Negotiated {
+            value,
+            formats: Vec::new(),
+        }
+        .format(mime::APPLICATION_JSON, |value: &T| {
+            serde_json::to_vec(value).map(Bytes::from)
+        })
+        .format(mime::APPLICATION_WWW_FORM_URLENCODED, |value: &T| {
+            serde_urlencoded::to_string(value).map(Bytes::from)
+        })
+    }
+}
+
+impl<T> Negotiated<T> {
+    /// Add serializer for media type.
+    ///
+    /// Serializer replaces previously added serializer for the same
+    /// media type. Equally acceptable formats are selected in order
+    /// of addition.
+    pub fn format<F, E>(mut self, mime: Mime, f: F) -> Self
+    where
+        F: Fn(&T) -> Result<Bytes, E> + 'static,
+        E: Error + 'static,
+    {
+        let f: Serializer<T> = Box::new(move |value| f(value).map_err(|e| e.into()));
+        if let Some(item) = self.formats.iter_mut().find(|(m, _)| *m == mime) {
+            item.1 = f;
+        } else {
+            self.formats.push((mime, f));
+        }
+        self
+    }
+
+    /// Deconstruct to a inner value
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    fn serialize(&self, req: &HttpRequest) -> Result<Response, NegotiationError> {
+        let mut values = req.headers().get_all(header::ACCEPT).peekable();
+        let accept = if values.peek().is_none() {
+            None
+        } else {
+            header::Accept::decode(values).ok()
+        };
+
+        let format = match accept {
+            Some(accept) => {
+                let mime = accept
+                    .negotiate(self.formats.iter().map(|(mime, _)| mime))
+                    .ok_or(NegotiationError::NotAcceptable)?;
+                self.formats.iter().find(|(m, _)| m == mime)
+            }
+            None => self.formats.first(),
+        };
+        let (mime, f) = format.ok_or(NegotiationError::NotAcceptable)?;
+        let body = f(&self.value).map_err(NegotiationError::Serialize)?;
+
+        Ok(Response::build(StatusCode::OK)
+            .content_type(mime.as_ref())
+            .header(header::VARY, HeaderValue::from_static("accept"))
+            .body(body))
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Negotiated<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Negotiated")
+            .field("value", &self.value)
+            .field(
+                "formats",
+                &self.formats.iter().map(|(m, _)| m).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
+
+impl<T, Err: ErrorRenderer> Responder<Err> for Negotiated<T>
+where
+    Err::Container: From<NegotiationError>,
+{
+    type Error = NegotiationError;
+    type Future = Ready<Response>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        match self.serialize(req) {
+            Ok(res) => res.into(),
+            Err(e) => {
+                log::debug!("Content negotiation failed: {}", e);
+                let mut res = Err::Container::from(e).error_response(req);
+                // error response depends on `Accept` header as well
+                res.headers_mut()
+                    .append(header::VARY, HeaderValue::from_static("accept"));
+                res.into()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, App, DefaultError};
+
+    #[derive(Serialize, Debug)]
+    struct Info {
+        name: String,
+    }
+
+    fn info() -> Negotiated<Info> {
+        Negotiated::new(Info {
+            name: "test".to_string(),
+        })
+    }
+
+    async fn respond(
+        res: Negotiated<Info>,
+        accept: Option<&str>,
+    ) -> (StatusCode, Option<String>, Bytes) {
+        let req = match accept {
+            Some(accept) => TestRequest::with_header(header::ACCEPT, accept),
+            None => TestRequest::default(),
+        }
+        .to_http_request();
+        let res = Responder::<DefaultError>::respond_to(res, &req).await;
+        let ct = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .map(|v| v.to_str().unwrap().to_string());
+        let body = Bytes::copy_from_slice(res.body().get_ref());
+        (res.status(), ct, body)
+    }
+
+    #[crate::rt_test]
+    async fn test_negotiated() {
+        let (status, ct, body) = respond(info(), None).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ct.unwrap(), "application/json");
+        assert_eq!(body, Bytes::from_static(b"{\"name\":\"test\"}"));
+
+        let (_, ct, body) = respond(info(), Some("*/*")).await;
+        assert_eq!(ct.unwrap(), "application/json");
+        assert_eq!(body, Bytes::from_static(b"{\"name\":\"test\"}"));
+
+        let accept = "application/json;q=0.5, application/x-www-form-urlencoded";
+        let (_, ct, body) = respond(info(), Some(accept)).await;
+        assert_eq!(ct.unwrap(), "application/x-www-form-urlencoded");
+        assert_eq!(body, Bytes::from_static(b"name=test"));
+
+        let (status, _, _) = respond(info(), Some("text/html, image/*")).await;
+        assert_eq!(status, StatusCode::NOT_ACCEPTABLE);
+
+        // malformed header is ignored
+        let (status, ct, _) = respond(info(), Some("json")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ct.unwrap(), "application/json");
+    }
+
+    #[crate::rt_test]
+    async fn test_custom_format() {
+        let res = || {
+            info()
+                .format(mime::TEXT_PLAIN, |info: &Info| {
+                    Ok::<_, std::io::Error>(Bytes::from(info.name.clone()))
+                })
+                .format(mime::APPLICATION_JSON, |_: &Info| Err(fmt::Error))
+        };
+
+        let (status, ct, body) = respond(res(), Some("text/*, */*;q=0.1")).await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(ct.unwrap(), "text/plain");
+        assert_eq!(body, Bytes::from_static(b"test"));
+
+        let (status, _, _) = respond(res(), Some("application/json")).await;
+        assert_eq!(status, StatusCode::INTERNAL_SERVER_ERROR);
+
+        assert_eq!(res().into_inner().name, "test");
+        assert!(format!("{:?}", res()).contains("text/plain"));
+    }
+
+    #[crate::rt_test]
+    async fn test_service() {
+        let srv =
+            init_service(App::new().service(web::resource("/").to(|| async { info() })))
+                .await;
+
+        let req =
+            TestRequest::with_header(header::ACCEPT, "application/x-www-form-urlencoded")
+                .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept");
+        assert_eq!(read_body(res).await, Bytes::from_static(b"name=test"));
+
+        let req = TestRequest::with_header(header::ACCEPT, "text/html").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_ACCEPTABLE);
+        assert_eq!(res.headers().get(header::VARY).unwrap(), "accept");
+    }
+}
@@ -160,8 +160,9 @@ impl<Err: ErrorRenderer> FromRequest<Err> for Bytes {
             return Either::Right(Ready::Err(e));
         }
 
-        let limit = cfg.limit;
-        let fut = HttpMessageBody::new(req, payload).limit(limit);
+        let fut = HttpMessageBody::new(req, payload)
+            .limit(cfg.limit)
+            .max_ratio(cfg.max_ratio);
         Either::Left(Box::pin(async move { fut.await }))
     }
 }
@@ -220,8 +221,9 @@ impl<Err: ErrorRenderer> FromRequest<Err> for String {
             Ok(enc) => enc,
             Err(e) => return Either::Right(Ready::Err(PayloadError::from(e))),
         };
-        let limit = cfg.limit;
-        let fut = HttpMessageBody::new(req, payload).limit(limit);
+        let fut = HttpMessageBody::new(req, payload)
+            .limit(cfg.limit)
+            .max_ratio(cfg.max_ratio);
 
         Either::Left(Box::pin(async move {
             let body = fut.await?;
@@ -243,6 +245,7 @@ impl<Err: ErrorRenderer> FromRequest<Err> for String {
 #[derive(Clone, Debug)]
 pub struct PayloadConfig {
     limit: usize,
+    max_ratio: usize,
     mimetype: Option<Mime>,
 }
 
@@ -261,6 +264,16 @@ impl PayloadConfig {
         self
     }
 
+    /// Set max ratio between decoded and encoded payload sizes.
+    ///
+    /// Compressed payload is rejected if decoded payload exceeds 64Kb
+    /// and is more than `ratio` times larger than encoded payload.
+    /// By default ratio is not checked.
+    pub fn max_ratio(mut self, ratio: usize) -> Self {
+        self.max_ratio = ratio;
+        self
+    }
+
     /// Set required mime-type of the request. By default mime type is not
     /// enforced.
     pub fn mimetype(mut self, mt: Mime) -> Self {
@@ -295,6 +308,7 @@ impl Default for PayloadConfig {
     fn default() -> Self {
         PayloadConfig {
             limit: 262_144,
+            max_ratio: 0,
             mimetype: None,
         }
     }
@@ -361,6 +375,16 @@ impl HttpMessageBody {
         self
     }
 
+    /// Set max ratio between decoded and encoded payload sizes
+    #[cfg_attr(not(feature = "compress"), allow(unused_mut, unused_variables))]
+    fn max_ratio(mut self, ratio: usize) -> Self {
+        #[cfg(feature = "compress")]
+        {
+            self.stream = self.stream.take().map(|stream| stream.max_ratio(ratio));
+        }
+        self
+    }
+
     fn err(e: PayloadError) -> Self {
         HttpMessageBody {
             stream: None,
@@ -392,6 +416,9 @@ impl Future for HttpMessageBody {
 
         // future
         let limit = self.limit;
+        #[cfg(feature = "compress")]
+        let mut stream = self.stream.take().unwrap().limit(limit);
+        #[cfg(not(feature = "compress"))]
         let mut stream = self.stream.take().unwrap();
         self.fut = Some(Box::pin(async move {
             let mut body = BytesMut::with_capacity(8192);
@@ -531,4 +558,57 @@ mod tests {
             _ => unreachable!("error"),
         }
     }
+
+    #[cfg(feature = "compress")]
+    #[crate::rt_test]
+    async fn test_message_body_decoded_limit() {
+        use std::io::Write;
+
+        use crate::web::{DefaultError, WebResponseError};
+
+        let mut enc =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        enc.write_all(&[b'x'; 4096]).unwrap();
+        let data = enc.finish().unwrap();
+
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_ENCODING, "gzip")
+            .set_payload(data)
+            .to_http_parts();
+        let err = HttpMessageBody::new(&req, &mut pl)
+            .limit(1024)
+            .await
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            PayloadError::Payload(error::PayloadError::DecodedOverflow)
+        ));
+        assert_eq!(
+            WebResponseError::<DefaultError>::status_code(&err),
+            crate::http::StatusCode::PAYLOAD_TOO_LARGE
+        );
+
+        // expansion ratio
+        let mut enc =
+            flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::best());
+        enc.write_all(&[0; 1_048_576]).unwrap();
+        let data = Bytes::from(enc.finish().unwrap());
+
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_ENCODING, "gzip")
+            .set_payload(data.clone())
+            .state(PayloadConfig::new(2_097_152))
+            .to_http_parts();
+        let body = from_request::<Bytes>(&req, &mut pl).await.unwrap();
+        assert_eq!(body.len(), 1_048_576);
+
+        let (req, mut pl) = TestRequest::with_header(header::CONTENT_ENCODING, "gzip")
+            .set_payload(data)
+            .state(PayloadConfig::new(2_097_152).max_ratio(200))
+            .to_http_parts();
+        let err = from_request::<Bytes>(&req, &mut pl).await.err().unwrap();
+        assert!(matches!(
+            err,
+            PayloadError::Payload(error::PayloadError::DecodedOverflow)
+        ));
+    }
 }
@@ -0,0 +1,552 @@
+//! Range requests support
+use std::collections::VecDeque;
+use std::task::{Context, Poll};
+use std::{convert::TryFrom, error::Error, fs::File, io, time::SystemTime};
+
+use nanorand::{Rng, WyRand};
+
+use crate::http::body::{Body, BodySize, MessageBody, SizedStream};
+use crate::http::error::HttpError;
+use crate::http::header::{self, AcceptRanges, ContentRange, HeaderValue, IfRange, Range};
+use crate::http::{Method, StatusCode};
+use crate::util::{Bytes, BytesMut};
+use crate::web::error::ErrorRenderer;
+use crate::web::files::ChunkedReadFile;
+use crate::web::responder::{Ready, Responder};
+use crate::web::{HttpRequest, HttpResponse};
+
+/// Source of the data for range responses
+pub trait RangeSource {
+    /// Size of the source in bytes.
+    ///
+    /// `None` means size is unknown and ranges are not supported.
+    fn size(&self) -> Option<u64>;
+
+    /// Body for `length` bytes starting at `start` position
+    fn range(&self, start: u64, length: u64) -> io::Result<Body>;
+
+    /// Complete body
+    fn into_body(self) -> Body;
+}
+
+impl RangeSource for Bytes {
+    fn size(&self) -> Option<u64> {
+        Some(self.len() as u64)
+    }
+
+    fn range(&self, start: u64, length: u64) -> io::Result<Body> {
+        Ok(Body::Bytes(
+            self.slice(start as usize..(start + length) as usize),
+        ))
+    }
+
+    fn into_body(self) -> Body {
+        Body::Bytes(self)
+    }
+}
+
+impl RangeSource for Body {
+    fn size(&self) -> Option<u64> {
+        match self {
+            Body::Bytes(ref bytes) => Some(bytes.len() as u64),
+            Body::Empty => Some(0),
+            Body::None | Body::Message(_) => None,
+        }
+    }
+
+    fn range(&self, start: u64, length: u64) -> io::Result<Body> {
+        match self {
+            Body::Bytes(ref bytes) => bytes.range(start, length),
+            _ => Err(io::ErrorKind::InvalidInput.into()),
+        }
+    }
+
+    fn into_body(self) -> Body {
+        self
+    }
+}
+
+/// Ranges are read on a blocking thread pool
+impl RangeSource for File {
+    fn size(&self) -> Option<u64> {
+        self.metadata().ok().map(|md| md.len())
+    }
+
+    fn range(&self, start: u64, length: u64) -> io::Result<Body> {
+        let file = self.try_clone()?;
+        Ok(SizedStream::new(length, ChunkedReadFile::new(file, start, length)).into())
+    }
+
+    fn into_body(self) -> Body {
+        let size = self.size().unwrap_or(0);
+        SizedStream::new(size, ChunkedReadFile::new(self, 0, size)).into()
+    }
+}
+
+/// Range-aware responder
+///
+/// Responder handles `Range` and `If-Range` request headers. Single range
+/// is returned as `206 Partial Content` response, multiple ranges are
+/// returned as `multipart/byteranges` response, `416 Range Not Satisfiable`
+/// is returned if none of the ranges could be satisfied. `Range` header is
+/// ignored and complete body is returned if header contains more than 32
+/// ranges or if ranges request more bytes than size of the source.
+///
+/// ```rust
+/// use ntex::web::{self, types::Ranged, App};
+/// use ntex::util::Bytes;
+///
+/// async fn index() -> Ranged<Bytes> {
+///     Ranged::new(Bytes::from_static(b"Hello world!"))
+///         .content_type("text/plain")
+///         .etag("\"v1\"")
+/// }
+///
+/// fn main() {
+///     let app = App::new().service(web::resource("/").to(index));
+/// }
+/// ```
+pub struct Ranged<T> {
+    source: T,
+    content_type: Option<HeaderValue>,
+    etag: Option<String>,
+    last_modified: Option<SystemTime>,
+}
+
+impl<T: RangeSource> Ranged<T> {
+    /// Create range-aware responder for the source
+    pub fn new(source: T) -> Self {
+        Ranged {
+            source,
+            content_type: None,
+            etag: None,
+            last_modified: None,
+        }
+    }
+
+    /// Set content type of the source.
+    ///
+    /// Panics if value is not valid header value.
+    pub fn content_type<V>(mut self, value: V) -> Self
+    where
+        HeaderValue: TryFrom<V>,
+        <HeaderValue as TryFrom<V>>::Error: Into<HttpError>,
+    {
+        match HeaderValue::try_from(value) {
+            Ok(value) => self.content_type = Some(value),
+            Err(_) => panic!("Cannot create content type header value"),
+        }
+        self
+    }
+
+    /// Set strong entity tag of the source.
+    ///
+    /// Entity tag is used for `If-Range` validation and is sent as `ETag`
+    /// header. Value must include quotes.
+    pub fn etag<S: Into<String>>(mut self, etag: S) -> Self {
+        self.etag = Some(etag.into());
+        self
+    }
+
+    /// Set modification time of the source.
+    ///
+    /// Modification time is used for `If-Range` validation and is sent as
+    /// `Last-Modified` header.
+    pub fn last_modified(mut self, time: SystemTime) -> Self {
+        self.last_modified = Some(time);
+        self
+    }
+
+    /// Build response for the request
+    pub fn into_response(self, req: &HttpRequest) -> HttpResponse {
+        let mut res = HttpResponse::Ok();
+        if let Some(ref etag) = self.etag {
+            res.header(header::ETAG, etag.as_str());
+        }
+        if let Some(modified) = self.last_modified {
+            res.header(
+                header::LAST_MODIFIED,
+                httpdate::HttpDate::from(modified).to_string(),
+            );
+        }
+
+        let size = if let Some(size) = self.source.size() {
+            size
+        } else {
+            if let Some(ct) = self.content_type {
+                res.header(header::CONTENT_TYPE, ct);
+            }
+            return res.body(self.source.into_body());
+        };
+        res.header(header::ACCEPT_RANGES, AcceptRanges::Bytes.as_str());
+
+        let ranges = match self
+            .requested_ranges(req)
+            .map(|range| range.satisfiable_ranges(size))
+        {
+            // overlapping ranges that request more bytes than whole
+            // representation are ignored
+            Some(ranges) if requested_size(&ranges) <= size => merge_ranges(ranges),
+            _ => {
+                if let Some(ct) = self.content_type {
+                    res.header(header::CONTENT_TYPE, ct);
+                }
+                return res.body(self.source.into_body());
+            }
+        };
+
+        if ranges.is_empty() {
+            return res
+                .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                .header(
+                    header::CONTENT_RANGE,
+                    ContentRange::unsatisfied(size).to_string(),
+                )
+                .finish();
+        }
+        res.status(StatusCode::PARTIAL_CONTENT);
+
+        if ranges.len() == 1 {
+            let (start, end) = ranges[0];
+            if let Some(ct) = self.content_type {
+                res.header(header::CONTENT_TYPE, ct);
+            }
+            return match self.source.range(start, end - start + 1) {
+                Ok(body) => res
+                    .header(
+                        header::CONTENT_RANGE,
+                        ContentRange::bytes(start, end, size).to_string(),
+                    )
+                    .body(body),
+                Err(e) => range_error(e),
+            };
+        }
+
+        // multipart/byteranges response
+        let boundary = format!("{:016x}", WyRand::new().generate::<u64>());
+        let mut body = MultipartRanges {
+            size: 0,
+            parts: VecDeque::with_capacity(ranges.len() * 2 + 1),
+        };
+        for (start, end) in ranges {
+            let mut part = BytesMut::new();
+            part.extend_from_slice(b"\r\n--");
+            part.extend_from_slice(boundary.as_bytes());
+            if let Some(ref ct) = self.content_type {
+                part.extend_from_slice(b"\r\nContent-Type: ");
+                part.extend_from_slice(ct.as_bytes());
+            }
+            part.extend_from_slice(b"\r\nContent-Range: ");
+            part.extend_from_slice(
+                ContentRange::bytes(start, end, size).to_string().as_bytes(),
+            );
+            part.extend_from_slice(b"\r\n\r\n");
+            body.push(Body::Bytes(part.freeze()), 0);
+
+            match self.source.range(start, end - start + 1) {
+                Ok(data) => body.push(data, end - start + 1),
+                Err(e) => return range_error(e),
+            }
+        }
+        let end = format!("\r\n--{}--\r\n", boundary);
+        body.push(Body::Bytes(Bytes::from(end)), 0);
+
+        res.content_type(format!("multipart/byteranges; boundary={}", boundary))
+            .body(Body::from_message(body))
+    }
+
+    /// Parse `Range` header, ranges are ignored if `If-Range` does not match
+    fn requested_ranges(&self, req: &HttpRequest) -> Option<Range> {
+        if req.method() != Method::GET && req.method() != Method::HEAD {
+            return None;
+        }
+        let range = Range::try_from(req.headers().get(&header::RANGE)?.clone()).ok()?;
+
+        if let Some(value) = req.headers().get(&header::IF_RANGE) {
+            let matched = IfRange::try_from(value.clone())
+                .map(|if_range| if_range.is_match(self.etag.as_deref(), self.last_modified))
+                .unwrap_or(false);
+            if !matched {
+                return None;
+            }
+        }
+        Some(range)
+    }
+}
+
+/// Total number of bytes requested by ranges
+fn requested_size(ranges: &[(u64, u64)]) -> u64 {
+    ranges.iter().fold(0, |total: u64, (start, end)| {
+        total.saturating_add(end - start + 1)
+    })
+}
+
+/// Sort and merge overlapping or adjacent ranges
+fn merge_ranges(mut ranges: Vec<(u64, u64)>) -> Vec<(u64, u64)> {
+    ranges.sort_unstable();
+
+    let mut merged: Vec<(u64, u64)> = Vec::with_capacity(ranges.len());
+    for (start, end) in ranges {
+        match merged.last_mut() {
+            Some(last) if start <= last.1 + 1 => last.1 = last.1.max(end),
+            _ => merged.push((start, end)),
+        }
+    }
+    merged
+}
+
+fn range_error(e: io::Error) -> HttpResponse {
+    log::error!("Cannot read range of the source: {}", e);
+    HttpResponse::InternalServerError().finish()
+}
+
+impl<T: RangeSource, Err: ErrorRenderer> Responder<Err> for Ranged<T> {
+    type Error = Err::Container;
+    type Future = Ready<HttpResponse>;
+
+    fn respond_to(self, req: &HttpRequest) -> Self::Future {
+        self.into_response(req).into()
+    }
+}
+
+/// Body of `multipart/byteranges` response
+struct MultipartRanges {
+    size: u64,
+    parts: VecDeque<Body>,
+}
+
+impl MultipartRanges {
+    fn push(&mut self, body: Body, size: u64) {
+        self.size += match MessageBody::size(&body) {
+            BodySize::Sized(size) => size,
+            _ => size,
+        };
+        self.parts.push_back(body);
+    }
+}
+
+impl MessageBody for MultipartRanges {
+    fn size(&self) -> BodySize {
+        BodySize::Sized(self.size)
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        loop {
+            let part = if let Some(part) = self.parts.front_mut() {
+                part
+            } else {
+                return Poll::Ready(None);
+            };
+
+            match part.poll_next_chunk(cx) {
+                Poll::Ready(None) => {
+                    self.parts.pop_front();
+                }
+                res => return res,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::web::test::{load_stream, respond_to, TestRequest};
+
+    async fn body(mut res: HttpResponse) -> Bytes {
+        load_stream(res.take_body()).await.unwrap()
+    }
+
+    fn ranged() -> Ranged<Bytes> {
+        Ranged::new(Bytes::from_static(b"0123456789abcdefghij"))
+    }
+
+    #[crate::rt_test]
+    async fn test_full() {
+        let req = TestRequest::default().to_http_request();
+        let res = respond_to(ranged().content_type("text/plain"), &req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.headers().get(header::ACCEPT_RANGES).unwrap(), "bytes");
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        assert_eq!(body(res).await, "0123456789abcdefghij");
+
+        // ranges are supported only for GET and HEAD requests
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-1")
+            .method(Method::POST)
+            .to_http_request();
+        let res = ranged().into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // malformed range is ignored
+        let req = TestRequest::with_header(header::RANGE, "bytes=5-1").to_http_request();
+        let res = ranged().into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+
+        // unsized body
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-1").to_http_request();
+        let res = Ranged::new(Body::None).into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::ACCEPT_RANGES));
+    }
+
+    #[crate::rt_test]
+    async fn test_single_range() {
+        let req = TestRequest::with_header(header::RANGE, "bytes=2-5").to_http_request();
+        let res = ranged().content_type("text/plain").into_response(&req);
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 2-5/20"
+        );
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/plain"
+        );
+        assert_eq!(body(res).await, "2345");
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=-3").to_http_request();
+        let res = Ranged::new(Body::from("0123456789")).into_response(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 7-9/10"
+        );
+        assert_eq!(body(res).await, "789");
+
+        // overlapping ranges are merged
+        let req = TestRequest::with_header(header::RANGE, "bytes=5-7, 0-3, 4-4")
+            .to_http_request();
+        let res = ranged().into_response(&req);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes 0-7/20"
+        );
+        assert_eq!(body(res).await, "01234567");
+    }
+
+    #[crate::rt_test]
+    async fn test_multiple_ranges() {
+        let req =
+            TestRequest::with_header(header::RANGE, "bytes=0-1, 10-11").to_http_request();
+        let res = ranged().content_type("text/plain").into_response(&req);
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        let ct = res
+            .headers()
+            .get(header::CONTENT_TYPE)
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .to_string();
+        let boundary = ct
+            .strip_prefix("multipart/byteranges; boundary=")
+            .unwrap()
+            .to_string();
+        let size = res.body().size();
+        let data = body(res).await;
+        assert_eq!(size, BodySize::Sized(data.len() as u64));
+        assert_eq!(
+            data,
+            format!(
+                "\r\n--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 0-1/20\r\n\r\n01\
+                 \r\n--{0}\r\nContent-Type: text/plain\r\nContent-Range: bytes 10-11/20\r\n\r\nab\
+                 \r\n--{0}--\r\n",
+                boundary
+            )
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_ranges_limit() {
+        let ranges = (0..32)
+            .map(|i| format!("{0}-{0}", i % 20))
+            .collect::<Vec<_>>();
+        let req =
+            TestRequest::with_header(header::RANGE, format!("bytes={}", ranges.join(",")))
+                .to_http_request();
+        let res = ranged().into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body(res).await, "0123456789abcdefghij");
+
+        let ranges = (0..33).map(|i| format!("{0}-{0}", i)).collect::<Vec<_>>();
+        let req =
+            TestRequest::with_header(header::RANGE, format!("bytes={}", ranges.join(",")))
+                .to_http_request();
+        let res = Ranged::new(Bytes::from(vec![b'a'; 100])).into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(res.body().size(), BodySize::Sized(100));
+
+        // overlapping ranges
+        let req =
+            TestRequest::with_header(header::RANGE, "bytes=0-, 0-, 0-").to_http_request();
+        let res = ranged().into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert!(!res.headers().contains_key(header::CONTENT_RANGE));
+        assert_eq!(body(res).await, "0123456789abcdefghij");
+    }
+
+    #[crate::rt_test]
+    async fn test_not_satisfiable() {
+        let req = TestRequest::with_header(header::RANGE, "bytes=20-").to_http_request();
+        let res = ranged().into_response(&req);
+        assert_eq!(res.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(
+            res.headers().get(header::CONTENT_RANGE).unwrap(),
+            "bytes */20"
+        );
+    }
+
+    #[crate::rt_test]
+    async fn test_if_range() {
+        let modified = SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(784111777);
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-1")
+            .header(header::IF_RANGE, "\"v1\"")
+            .to_http_request();
+        let res = ranged().etag("\"v1\"").into_response(&req);
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(res.headers().get(header::ETAG).unwrap(), "\"v1\"");
+
+        let res = ranged().etag("\"v2\"").into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(body(res).await, "0123456789abcdefghij");
+
+        let req = TestRequest::with_header(header::RANGE, "bytes=0-1")
+            .header(header::IF_RANGE, "Sun, 06 Nov 1994 08:49:37 GMT")
+            .to_http_request();
+        let res = ranged().last_modified(modified).into_response(&req);
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(
+            res.headers().get(header::LAST_MODIFIED).unwrap(),
+            "Sun, 06 Nov 1994 08:49:37 GMT"
+        );
+        let res = ranged().into_response(&req);
+        assert_eq!(res.status(), StatusCode::OK);
+    }
+
+    #[crate::rt_test]
+    async fn test_file() {
+        let data = std::fs::read("Cargo.toml").unwrap();
+
+        let req =
+            TestRequest::with_header(header::RANGE, "bytes=0-4, 10-14").to_http_request();
+        let res = Ranged::new(File::open("Cargo.toml").unwrap()).into_response(&req);
+        assert_eq!(res.status(), StatusCode::PARTIAL_CONTENT);
+        let payload = body(res).await;
+        assert!(payload.ends_with(b"--\r\n"));
+        assert!(contains(&payload, &data[0..5]));
+        assert!(contains(&payload, &data[10..15]));
+
+        let req = TestRequest::default().to_http_request();
+        let res = Ranged::new(File::open("Cargo.toml").unwrap()).into_response(&req);
+        assert_eq!(body(res).await, data);
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|w| w == needle)
+    }
+}
@@ -0,0 +1,257 @@
+use std::task::{Context, Poll};
+use std::{error::Error, marker::PhantomData, pin::Pin};
+
+use crate::http::body::{Body, BodySize, MessageBody};
+use crate::http::header::{self, ContentEncoding, HeaderValue};
+use crate::http::{Response, StatusCode};
+use crate::sse::Event;
+use crate::time::{Interval, Millis};
+use crate::util::{Bytes, BytesMut, Stream};
+use crate::web::error::ErrorRenderer;
+use crate::web::responder::{Ready, Responder};
+use crate::web::{BodyEncoding, HttpRequest, HttpResponse};
+
+const MAX_CHUNK_SIZE: usize = 16_384;
+
+/// Server-sent events responder
+///
+/// Responder sends `text/event-stream` response, each item of the stream
+/// is encoded as separate event. Stream error terminates response.
+/// Compression is disabled for the response.
+///
+/// ```rust
+/// use futures_util::stream;
+/// use ntex::{sse::Event, time::Seconds, web};
+///
+/// async fn events() -> impl web::Responder {
+///     let events = stream::iter(vec![
+///         Ok::<_, std::io::Error>(Event::new("first").id("1")),
+///         Ok(Event::new("second").id("2").event("update")),
+///     ]);
+///     web::types::Sse::new(events).keep_alive(Seconds(15))
+/// }
+/// ```
+pub struct Sse<S> {
+    stream: S,
+    keep_alive: Option<Millis>,
+    retry: Option<Millis>,
+}
+
+impl<S, E> Sse<S>
+where
+    S: Stream<Item = Result<Event, E>> + Unpin + 'static,
+    E: Error + 'static,
+{
+    /// Create new responder from stream of events.
+    pub fn new(stream: S) -> Self {
+        Sse {
+            stream,
+            keep_alive: None,
+            retry: None,
+        }
+    }
+
+    /// Send keep-alive comment every `period`.
+    ///
+    /// Comments are ignored by clients but keep connection and
+    /// intermediate proxies alive. By default keep-alive is disabled.
+    pub fn keep_alive<T: Into<Millis>>(mut self, period: T) -> Self {
+        let period = period.into();
+        self.keep_alive = if period.0 == 0 { None } else { Some(period) };
+        self
+    }
+
+    /// Set client's reconnection time, sent before first event.
+    pub fn retry<T: Into<Millis>>(mut self, retry: T) -> Self {
+        self.retry = Some(retry.into());
+        self
+    }
+
+    /// Build response
+    pub fn into_response(self) -> HttpResponse {
+        let mut buf = BytesMut::new();
+        if let Some(retry) = self.retry {
+            buf.extend_from_slice(format!("retry: {}\n\n", retry.0).as_bytes());
+        }
+
+        Response::build(StatusCode::OK)
+            .header(
+                header::CONTENT_TYPE,
+                HeaderValue::from_static("text/event-stream"),
+            )
+            .header(header::CACHE_CONTROL, HeaderValue::from_static("no-cache"))
+            .encoding(ContentEncoding::Identity)
+            .body(Body::from_message(SseBody {
+                buf,
+                stream: self.stream,
+                interval: self.keep_alive.map(Interval::new),
+                eof: false,
+                _t: PhantomData,
+            }))
+    }
+}
+
+impl<S, E, Err> Responder<Err> for Sse<S>
+where
+    S: Stream<Item = Result<Event, E>> + Unpin + 'static,
+    E: Error + 'static,
+    Err: ErrorRenderer,
+{
+    type Error = Err::Container;
+    type Future = Ready<HttpResponse>;
+
+    fn respond_to(self, _: &HttpRequest) -> Self::Future {
+        self.into_response().into()
+    }
+}
+
+struct SseBody<S, E> {
+    buf: BytesMut,
+    stream: S,
+    interval: Option<Interval>,
+    eof: bool,
+    _t: PhantomData<E>,
+}
+
+impl<S, E> MessageBody for SseBody<S, E>
+where
+    S: Stream<Item = Result<Event, E>> + Unpin + 'static,
+    E: Error + 'static,
+{
+    fn size(&self) -> BodySize {
+        BodySize::Stream
+    }
+
+    fn poll_next_chunk(
+        &mut self,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Box<dyn Error>>>> {
+        // collect ready events
+        while !self.eof && self.buf.len() < MAX_CHUNK_SIZE {
+            match Pin::new(&mut self.stream).poll_next(cx) {
+                Poll::Ready(Some(Ok(event))) => event.encode(&mut self.buf),
+                Poll::Ready(Some(Err(err))) => {
+                    return Poll::Ready(Some(Err(Box::new(err))))
+                }
+                Poll::Ready(None) => self.eof = true,
+                Poll::Pending => break,
+            }
+        }
+
+        if !self.buf.is_empty() {
+            Poll::Ready(Some(Ok(self.buf.split().freeze())))
+        } else if self.eof {
+            Poll::Ready(None)
+        } else if let Some(Poll::Ready(_)) = self.interval.as_ref().map(|i| i.poll_tick(cx))
+        {
+            Poll::Ready(Some(Ok(Bytes::from_static(b":\n\n"))))
+        } else {
+            Poll::Pending
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io;
+
+    use super::*;
+    use crate::channel::mpsc;
+    use crate::util::stream_recv;
+    use crate::web::test::{respond_to, TestRequest};
+
+    #[crate::rt_test]
+    async fn test_sse() {
+        let (tx, rx) = mpsc::channel::<Result<Event, io::Error>>();
+        tx.send(Ok(Event::new("1").id("1"))).unwrap();
+        tx.send(Ok(Event::new("2").event("update"))).unwrap();
+
+        let req = TestRequest::default().to_http_request();
+        let mut res = respond_to(Sse::new(rx).retry(Millis(500)), &req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "text/event-stream"
+        );
+        assert_eq!(
+            res.headers().get(header::CACHE_CONTROL).unwrap(),
+            "no-cache"
+        );
+        assert_eq!(res.get_encoding(), Some(ContentEncoding::Identity));
+
+        let mut body = res.take_body();
+        assert_eq!(body.size(), BodySize::Stream);
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(
+            chunk,
+            "retry: 500\n\nid: 1\ndata: 1\n\nevent: update\ndata: 2\n\n"
+        );
+
+        tx.send(Ok(Event::new("3"))).unwrap();
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, "data: 3\n\n");
+
+        tx.send(Err(io::Error::new(io::ErrorKind::InvalidData, "err")))
+            .unwrap();
+        assert!(stream_recv(&mut body).await.unwrap().is_err());
+
+        let (tx, rx) = mpsc::channel::<Result<Event, io::Error>>();
+        let mut body = Sse::new(rx).into_response().take_body();
+        tx.send(Ok(Event::new("1"))).unwrap();
+        drop(tx);
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, "data: 1\n\n");
+        assert!(stream_recv(&mut body).await.is_none());
+    }
+
+    #[crate::rt_test]
+    async fn test_keep_alive() {
+        let (tx, rx) = mpsc::channel::<Result<Event, io::Error>>();
+        let mut body = Sse::new(rx)
+            .keep_alive(Millis(50))
+            .into_response()
+            .take_body();
+
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, ":\n\n");
+        tx.send(Ok(Event::new("1"))).unwrap();
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, "data: 1\n\n");
+        let chunk = stream_recv(&mut body).await.unwrap().unwrap();
+        assert_eq!(chunk, ":\n\n");
+    }
+
+    #[cfg(feature = "tokio")]
+    #[crate::rt_test]
+    async fn test_client_events() {
+        use crate::sse::SseError;
+        use crate::web::{self, test, App};
+
+        let srv = test::server(|| {
+            App::new()
+                .service(web::resource("/events").to(|| async {
+                    let events = futures_util::stream::iter(vec![
+                        Ok::<_, io::Error>(Event::new("first").id("1")),
+                        Ok(Event::new("second\nline").event("update")),
+                    ]);
+                    Sse::new(events)
+                }))
+                .service(web::resource("/").to(|| async { "text" }))
+        });
+
+        let mut res = srv.get("/events").send().await.unwrap();
+        let mut events = res.events();
+        let ev = stream_recv(&mut events).await.unwrap().unwrap();
+        assert_eq!(ev, Event::new("first").id("1"));
+        let ev = stream_recv(&mut events).await.unwrap().unwrap();
+        assert_eq!(ev, Event::new("second\nline").id("1").event("update"));
+        assert!(stream_recv(&mut events).await.is_none());
+
+        let mut res = srv.get("/").send().await.unwrap();
+        let mut events = res.events();
+        assert!(matches!(
+            stream_recv(&mut events).await,
+            Some(Err(SseError::ContentType))
+        ));
+    }
+}
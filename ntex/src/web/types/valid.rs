@@ -0,0 +1,473 @@
+//! Validated extractor
+use std::{cell::RefCell, collections::HashMap, fmt, future::Future, ops, pin::Pin};
+
+use regex::Regex;
+
+use crate::http::Payload;
+use crate::web::error::{ErrorRenderer, ValidationError};
+use crate::web::{FromRequest, HttpRequest};
+
+/// Validation of extracted value.
+///
+/// Could be implemented manually or derived with `#[derive(Validate)]`.
+/// Derive supports following field constraints:
+///
+/// - `length(min = 1, max = 32)` - length of string in chars or length of collection
+/// - `range(min = 1, max = 100)` - range of value, bounds are inclusive
+/// - `regex = "^[a-z]+$"` - string matches regular expression
+/// - `email` - string is an email address
+///
+/// Constraints of `Option` fields are checked only if value is present.
+/// Field name in error is taken from `#[serde(rename = "..")]` attribute
+/// if it is specified.
+///
+/// ```rust
+/// use ntex::web::types::Validate;
+///
+/// #[derive(Validate, serde::Deserialize)]
+/// struct User {
+///     #[validate(length(min = 1, max = 32), regex = "^[a-z0-9_]+$")]
+///     username: String,
+///     #[validate(email)]
+///     email: Option<String>,
+///     #[validate(range(min = 18))]
+///     age: u32,
+/// }
+/// ```
+pub trait Validate {
+    /// Validate value, returns all failed constraints
+    fn validate(&self) -> Result<(), ValidationError>;
+}
+
+impl<T: Validate> Validate for Vec<T> {
+    fn validate(&self) -> Result<(), ValidationError> {
+        self.iter().try_for_each(Validate::validate)
+    }
+}
+
+/// Extractor wrapper that validates extracted value.
+///
+/// Wraps extractors that dereference to extracted value, like `Json`,
+/// `Form`, `Query` and `Path`. Value is validated after deserialization,
+/// if validation fails `ValidationError` is returned and
+/// `422 Unprocessable Entity` response with field errors is generated
+/// by default error renderer.
+///
+/// ## Example
+///
+/// ```rust
+/// use ntex::web::{self, types::{Json, Valid, Validate}};
+///
+/// #[derive(Validate, serde::Deserialize)]
+/// struct Info {
+///     #[validate(length(min = 1, max = 32))]
+///     username: String,
+/// }
+///
+/// async fn index(info: Valid<Json<Info>>) -> String {
+///     format!("Welcome {}!", info.username)
+/// }
+///
+/// fn main() {
+///     let app = web::App::new().service(
+///         web::resource("/index.html").route(web::post().to(index)));
+/// }
+/// ```
+pub struct Valid<T>(pub T);
+
+impl<T> Valid<T> {
+    /// Deconstruct to a inner value
+    pub fn into_inner(self) -> T {
+        self.0
+    }
+}
+
+impl<T> ops::Deref for Valid<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> ops::DerefMut for Valid<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for Valid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T: fmt::Display> fmt::Display for Valid<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl<T, Err> FromRequest<Err> for Valid<T>
+where
+    T: FromRequest<Err> + ops::Deref + 'static,
+    T::Target: Validate,
+    T::Future: 'static,
+    <T as FromRequest<Err>>::Error: Into<Err::Container>,
+    ValidationError: Into<Err::Container>,
+    Err: ErrorRenderer,
+{
+    type Error = Err::Container;
+    type Future = Pin<Box<dyn Future<Output = Result<Self, Self::Error>>>>;
+
+    #[inline]
+    fn from_request(req: &HttpRequest, payload: &mut Payload) -> Self::Future {
+        let fut = T::from_request(req, payload);
+        let path = req.path().to_string();
+        Box::pin(async move {
+            let value = fut.await.map_err(Into::into)?;
+            match value.validate() {
+                Ok(()) => Ok(Valid(value)),
+                Err(e) => {
+                    log::debug!("Validation failed: {}. Request path: {}", e, path);
+                    Err(e.into())
+                }
+            }
+        })
+    }
+}
+
+/// Value with length, used by `length` constraint
+pub trait ValidateLength {
+    /// Length of value
+    fn length(&self) -> usize;
+}
+
+impl ValidateLength for str {
+    fn length(&self) -> usize {
+        self.chars().count()
+    }
+}
+
+impl ValidateLength for String {
+    fn length(&self) -> usize {
+        self.as_str().length()
+    }
+}
+
+impl<T> ValidateLength for [T] {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T> ValidateLength for Vec<T> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<K, V, S> ValidateLength for HashMap<K, V, S> {
+    fn length(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<T: ValidateLength + ?Sized> ValidateLength for &T {
+    fn length(&self) -> usize {
+        (**self).length()
+    }
+}
+
+thread_local! {
+    static REGEX: RefCell<HashMap<&'static str, Option<Regex>>> = RefCell::new(HashMap::new());
+}
+
+/// Constraint checks, failed constraints are added as field errors
+impl ValidationError {
+    /// Check length of value, bounds are inclusive
+    pub fn check_length<T: ValidateLength + ?Sized>(
+        &mut self,
+        field: &'static str,
+        value: &T,
+        min: Option<usize>,
+        max: Option<usize>,
+    ) {
+        let len = value.length();
+        if min.map(|min| len < min).unwrap_or(false)
+            || max.map(|max| len > max).unwrap_or(false)
+        {
+            self.add(field, "length", bounds_message("length", min, max));
+        }
+    }
+
+    /// Check range of value, bounds are inclusive
+    pub fn check_range<T: PartialOrd + fmt::Display>(
+        &mut self,
+        field: &'static str,
+        value: &T,
+        min: Option<T>,
+        max: Option<T>,
+    ) {
+        if min.as_ref().map(|min| value < min).unwrap_or(false)
+            || max.as_ref().map(|max| value > max).unwrap_or(false)
+        {
+            self.add(field, "range", bounds_message("value", min, max));
+        }
+    }
+
+    /// Check if value matches regular expression.
+    ///
+    /// Compiled expressions are cached per thread. Invalid pattern does
+    /// not match any value, `Validate` derive rejects invalid patterns
+    /// at compile time.
+    pub fn check_regex(&mut self, field: &'static str, value: &str, pattern: &'static str) {
+        let matched = REGEX.with(|cache| {
+            cache
+                .borrow_mut()
+                .entry(pattern)
+                .or_insert_with(|| {
+                    Regex::new(pattern)
+                        .map_err(|e| {
+                            log::error!("Invalid regex pattern {:?}: {}", pattern, e)
+                        })
+                        .ok()
+                })
+                .as_ref()
+                .map(|re| re.is_match(value))
+                .unwrap_or(false)
+        });
+        if !matched {
+            self.add(field, "regex", "does not match required pattern");
+        }
+    }
+
+    /// Check if value is an email address
+    pub fn check_email(&mut self, field: &'static str, value: &str) {
+        if !is_email(value) {
+            self.add(field, "email", "must be a valid email address");
+        }
+    }
+}
+
+fn bounds_message<T: fmt::Display>(name: &str, min: Option<T>, max: Option<T>) -> String {
+    match (min, max) {
+        (Some(min), Some(max)) => format!("{} must be between {} and {}", name, min, max),
+        (Some(min), None) => format!("{} must be at least {}", name, min),
+        (None, Some(max)) => format!("{} must be at most {}", name, max),
+        (None, None) => format!("{} is invalid", name),
+    }
+}
+
+/// Check `local@domain` address, domain must be a valid host name
+fn is_email(value: &str) -> bool {
+    let (local, domain) = match value.rfind('@') {
+        Some(idx) => (&value[..idx], &value[idx + 1..]),
+        None => return false,
+    };
+    let local_valid = !local.is_empty()
+        && local.len() <= 64
+        && !local.starts_with('.')
+        && !local.ends_with('.')
+        && !local.contains("..")
+        && local
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "!#$%&'*+-/=?^_`{|}~.".contains(c));
+    let domain_valid = domain.len() <= 253
+        && domain.contains('.')
+        && domain.split('.').all(|label| {
+            !label.is_empty()
+                && label.len() <= 63
+                && !label.starts_with('-')
+                && !label.ends_with('-')
+                && label.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+        });
+    local_valid && domain_valid
+}
+
+#[cfg(test)]
+mod tests {
+    use serde::Deserialize;
+
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::util::Bytes;
+    use crate::web::error::{DefaultError, ProblemJson};
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::types::{Form, Json, Path, Query};
+    use crate::web::{self, App};
+
+    #[derive(Deserialize, Debug)]
+    struct Info {
+        name: String,
+        age: u32,
+    }
+
+    impl Validate for Info {
+        fn validate(&self) -> Result<(), ValidationError> {
+            let mut errors = ValidationError::new();
+            errors.check_length("name", &self.name, Some(1), Some(5));
+            errors.check_regex("name", &self.name, "^[a-z]*$");
+            errors.check_range("age", &self.age, Some(18), None);
+            errors.into_result()
+        }
+    }
+
+    #[test]
+    fn test_checks() {
+        let mut errors = ValidationError::new();
+        errors.check_length("a", "абв", Some(3), Some(3));
+        errors.check_length("b", &vec![1, 2], None, Some(1));
+        errors.check_range("c", &5.5, Some(0.0), Some(10.0));
+        errors.check_range("d", &-1, Some(0), Some(10));
+        errors.check_regex("e", "abc", "^a");
+        errors.check_regex("f", "abc", "^b");
+        errors.check_email("g", "user.name+tag@example.co.uk");
+        errors.check_email("h", "user@localhost");
+        errors.check_regex("i", "abc", "(");
+        errors.check_regex("i", "(", "(");
+        let fields: Vec<_> = errors.fields().iter().map(|e| &*e.field).collect();
+        assert_eq!(fields, vec!["b", "d", "f", "h", "i", "i"]);
+        assert_eq!(errors.fields()[0].message, "length must be at most 1");
+        assert_eq!(errors.fields()[1].code, "range");
+        assert_eq!(errors.fields()[1].message, "value must be between 0 and 10");
+
+        for email in &[
+            "",
+            "user",
+            "@example.com",
+            "user@",
+            "user@.com",
+            "user@-example.com",
+            "user@exa mple.com",
+            ".user@example.com",
+            "us..er@example.com",
+        ] {
+            assert!(!is_email(email), "{}", email);
+        }
+        assert!(is_email("a@b.c"));
+
+        assert!(vec![Info {
+            name: "abc".to_string(),
+            age: 20
+        }]
+        .validate()
+        .is_ok());
+    }
+
+    #[crate::rt_test]
+    async fn test_valid() {
+        let srv =
+            init_service(
+                App::new()
+                    .service(
+                        web::resource("/json").route(web::post().to(
+                            |info: Valid<Json<Info>>| async move { info.name.clone() },
+                        )),
+                    )
+                    .service(
+                        web::resource("/form").route(web::post().to(
+                            |info: Valid<Form<Info>>| async move { info.name.clone() },
+                        )),
+                    )
+                    .service(
+                        web::resource("/query").to(|info: Valid<Query<Info>>| async move {
+                            info.name.clone()
+                        }),
+                    )
+                    .service(web::resource("/path/{name}/{age}").to(
+                        |info: Valid<Path<Info>>| async move {
+                            info.into_inner().into_inner().name
+                        },
+                    )),
+            )
+            .await;
+
+        let req = TestRequest::with_uri("/json")
+            .method(crate::http::Method::POST)
+            .set_json(&serde_json::json!({"name": "abc", "age": 20}))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+        assert_eq!(read_body(res).await, Bytes::from_static(b"abc"));
+
+        let req = TestRequest::with_uri("/json")
+            .method(crate::http::Method::POST)
+            .set_json(&serde_json::json!({"name": "ABCDEF", "age": 10}))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body: serde_json::Value =
+            serde_json::from_slice(&read_body(res).await).unwrap();
+        assert_eq!(body["errors"].as_array().unwrap().len(), 3);
+        assert_eq!(body["errors"][0]["field"], "name");
+        assert_eq!(body["errors"][0]["code"], "length");
+        assert_eq!(body["errors"][2]["message"], "value must be at least 18");
+
+        // deserialization errors are reported by inner extractor
+        let req = TestRequest::with_uri("/json")
+            .method(crate::http::Method::POST)
+            .set_json(&serde_json::json!({"name": "abc"}))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::BAD_REQUEST);
+
+        let req = TestRequest::with_uri("/form")
+            .method(crate::http::Method::POST)
+            .set_form(&serde_json::json!({"name": "abc", "age": 17}))
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let req = TestRequest::with_uri("/query?name=abc&age=30").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::OK);
+
+        let req = TestRequest::with_uri("/path/abc/30").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"abc"));
+
+        let req = TestRequest::with_uri("/path/a1/30").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[crate::rt_test]
+    async fn test_valid_problem_json() {
+        let srv = init_service(
+            App::with(ProblemJson).service(
+                web::resource("/")
+                    .to(|info: Valid<Query<Info>>| async move { info.name.clone() }),
+            ),
+        )
+        .await;
+
+        let req = TestRequest::with_uri("/?name=&age=30").to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert_eq!(
+            res.headers().get(header::CONTENT_TYPE).unwrap(),
+            "application/problem+json"
+        );
+        let body: serde_json::Value =
+            serde_json::from_slice(&read_body(res).await).unwrap();
+        assert_eq!(body["status"], 422);
+        assert_eq!(body["errors"][0]["field"], "name");
+    }
+
+    #[test]
+    fn test_validation_error() {
+        let mut err = ValidationError::new();
+        assert!(err.is_empty());
+        err.add("name", "length", "is too long");
+        err.add("age", "range", "is too small");
+        assert_eq!(
+            err.to_string(),
+            "Validation failed: name is too long, age is too small"
+        );
+        let req = TestRequest::default().to_http_request();
+        let res = crate::web::WebResponseError::<DefaultError>::error_response(&err, &req);
+        assert_eq!(res.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(err.into_result().is_err());
+    }
+}
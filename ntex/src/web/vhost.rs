@@ -0,0 +1,353 @@
+use std::task::{Context, Poll};
+use std::{cmp::Reverse, future::Future, pin::Pin};
+
+use crate::http::Request;
+use crate::service::boxed::{self, BoxService, BoxServiceFactory};
+use crate::service::{IntoServiceFactory, Service, ServiceFactory};
+use crate::util::HashMap;
+
+use super::config::AppConfig;
+use super::error::{DefaultError, ErrorRenderer};
+use super::info::ConnectionInfo;
+use super::response::WebResponse;
+use super::App;
+
+type HttpService<Err: ErrorRenderer> = BoxService<Request, WebResponse, Err::Container>;
+type HttpNewService<Err: ErrorRenderer> =
+    BoxServiceFactory<AppConfig, Request, WebResponse, Err::Container, ()>;
+
+#[derive(Clone, PartialEq, Eq)]
+enum Host {
+    Exact(String),
+    Wildcard(String),
+}
+
+/// Virtual hosts router
+///
+/// Dispatches requests to independently built applications by request's
+/// host name. Host name is taken from `ConnectionInfo`, port is ignored.
+/// Exact host names take precedence over wildcard names, wildcard
+/// `*.example.com` matches any subdomain of `example.com` but not
+/// `example.com` itself, the longest wildcard wins. Requests for unknown
+/// hosts are handled by fallback application, empty application that
+/// responds with `404 Not Found` is used if fallback application is not set.
+///
+/// Router could be used as `HttpServer` factory.
+///
+/// ```rust,no_run
+/// use ntex::web::{self, App, HttpResponse, HttpServer, VirtualHosts};
+///
+/// #[ntex::main]
+/// async fn main() -> std::io::Result<()> {
+///     HttpServer::new(|| {
+///         VirtualHosts::new()
+///             .host("example.com", App::new()
+///                 .route("/", web::get().to(|| async { "example.com" })))
+///             .host("*.example.com", App::new()
+///                 .route("/", web::get().to(|| async { "subdomain" })))
+///             .fallback(App::new()
+///                 .default_service(web::to(|| async { HttpResponse::BadRequest() })))
+///     })
+///     .bind("127.0.0.1:8080")?
+///     .run()
+///     .await
+/// }
+/// ```
+pub struct VirtualHosts<Err: ErrorRenderer = DefaultError> {
+    hosts: Vec<(Host, HttpNewService<Err>)>,
+    fallback: HttpNewService<Err>,
+}
+
+impl VirtualHosts<DefaultError> {
+    /// Create virtual hosts router.
+    pub fn new() -> Self {
+        VirtualHosts::with_fallback(App::new())
+    }
+}
+
+impl Default for VirtualHosts<DefaultError> {
+    fn default() -> Self {
+        VirtualHosts::new()
+    }
+}
+
+impl<Err: ErrorRenderer> VirtualHosts<Err> {
+    /// Create virtual hosts router with custom error renderer.
+    pub fn with(err: Err) -> Self {
+        VirtualHosts::with_fallback(App::with(err))
+    }
+
+    fn with_fallback<T, U>(fallback: T) -> Self
+    where
+        T: IntoServiceFactory<U, Request, AppConfig>,
+        U: ServiceFactory<
+                Request,
+                AppConfig,
+                Response = WebResponse,
+                Error = Err::Container,
+                InitError = (),
+            > + 'static,
+    {
+        VirtualHosts {
+            hosts: Vec::new(),
+            fallback: boxed::factory(fallback.into_factory()),
+        }
+    }
+
+    /// Register application for host name.
+    ///
+    /// Host name is either exact name, `www.example.com`, or wildcard name,
+    /// `*.example.com`. Names are case insensitive. Application replaces
+    /// previously registered application for the same name.
+    pub fn host<T, U>(mut self, host: &str, app: T) -> Self
+    where
+        T: IntoServiceFactory<U, Request, AppConfig>,
+        U: ServiceFactory<
+                Request,
+                AppConfig,
+                Response = WebResponse,
+                Error = Err::Container,
+                InitError = (),
+            > + 'static,
+    {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        let host = if let Some(suffix) = host.strip_prefix("*.") {
+            Host::Wildcard(format!(".{}", suffix))
+        } else {
+            Host::Exact(host)
+        };
+        let factory = boxed::factory(app.into_factory());
+
+        if let Some(item) = self.hosts.iter_mut().find(|(h, _)| *h == host) {
+            item.1 = factory;
+        } else {
+            self.hosts.push((host, factory));
+        }
+        self
+    }
+
+    /// Set fallback application.
+    ///
+    /// Fallback application handles requests for unknown hosts.
+    pub fn fallback<T, U>(mut self, app: T) -> Self
+    where
+        T: IntoServiceFactory<U, Request, AppConfig>,
+        U: ServiceFactory<
+                Request,
+                AppConfig,
+                Response = WebResponse,
+                Error = Err::Container,
+                InitError = (),
+            > + 'static,
+    {
+        self.fallback = boxed::factory(app.into_factory());
+        self
+    }
+}
+
+impl<Err: ErrorRenderer> ServiceFactory<Request, AppConfig> for VirtualHosts<Err> {
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type InitError = ();
+    type Service = VirtualHostsService<Err>;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Service, Self::InitError>>>>;
+
+    fn new_service(&self, config: AppConfig) -> Self::Future {
+        let mut exact = HashMap::default();
+        let mut wildcard = Vec::new();
+        let mut futs = Vec::new();
+        for (idx, (host, factory)) in self.hosts.iter().enumerate() {
+            match host {
+                Host::Exact(host) => {
+                    exact.insert(host.clone(), idx);
+                }
+                Host::Wildcard(suffix) => wildcard.push((suffix.clone(), idx)),
+            }
+            futs.push(factory.new_service(config.clone()));
+        }
+        wildcard.sort_by_key(|(suffix, _)| Reverse(suffix.len()));
+        let fallback_fut = self.fallback.new_service(config.clone());
+
+        Box::pin(async move {
+            let mut services = Vec::with_capacity(futs.len());
+            for fut in futs {
+                services.push(fut.await?);
+            }
+
+            Ok(VirtualHostsService {
+                exact,
+                wildcard,
+                services,
+                config,
+                fallback: fallback_fut.await?,
+            })
+        })
+    }
+}
+
+/// Virtual hosts router service
+pub struct VirtualHostsService<Err: ErrorRenderer> {
+    exact: HashMap<String, usize>,
+    wildcard: Vec<(String, usize)>,
+    services: Vec<HttpService<Err>>,
+    fallback: HttpService<Err>,
+    config: AppConfig,
+}
+
+impl<Err: ErrorRenderer> VirtualHostsService<Err> {
+    fn find(&self, req: &Request) -> &HttpService<Err> {
+        let info = ConnectionInfo::get(req.head(), &self.config);
+        let host = normalize(info.host());
+
+        self.exact
+            .get(&host)
+            .or_else(|| {
+                self.wildcard
+                    .iter()
+                    .find(|(suffix, _)| {
+                        host.len() > suffix.len() && host.ends_with(suffix.as_str())
+                    })
+                    .map(|(_, idx)| idx)
+            })
+            .map(|idx| &self.services[*idx])
+            .unwrap_or(&self.fallback)
+    }
+}
+
+impl<Err: ErrorRenderer> Service<Request> for VirtualHostsService<Err> {
+    type Response = WebResponse;
+    type Error = Err::Container;
+    type Future = Pin<Box<dyn Future<Output = Result<WebResponse, Err::Container>>>>;
+
+    fn poll_ready(&self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        let mut ready = self.fallback.poll_ready(cx)?.is_ready();
+        for srv in &self.services {
+            ready = srv.poll_ready(cx)?.is_ready() && ready;
+        }
+        if ready {
+            Poll::Ready(Ok(()))
+        } else {
+            Poll::Pending
+        }
+    }
+
+    fn poll_shutdown(&self, cx: &mut Context<'_>, is_error: bool) -> Poll<()> {
+        let mut ready = self.fallback.poll_shutdown(cx, is_error).is_ready();
+        for srv in &self.services {
+            ready = srv.poll_shutdown(cx, is_error).is_ready() && ready;
+        }
+        if ready {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    }
+
+    #[inline]
+    fn call(&self, req: Request) -> Self::Future {
+        self.find(&req).call(req)
+    }
+}
+
+/// Lowercase host name without port and trailing dot
+fn normalize(host: &str) -> String {
+    let host = if host.starts_with('[') {
+        // ipv6 address
+        host.find(']').map(|pos| &host[..=pos]).unwrap_or(host)
+    } else {
+        host.split(':').next().unwrap_or(host)
+    };
+    host.trim_end_matches('.').to_ascii_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::http::{header, StatusCode};
+    use crate::util::Bytes;
+    use crate::web::test::{call_service, init_service, read_body, TestRequest};
+    use crate::web::{self, HttpResponse};
+
+    fn host(hosts: VirtualHosts, host: &str, name: &'static str) -> VirtualHosts {
+        hosts.host(
+            host,
+            App::new().route("/", web::get().to(move || async move { name })),
+        )
+    }
+
+    async fn request<S>(srv: &S, host: &str) -> (StatusCode, Bytes)
+    where
+        S: Service<Request, Response = WebResponse>,
+        S::Error: std::fmt::Debug,
+    {
+        let req = TestRequest::with_header(header::HOST, host).to_request();
+        let res = call_service(srv, req).await;
+        (res.status(), read_body(res).await)
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("Example.COM"), "example.com");
+        assert_eq!(normalize("example.com:8080"), "example.com");
+        assert_eq!(normalize("example.com."), "example.com");
+        assert_eq!(normalize("[::1]:8080"), "[::1]");
+        assert_eq!(normalize("127.0.0.1:80"), "127.0.0.1");
+    }
+
+    #[crate::rt_test]
+    async fn test_virtual_hosts() {
+        let hosts = host(VirtualHosts::new(), "example.com", "exact");
+        let hosts = host(hosts, "*.example.com", "wildcard");
+        let hosts = host(hosts, "*.API.example.com", "api");
+        let hosts = host(hosts, "www.example.com", "www");
+        let srv = init_service(hosts).await;
+
+        let (status, body) = request(&srv, "example.com").await;
+        assert_eq!(status, StatusCode::OK);
+        assert_eq!(body, Bytes::from_static(b"exact"));
+
+        let (_, body) = request(&srv, "Example.com:8080").await;
+        assert_eq!(body, Bytes::from_static(b"exact"));
+
+        let (_, body) = request(&srv, "www.example.com").await;
+        assert_eq!(body, Bytes::from_static(b"www"));
+
+        let (_, body) = request(&srv, "blog.example.com").await;
+        assert_eq!(body, Bytes::from_static(b"wildcard"));
+
+        let (_, body) = request(&srv, "v1.api.example.com").await;
+        assert_eq!(body, Bytes::from_static(b"api"));
+
+        let (_, body) = request(&srv, "api.example.com").await;
+        assert_eq!(body, Bytes::from_static(b"wildcard"));
+
+        let (status, _) = request(&srv, "example.org").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        let (status, _) = request(&srv, "badexample.com").await;
+        assert_eq!(status, StatusCode::NOT_FOUND);
+
+        // forwarded host
+        let req = TestRequest::with_header(header::HOST, "localhost")
+            .header("x-forwarded-host", "www.example.com")
+            .to_request();
+        let res = call_service(&srv, req).await;
+        assert_eq!(read_body(res).await, Bytes::from_static(b"www"));
+    }
+
+    #[crate::rt_test]
+    async fn test_default() {
+        let hosts = host(VirtualHosts::default(), "example.com", "first");
+        let hosts = host(hosts, "EXAMPLE.com.", "second");
+        let srv = init_service(hosts.fallback(
+            App::new().default_service(web::to(|| async { HttpResponse::BadRequest() })),
+        ))
+        .await;
+
+        let (_, body) = request(&srv, "example.com").await;
+        assert_eq!(body, Bytes::from_static(b"second"));
+
+        let (status, _) = request(&srv, "example.org").await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+    }
+}
@@ -556,6 +556,27 @@ async fn test_client_brotli_encoding() {
     assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
 }
 
+#[cfg(feature = "zstd")]
+#[ntex::test]
+async fn test_client_zstd_encoding() {
+    let srv = test::server(|| {
+        App::new().service(web::resource("/").route(web::to(|data: Bytes| async move {
+            let data = zstd_pkg::stream::encode_all(data.as_ref(), 3).unwrap();
+            HttpResponse::Ok()
+                .header("content-encoding", "zstd")
+                .body(data)
+        })))
+    });
+
+    // client request
+    let mut response = srv.post("/").send_body(STR).await.unwrap();
+    assert!(response.status().is_success());
+
+    // read response
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
 #[ntex::test]
 async fn test_client_brotli_encoding_large_random() {
     let data = rand::thread_rng()
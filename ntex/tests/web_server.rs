@@ -451,6 +451,59 @@ async fn test_body_brotli() {
     assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
 }
 
+#[cfg(feature = "zstd")]
+#[ntex::test]
+async fn test_body_zstd() {
+    let srv = test::server_with(test::config().h1(), || {
+        App::new().wrap(Compress::default()).service(
+            web::resource("/")
+                .route(web::to(move || async { HttpResponse::Ok().body(STR) })),
+        )
+    });
+
+    // client request
+    let mut response = srv
+        .get("/")
+        .header(ACCEPT_ENCODING, "gzip, deflate, zstd")
+        .no_decompress()
+        .send()
+        .await
+        .unwrap();
+    assert!(response.status().is_success());
+    assert_eq!(response.headers().get(CONTENT_ENCODING).unwrap(), "zstd");
+
+    // read response
+    let bytes = response.body().await.unwrap();
+
+    // decode zstd
+    let dec = zstd_pkg::stream::decode_all(bytes.as_ref()).unwrap();
+    assert_eq!(Bytes::from(dec), Bytes::from_static(STR.as_ref()));
+}
+
+#[cfg(feature = "zstd")]
+#[ntex::test]
+async fn test_zstd_encoding() {
+    let srv = test::server_with(test::config().h1(), || {
+        App::new().service(web::resource("/").route(web::to(move |body: Bytes| async {
+            HttpResponse::Ok().body(body)
+        })))
+    });
+
+    let enc = zstd_pkg::stream::encode_all(STR.as_bytes(), 3).unwrap();
+
+    // client request
+    let request = srv
+        .post("/")
+        .header(CONTENT_ENCODING, "zstd")
+        .send_body(enc);
+    let mut response = request.await.unwrap();
+    assert!(response.status().is_success());
+
+    // read response
+    let bytes = response.body().await.unwrap();
+    assert_eq!(bytes, Bytes::from_static(STR.as_ref()));
+}
+
 #[ntex::test]
 async fn test_encoding() {
     let srv = test::server_with(test::config().h1(), || {